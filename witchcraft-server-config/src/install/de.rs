@@ -11,7 +11,9 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use conjure_runtime_config::ServiceConfig;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -20,6 +22,7 @@ use std::time::Duration;
 pub struct InstallConfig {
     pub product_name: String,
     pub product_version: String,
+    pub deployment: Option<String>,
     pub port: u16,
     pub management_port: Option<u16>,
     pub keystore: Option<super::KeystoreConfig>,
@@ -27,6 +30,8 @@ pub struct InstallConfig {
     pub context_path: Option<String>,
     pub use_console_log: Option<bool>,
     pub server: Option<super::ServerConfig>,
+    pub logging: Option<super::LoggingConfig>,
+    pub metrics: Option<super::MetricsConfig>,
 }
 
 #[derive(Deserialize)]
@@ -57,4 +62,65 @@ pub struct ServerConfig {
     pub http2: Option<bool>,
     #[serde(default, with = "humantime_serde")]
     pub idle_connection_timeout: Option<Duration>,
+    pub trace_propagation: Option<Vec<super::TracePropagationFormat>>,
+    pub request_cancellation_endpoint: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MetricsConfig {
+    pub openmetrics_endpoint: Option<bool>,
+    pub reporter: Option<super::MetricsReporterConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MetricsReporterConfig {
+    #[serde(default, with = "humantime_serde")]
+    pub interval: Option<Duration>,
+    pub statsd: Option<super::StatsdReporterConfig>,
+    pub otlp: Option<super::OtlpReporterConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct StatsdReporterConfig {
+    pub address: String,
+    pub prefix: Option<String>,
+    pub max_packet_size: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct OtlpReporterConfig {
+    pub collector: ServiceConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LoggingConfig {
+    pub types: Option<HashMap<String, super::LogTypeConfig>>,
+    pub trace_exporter: Option<super::TraceExporterConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LogTypeConfig {
+    pub queue_size: Option<usize>,
+    #[serde(default, with = "humantime_serde")]
+    pub send_timeout: Option<Duration>,
+    pub max_file_size_bytes: Option<u64>,
+    pub total_size_cap_bytes: Option<u64>,
+    pub retention_days: Option<u32>,
+    pub compress: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TraceExporterConfig {
+    pub collector: ServiceConfig,
+    pub batch_size: Option<usize>,
+    pub queue_size: Option<usize>,
+    #[serde(default, with = "humantime_serde")]
+    pub flush_interval: Option<Duration>,
 }
@@ -13,9 +13,11 @@
 // limitations under the License.
 //! Fixed configuration.
 use crate::ConfigError;
+use conjure_runtime_config::ServiceConfig;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer};
 use staged_builder::{staged_builder, Validate};
+use std::collections::HashMap;
 use std::env;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
@@ -31,6 +33,8 @@ pub struct InstallConfig {
     product_name: String,
     #[builder(into)]
     product_version: String,
+    #[builder(default, into)]
+    deployment: Option<String>,
     port: u16,
     #[builder(default, into)]
     management_port: Option<u16>,
@@ -44,6 +48,10 @@ pub struct InstallConfig {
     use_console_log: bool,
     #[builder(default)]
     server: ServerConfig,
+    #[builder(default)]
+    logging: LoggingConfig,
+    #[builder(default)]
+    metrics: MetricsConfig,
 }
 
 impl Validate for InstallConfig {
@@ -72,6 +80,9 @@ impl<'de> Deserialize<'de> for InstallConfig {
             .product_name(raw.product_name)
             .product_version(raw.product_version)
             .port(raw.port);
+        if let Some(deployment) = raw.deployment {
+            builder = builder.deployment(deployment);
+        }
         if let Some(management_port) = raw.management_port {
             builder = builder.management_port(management_port);
         }
@@ -90,6 +101,12 @@ impl<'de> Deserialize<'de> for InstallConfig {
         if let Some(server) = raw.server {
             builder = builder.server(server);
         }
+        if let Some(logging) = raw.logging {
+            builder = builder.logging(logging);
+        }
+        if let Some(metrics) = raw.metrics {
+            builder = builder.metrics(metrics);
+        }
 
         builder.build().map_err(Error::custom)
     }
@@ -119,6 +136,14 @@ impl InstallConfig {
         &self.product_version
     }
 
+    /// Returns the name of the deployment the service is running in.
+    ///
+    /// This is used to attribute audit logs emitted by the server.
+    #[inline]
+    pub fn deployment(&self) -> Option<&str> {
+        self.deployment.as_deref()
+    }
+
     /// Returns the port the server will listen on.
     ///
     /// Required.
@@ -175,6 +200,18 @@ impl InstallConfig {
     pub fn server(&self) -> &ServerConfig {
         &self.server
     }
+
+    /// Returns the server's logging settings.
+    #[inline]
+    pub fn logging(&self) -> &LoggingConfig {
+        &self.logging
+    }
+
+    /// Returns the server's metrics settings.
+    #[inline]
+    pub fn metrics(&self) -> &MetricsConfig {
+        &self.metrics
+    }
 }
 
 /// TLS key configuration.
@@ -296,6 +333,10 @@ pub struct ServerConfig {
     http2: bool,
     #[builder(default, into)]
     idle_connection_timeout: Option<Duration>,
+    #[builder(default = vec![TracePropagationFormat::W3c, TracePropagationFormat::B3])]
+    trace_propagation: Vec<TracePropagationFormat>,
+    #[builder(default = false)]
+    request_cancellation_endpoint: bool,
 }
 
 impl Default for ServerConfig {
@@ -342,6 +383,12 @@ impl<'de> Deserialize<'de> for ServerConfig {
         if let Some(idle_connection_timeout) = raw.idle_connection_timeout {
             builder = builder.idle_connection_timeout(idle_connection_timeout);
         }
+        if let Some(trace_propagation) = raw.trace_propagation {
+            builder = builder.trace_propagation(trace_propagation);
+        }
+        if let Some(request_cancellation_endpoint) = raw.request_cancellation_endpoint {
+            builder = builder.request_cancellation_endpoint(request_cancellation_endpoint);
+        }
 
         Ok(builder.build())
     }
@@ -433,4 +480,525 @@ impl ServerConfig {
     pub fn idle_connection_timeout(&self) -> Option<Duration> {
         self.idle_connection_timeout
     }
+
+    /// Returns the formats used to propagate trace information in request and response headers.
+    ///
+    /// If a request contains trace information in multiple enabled formats, W3C Trace Context is preferred. If no
+    /// formats are enabled, every request will start a new trace.
+    ///
+    /// Defaults to `[w3c, b3]`.
+    #[inline]
+    pub fn trace_propagation(&self) -> &[TracePropagationFormat] {
+        &self.trace_propagation
+    }
+
+    /// Determines if in-flight requests can be cancelled by an administrator via the
+    /// `/debug/requests/{requestId}/cancel` endpoint.
+    ///
+    /// Requests to the endpoint must be authorized with the `diagnostics.debug-shared-secret` from the server's runtime
+    /// configuration.
+    ///
+    /// Defaults to `false`.
+    #[inline]
+    pub fn request_cancellation_endpoint(&self) -> bool {
+        self.request_cancellation_endpoint
+    }
+}
+
+/// A format used to propagate trace information in HTTP headers.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum TracePropagationFormat {
+    /// The [W3C Trace Context](https://www.w3.org/TR/trace-context/) `traceparent` and `tracestate` headers.
+    W3c,
+    /// The [Zipkin B3](https://github.com/openzipkin/b3-propagation) `X-B3-*` headers.
+    B3,
+}
+
+/// Metrics configuration.
+#[derive(Clone, PartialEq, Debug, Default)]
+#[staged_builder]
+pub struct MetricsConfig {
+    #[builder(default)]
+    openmetrics_endpoint: bool,
+    #[builder(default, into)]
+    reporter: Option<MetricsReporterConfig>,
+}
+
+impl<'de> Deserialize<'de> for MetricsConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::MetricsConfig::deserialize(deserializer)?;
+        let mut builder = MetricsConfig::builder();
+        if let Some(openmetrics_endpoint) = raw.openmetrics_endpoint {
+            builder = builder.openmetrics_endpoint(openmetrics_endpoint);
+        }
+        if let Some(reporter) = raw.reporter {
+            builder = builder.reporter(reporter);
+        }
+
+        Ok(builder.build())
+    }
+}
+
+impl MetricsConfig {
+    /// Determines if the server's metrics are exposed in the OpenMetrics text format at `/metrics`.
+    ///
+    /// The endpoint is served on the management port if one is configured. Requests must be authorized with the
+    /// runtime config's `diagnostics.metrics-shared-secret` if it is set.
+    ///
+    /// Defaults to `false`.
+    #[inline]
+    pub fn openmetrics_endpoint(&self) -> bool {
+        self.openmetrics_endpoint
+    }
+
+    /// Returns the configuration of the reporter periodically pushing the server's metrics to external systems.
+    ///
+    /// Defaults to `None`, which disables the reporter. Metrics are written to the metric log regardless.
+    #[inline]
+    pub fn reporter(&self) -> Option<&MetricsReporterConfig> {
+        self.reporter.as_ref()
+    }
+}
+
+/// Configuration for pushing metrics to external systems.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+#[builder(validate)]
+pub struct MetricsReporterConfig {
+    #[builder(default = Duration::from_secs(30))]
+    interval: Duration,
+    #[builder(default, into)]
+    statsd: Option<StatsdReporterConfig>,
+    #[builder(default, into)]
+    otlp: Option<OtlpReporterConfig>,
+}
+
+impl Validate for MetricsReporterConfig {
+    type Error = ConfigError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        if self.interval.is_zero() {
+            return Err(ConfigError("interval must be positive".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for MetricsReporterConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::MetricsReporterConfig::deserialize(deserializer)?;
+        let mut builder = MetricsReporterConfig::builder();
+        if let Some(interval) = raw.interval {
+            builder = builder.interval(interval);
+        }
+        if let Some(statsd) = raw.statsd {
+            builder = builder.statsd(statsd);
+        }
+        if let Some(otlp) = raw.otlp {
+            builder = builder.otlp(otlp);
+        }
+
+        builder.build().map_err(Error::custom)
+    }
+}
+
+impl MetricsReporterConfig {
+    /// Returns the interval between pushes.
+    ///
+    /// Defaults to 30 seconds.
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the configuration of the DogStatsD destination.
+    ///
+    /// Defaults to `None`.
+    #[inline]
+    pub fn statsd(&self) -> Option<&StatsdReporterConfig> {
+        self.statsd.as_ref()
+    }
+
+    /// Returns the configuration of the OTLP destination.
+    ///
+    /// Defaults to `None`.
+    #[inline]
+    pub fn otlp(&self) -> Option<&OtlpReporterConfig> {
+        self.otlp.as_ref()
+    }
+}
+
+/// Configuration for pushing metrics to a DogStatsD agent over UDP.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+#[builder(validate)]
+pub struct StatsdReporterConfig {
+    #[builder(into)]
+    address: String,
+    #[builder(default, into)]
+    prefix: Option<String>,
+    #[builder(default = 1432)]
+    max_packet_size: usize,
+}
+
+impl Validate for StatsdReporterConfig {
+    type Error = ConfigError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        if self.max_packet_size == 0 {
+            return Err(ConfigError("max-packet-size must be positive".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for StatsdReporterConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::StatsdReporterConfig::deserialize(deserializer)?;
+        let mut builder = StatsdReporterConfig::builder()
+            .address(raw.address)
+            .prefix(raw.prefix);
+        if let Some(max_packet_size) = raw.max_packet_size {
+            builder = builder.max_packet_size(max_packet_size);
+        }
+
+        builder.build().map_err(Error::custom)
+    }
+}
+
+impl StatsdReporterConfig {
+    /// Returns the `host:port` address of the agent.
+    ///
+    /// Required.
+    #[inline]
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Returns a prefix prepended to the names of all metrics, separated by a `.`.
+    ///
+    /// Defaults to `None`.
+    #[inline]
+    pub fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    /// Returns the maximum size of a single UDP packet in bytes. Multiple metrics are combined into one packet up to
+    /// this size.
+    ///
+    /// Defaults to 1432.
+    #[inline]
+    pub fn max_packet_size(&self) -> usize {
+        self.max_packet_size
+    }
+}
+
+/// Configuration for pushing metrics to an OpenTelemetry collector via OTLP/HTTP.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+pub struct OtlpReporterConfig {
+    collector: ServiceConfig,
+}
+
+impl<'de> Deserialize<'de> for OtlpReporterConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::OtlpReporterConfig::deserialize(deserializer)?;
+        Ok(OtlpReporterConfig::builder()
+            .collector(raw.collector)
+            .build())
+    }
+}
+
+impl OtlpReporterConfig {
+    /// Returns the configuration of the HTTP client used to communicate with the collector.
+    ///
+    /// Metrics are sent to the `/v1/metrics` path relative to the configured URIs.
+    ///
+    /// Required.
+    #[inline]
+    pub fn collector(&self) -> &ServiceConfig {
+        &self.collector
+    }
+}
+
+/// Logging configuration.
+#[derive(Clone, PartialEq, Debug, Default)]
+#[staged_builder]
+pub struct LoggingConfig {
+    #[builder(map(key(type = String, into), value(type = LogTypeConfig)))]
+    types: HashMap<String, LogTypeConfig>,
+    #[builder(default, into)]
+    trace_exporter: Option<TraceExporterConfig>,
+}
+
+impl<'de> Deserialize<'de> for LoggingConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::LoggingConfig::deserialize(deserializer)?;
+        let mut builder = LoggingConfig::builder();
+        if let Some(types) = raw.types {
+            builder = builder.types(types);
+        }
+        if let Some(trace_exporter) = raw.trace_exporter {
+            builder = builder.trace_exporter(trace_exporter);
+        }
+
+        Ok(builder.build())
+    }
+}
+
+impl LoggingConfig {
+    /// Returns settings for individual log types, keyed by the log type (e.g. `service.1` or `request.2`).
+    ///
+    /// Log types without an entry use the default settings.
+    #[inline]
+    pub fn types(&self) -> &HashMap<String, LogTypeConfig> {
+        &self.types
+    }
+
+    /// Returns the configuration of the OTLP trace exporter.
+    ///
+    /// Defaults to `None`, which disables the exporter. Spans are written to the trace log regardless.
+    #[inline]
+    pub fn trace_exporter(&self) -> Option<&TraceExporterConfig> {
+        self.trace_exporter.as_ref()
+    }
+}
+
+/// Configuration for exporting spans to an OpenTelemetry collector via OTLP/HTTP.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+#[builder(validate)]
+pub struct TraceExporterConfig {
+    collector: ServiceConfig,
+    #[builder(default = 512)]
+    batch_size: usize,
+    #[builder(default = 2048)]
+    queue_size: usize,
+    #[builder(default = Duration::from_secs(5))]
+    flush_interval: Duration,
+}
+
+impl Validate for TraceExporterConfig {
+    type Error = ConfigError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        if self.batch_size == 0 {
+            return Err(ConfigError("batch-size must be positive".to_string()));
+        }
+
+        if self.queue_size < self.batch_size {
+            return Err(ConfigError(
+                "queue-size must be at least batch-size".to_string(),
+            ));
+        }
+
+        if self.flush_interval.is_zero() {
+            return Err(ConfigError("flush-interval must be positive".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for TraceExporterConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::TraceExporterConfig::deserialize(deserializer)?;
+        let mut builder = TraceExporterConfig::builder().collector(raw.collector);
+        if let Some(batch_size) = raw.batch_size {
+            builder = builder.batch_size(batch_size);
+        }
+        if let Some(queue_size) = raw.queue_size {
+            builder = builder.queue_size(queue_size);
+        }
+        if let Some(flush_interval) = raw.flush_interval {
+            builder = builder.flush_interval(flush_interval);
+        }
+
+        builder.build().map_err(Error::custom)
+    }
+}
+
+impl TraceExporterConfig {
+    /// Returns the configuration of the HTTP client used to communicate with the collector.
+    ///
+    /// Spans are sent to the `/v1/traces` path relative to the configured URIs.
+    ///
+    /// Required.
+    #[inline]
+    pub fn collector(&self) -> &ServiceConfig {
+        &self.collector
+    }
+
+    /// Returns the maximum number of spans sent to the collector in a single request.
+    ///
+    /// Defaults to 512.
+    #[inline]
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Returns the maximum number of spans buffered while waiting to be sent. Spans reported while the buffer is full
+    /// are dropped.
+    ///
+    /// Defaults to 2048.
+    #[inline]
+    pub fn queue_size(&self) -> usize {
+        self.queue_size
+    }
+
+    /// Returns the maximum amount of time a span is buffered before being sent.
+    ///
+    /// Defaults to 5 seconds.
+    #[inline]
+    pub fn flush_interval(&self) -> Duration {
+        self.flush_interval
+    }
+}
+
+/// Configuration for a single log type.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+#[builder(validate)]
+pub struct LogTypeConfig {
+    #[builder(default = 10_000)]
+    queue_size: usize,
+    #[builder(default, into)]
+    send_timeout: Option<Duration>,
+    #[builder(default = 1024 * 1024 * 1024)]
+    max_file_size_bytes: u64,
+    #[builder(default, into)]
+    total_size_cap_bytes: Option<u64>,
+    #[builder(default, into)]
+    retention_days: Option<u32>,
+    #[builder(default = true)]
+    compress: bool,
+}
+
+impl Validate for LogTypeConfig {
+    type Error = ConfigError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        if self.queue_size == 0 {
+            return Err(ConfigError("queue-size must be positive".to_string()));
+        }
+
+        if self.max_file_size_bytes == 0 {
+            return Err(ConfigError(
+                "max-file-size-bytes must be positive".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for LogTypeConfig {
+    #[inline]
+    fn default() -> Self {
+        LogTypeConfig::builder().build().unwrap()
+    }
+}
+
+impl<'de> Deserialize<'de> for LogTypeConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::LogTypeConfig::deserialize(deserializer)?;
+        let mut builder = LogTypeConfig::builder();
+        if let Some(queue_size) = raw.queue_size {
+            builder = builder.queue_size(queue_size);
+        }
+        if let Some(send_timeout) = raw.send_timeout {
+            builder = builder.send_timeout(send_timeout);
+        }
+        if let Some(max_file_size_bytes) = raw.max_file_size_bytes {
+            builder = builder.max_file_size_bytes(max_file_size_bytes);
+        }
+        if let Some(total_size_cap_bytes) = raw.total_size_cap_bytes {
+            builder = builder.total_size_cap_bytes(total_size_cap_bytes);
+        }
+        if let Some(retention_days) = raw.retention_days {
+            builder = builder.retention_days(retention_days);
+        }
+        if let Some(compress) = raw.compress {
+            builder = builder.compress(compress);
+        }
+
+        builder.build().map_err(Error::custom)
+    }
+}
+
+impl LogTypeConfig {
+    /// Returns the maximum number of logs buffered in memory while waiting to be written.
+    ///
+    /// Defaults to 10,000.
+    #[inline]
+    pub fn queue_size(&self) -> usize {
+        self.queue_size
+    }
+
+    /// Returns the amount of time a thread emitting a log will wait for space in a full queue before dropping the log.
+    ///
+    /// If `None`, logs are dropped immediately when the queue is full.
+    ///
+    /// Defaults to `None`.
+    #[inline]
+    pub fn send_timeout(&self) -> Option<Duration> {
+        self.send_timeout
+    }
+
+    /// Returns the size in bytes at which the active log file is rotated into an archive.
+    ///
+    /// Defaults to 1 GiB.
+    #[inline]
+    pub fn max_file_size_bytes(&self) -> u64 {
+        self.max_file_size_bytes
+    }
+
+    /// Returns the maximum total size in bytes of archived log files, after which the oldest archives are deleted.
+    ///
+    /// Defaults to a limit specific to the log type.
+    #[inline]
+    pub fn total_size_cap_bytes(&self) -> Option<u64> {
+        self.total_size_cap_bytes
+    }
+
+    /// Returns the number of days archived log files are retained before being deleted.
+    ///
+    /// Defaults to a limit specific to the log type.
+    #[inline]
+    pub fn retention_days(&self) -> Option<u32> {
+        self.retention_days
+    }
+
+    /// Determines if archived log files are gzip compressed.
+    ///
+    /// Defaults to `true`.
+    #[inline]
+    pub fn compress(&self) -> bool {
+        self.compress
+    }
 }
@@ -13,6 +13,7 @@
 // limitations under the License.
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
 use witchcraft_log::LevelFilter;
 
 #[derive(Deserialize)]
@@ -28,12 +29,24 @@ pub struct RuntimeConfig {
 #[serde(rename_all = "kebab-case")]
 pub struct DiagnosticsConfig {
     pub debug_shared_secret: String,
+    pub metrics_shared_secret: Option<String>,
+    pub heap_profiling_active: Option<bool>,
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct HealthChecksConfig {
     pub shared_secret: String,
+    pub overrides: Option<HashMap<String, super::HealthCheckOverrideConfig>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthCheckOverrideConfig {
+    pub disabled: Option<bool>,
+    pub max_state: Option<super::HealthCheckState>,
+    #[serde(default, with = "humantime_serde")]
+    pub muted_until: Option<SystemTime>,
 }
 
 #[derive(Deserialize)]
@@ -42,4 +55,85 @@ pub struct LoggingConfig {
     pub level: Option<LevelFilter>,
     pub loggers: Option<HashMap<String, LevelFilter>>,
     pub trace_rate: Option<f32>,
+    pub level_overrides: Option<Vec<super::LevelOverrideConfig>>,
+    pub rate_limit: Option<super::LogRateLimitConfig>,
+    pub redaction: Option<super::RedactionConfig>,
+    pub trace_sampling: Option<super::TraceSamplingConfig>,
+    pub metrics: Option<super::MetricLoggingConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LevelOverrideConfig {
+    pub level: LevelFilter,
+    pub trace_id: Option<String>,
+    pub user_id: Option<String>,
+    pub endpoint: Option<String>,
+    pub debug_header_secret: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LogRateLimitConfig {
+    pub max_logs: Option<u64>,
+    #[serde(default, with = "humantime_serde")]
+    pub interval: Option<Duration>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RedactionConfig {
+    pub test_mode: Option<bool>,
+    pub rules: Option<Vec<super::RedactionRuleConfig>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RedactionRuleConfig {
+    pub name: String,
+    pub key_pattern: Option<String>,
+    pub value_pattern: Option<String>,
+    pub action: super::RedactionAction,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MetricLoggingConfig {
+    #[serde(default, with = "humantime_serde")]
+    pub interval: Option<Duration>,
+    pub filters: Option<Vec<super::MetricFilterConfig>>,
+    pub skip_unchanged: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MetricFilterConfig {
+    pub action: super::MetricFilterAction,
+    pub name_prefix: Option<String>,
+    pub tags: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TraceSamplingConfig {
+    pub rules: Option<Vec<super::TraceSamplingRuleConfig>>,
+    pub tail: Option<super::TailSamplingConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TraceSamplingRuleConfig {
+    pub service: Option<String>,
+    pub endpoint: Option<String>,
+    pub rate: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TailSamplingConfig {
+    pub enabled: Option<bool>,
+    pub errors: Option<bool>,
+    #[serde(default, with = "humantime_serde")]
+    pub latency_threshold: Option<Duration>,
+    pub max_pending_traces: Option<usize>,
 }
@@ -14,10 +14,12 @@
 //! Runtime-reloadable configuration.
 use crate::ConfigError;
 use conjure_runtime_config::ServicesConfig;
+use regex::Regex;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer};
 use staged_builder::{staged_builder, Validate};
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
 use witchcraft_log::LevelFilter;
 
 mod de;
@@ -97,6 +99,10 @@ impl RuntimeConfig {
 pub struct DiagnosticsConfig {
     #[builder(into)]
     debug_shared_secret: String,
+    #[builder(default, into)]
+    metrics_shared_secret: Option<String>,
+    #[builder(default, into)]
+    heap_profiling_active: Option<bool>,
 }
 
 impl<'de> Deserialize<'de> for DiagnosticsConfig {
@@ -105,7 +111,10 @@ impl<'de> Deserialize<'de> for DiagnosticsConfig {
         D: Deserializer<'de>,
     {
         let raw = de::DiagnosticsConfig::deserialize(deserializer)?;
-        let builder = DiagnosticsConfig::builder().debug_shared_secret(raw.debug_shared_secret);
+        let builder = DiagnosticsConfig::builder()
+            .debug_shared_secret(raw.debug_shared_secret)
+            .metrics_shared_secret(raw.metrics_shared_secret)
+            .heap_profiling_active(raw.heap_profiling_active);
 
         Ok(builder.build())
     }
@@ -119,6 +128,25 @@ impl DiagnosticsConfig {
     pub fn debug_shared_secret(&self) -> &str {
         &self.debug_shared_secret
     }
+
+    /// Returns the shared secret used to authorize requests to the server's `/metrics` endpoint.
+    ///
+    /// Defaults to `None`, which allows unauthenticated requests.
+    #[inline]
+    pub fn metrics_shared_secret(&self) -> Option<&str> {
+        self.metrics_shared_secret.as_deref()
+    }
+
+    /// Returns whether jemalloc heap profiling samples allocations.
+    ///
+    /// This only has an effect if the process was started with heap profiling enabled (e.g. by setting the
+    /// `MALLOC_CONF` environment variable to `prof:true,prof_active:false`).
+    ///
+    /// Defaults to `None`, which leaves the value configured at startup unchanged.
+    #[inline]
+    pub fn heap_profiling_active(&self) -> Option<bool> {
+        self.heap_profiling_active
+    }
 }
 
 /// Health checks configuration.
@@ -127,6 +155,8 @@ impl DiagnosticsConfig {
 pub struct HealthChecksConfig {
     #[builder(into)]
     shared_secret: String,
+    #[builder(map(key(type = String, into), value(type = HealthCheckOverrideConfig)))]
+    overrides: HashMap<String, HealthCheckOverrideConfig>,
 }
 
 impl<'de> Deserialize<'de> for HealthChecksConfig {
@@ -135,7 +165,10 @@ impl<'de> Deserialize<'de> for HealthChecksConfig {
         D: Deserializer<'de>,
     {
         let raw = de::HealthChecksConfig::deserialize(deserializer)?;
-        let builder = HealthChecksConfig::builder().shared_secret(raw.shared_secret);
+        let mut builder = HealthChecksConfig::builder().shared_secret(raw.shared_secret);
+        if let Some(overrides) = raw.overrides {
+            builder = builder.overrides(overrides);
+        }
 
         Ok(builder.build())
     }
@@ -147,6 +180,95 @@ impl HealthChecksConfig {
     pub fn shared_secret(&self) -> &str {
         &self.shared_secret
     }
+
+    /// Returns overrides applied to the results of health checks, keyed by check type.
+    #[inline]
+    pub fn overrides(&self) -> &HashMap<String, HealthCheckOverrideConfig> {
+        &self.overrides
+    }
+}
+
+/// An override applied to the result of a health check.
+///
+/// Overrides are intended to silence misbehaving checks during an incident. An overridden check is still reported, with
+/// its original state included in its parameters.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+pub struct HealthCheckOverrideConfig {
+    #[builder(default = false)]
+    disabled: bool,
+    #[builder(default, into)]
+    max_state: Option<HealthCheckState>,
+    #[builder(default, into)]
+    muted_until: Option<SystemTime>,
+}
+
+impl<'de> Deserialize<'de> for HealthCheckOverrideConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::HealthCheckOverrideConfig::deserialize(deserializer)?;
+        let mut builder = HealthCheckOverrideConfig::builder();
+        if let Some(disabled) = raw.disabled {
+            builder = builder.disabled(disabled);
+        }
+        if let Some(max_state) = raw.max_state {
+            builder = builder.max_state(max_state);
+        }
+        if let Some(muted_until) = raw.muted_until {
+            builder = builder.muted_until(muted_until);
+        }
+
+        Ok(builder.build())
+    }
+}
+
+impl HealthCheckOverrideConfig {
+    /// If true, the check is always reported as healthy.
+    ///
+    /// Defaults to `false`.
+    #[inline]
+    pub fn disabled(&self) -> bool {
+        self.disabled
+    }
+
+    /// Returns the most severe state the check will be reported with.
+    ///
+    /// More severe states will be downgraded to this state. Defaults to `None`.
+    #[inline]
+    pub fn max_state(&self) -> Option<HealthCheckState> {
+        self.max_state
+    }
+
+    /// Returns the time until which the check is reported as healthy.
+    ///
+    /// Configured as an RFC 3339 timestamp, for example `2024-01-01T00:00:00Z`. Defaults to `None`.
+    #[inline]
+    pub fn muted_until(&self) -> Option<SystemTime> {
+        self.muted_until
+    }
+}
+
+/// The state of a health check, in increasing order of severity.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum HealthCheckState {
+    /// The service is fully operational.
+    Healthy,
+    /// The service is requesting to defer shutdown or restart.
+    Deferring,
+    /// The service is no longer serving requests and is ready to be shut down.
+    Suspended,
+    /// The service is degraded, but is capable of automatically recovering.
+    Repairing,
+    /// The service is trending towards an error.
+    Warning,
+    /// The service is operationally unhealthy.
+    Error,
+    /// The service has entered an unrecoverable state.
+    Terminal,
 }
 
 /// Logging configuration.
@@ -160,6 +282,16 @@ pub struct LoggingConfig {
     loggers: HashMap<String, LevelFilter>,
     #[builder(default = 0.0005)]
     trace_rate: f32,
+    #[builder(list(item(type = LevelOverrideConfig)))]
+    level_overrides: Vec<LevelOverrideConfig>,
+    #[builder(default, into)]
+    rate_limit: Option<LogRateLimitConfig>,
+    #[builder(default)]
+    redaction: RedactionConfig,
+    #[builder(default)]
+    trace_sampling: TraceSamplingConfig,
+    #[builder(default)]
+    metrics: MetricLoggingConfig,
 }
 
 impl Validate for LoggingConfig {
@@ -192,6 +324,21 @@ impl<'de> Deserialize<'de> for LoggingConfig {
         if let Some(trace_rate) = raw.trace_rate {
             builder = builder.trace_rate(trace_rate);
         }
+        if let Some(level_overrides) = raw.level_overrides {
+            builder = builder.level_overrides(level_overrides);
+        }
+        if let Some(rate_limit) = raw.rate_limit {
+            builder = builder.rate_limit(rate_limit);
+        }
+        if let Some(redaction) = raw.redaction {
+            builder = builder.redaction(redaction);
+        }
+        if let Some(trace_sampling) = raw.trace_sampling {
+            builder = builder.trace_sampling(trace_sampling);
+        }
+        if let Some(metrics) = raw.metrics {
+            builder = builder.metrics(metrics);
+        }
 
         builder.build().map_err(Error::custom)
     }
@@ -222,11 +369,703 @@ impl LoggingConfig {
     /// Returns the rate at which new traces will be sampled between 0 and 1, inclusive.
     ///
     /// This only applies to fresh traces - Witchcraft will respect sampling decisions made by upstream services for a
-    /// given request.
+    /// given request. It can be overridden for specific endpoints by [`TraceSamplingConfig::rules`].
     ///
     /// Defaults to 0.05%.
     #[inline]
     pub fn trace_rate(&self) -> f32 {
         self.trace_rate
     }
+
+    /// Returns rules which raise the logging verbosity for individual requests.
+    ///
+    /// A service log emitted while handling a request is enabled if it passes either the global and per-target
+    /// filters or the level of any override matching the request.
+    #[inline]
+    pub fn level_overrides(&self) -> &[LevelOverrideConfig] {
+        &self.level_overrides
+    }
+
+    /// Returns the rate limit applied to repeated service logs.
+    ///
+    /// Defaults to `None`, which disables rate limiting.
+    #[inline]
+    pub fn rate_limit(&self) -> Option<&LogRateLimitConfig> {
+        self.rate_limit.as_ref()
+    }
+
+    /// Returns the rules used to redact unsafe parameters from logs.
+    #[inline]
+    pub fn redaction(&self) -> &RedactionConfig {
+        &self.redaction
+    }
+
+    /// Returns advanced trace sampling configuration.
+    #[inline]
+    pub fn trace_sampling(&self) -> &TraceSamplingConfig {
+        &self.trace_sampling
+    }
+
+    /// Returns configuration for the metric log.
+    #[inline]
+    pub fn metrics(&self) -> &MetricLoggingConfig {
+        &self.metrics
+    }
+}
+
+/// Service log rate limiting configuration.
+///
+/// Logs are grouped by their origin and message template. Once a group has emitted `max_logs` logs within an
+/// interval, further logs in that group are dropped until the interval ends, at which point a single summary log
+/// recording the number of suppressed logs is emitted.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+#[builder(validate)]
+pub struct LogRateLimitConfig {
+    #[builder(default = 100)]
+    max_logs: u64,
+    #[builder(default = Duration::from_secs(60))]
+    interval: Duration,
+}
+
+impl Validate for LogRateLimitConfig {
+    type Error = ConfigError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        if self.interval.is_zero() {
+            return Err(ConfigError(
+                "rate-limit interval must be positive".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for LogRateLimitConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::LogRateLimitConfig::deserialize(deserializer)?;
+        let mut builder = LogRateLimitConfig::builder();
+        if let Some(max_logs) = raw.max_logs {
+            builder = builder.max_logs(max_logs);
+        }
+        if let Some(interval) = raw.interval {
+            builder = builder.interval(interval);
+        }
+
+        builder.build().map_err(Error::custom)
+    }
+}
+
+impl LogRateLimitConfig {
+    /// Returns the maximum number of logs with the same origin and message emitted per interval.
+    ///
+    /// Defaults to 100.
+    #[inline]
+    pub fn max_logs(&self) -> u64 {
+        self.max_logs
+    }
+
+    /// Returns the length of the rate limiting interval.
+    ///
+    /// Defaults to 1 minute.
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+/// A rule raising the logging verbosity for requests matching all of its criteria.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+#[builder(validate)]
+pub struct LevelOverrideConfig {
+    level: LevelFilter,
+    #[builder(default, into)]
+    trace_id: Option<String>,
+    #[builder(default, into)]
+    user_id: Option<String>,
+    #[builder(default, into)]
+    endpoint: Option<String>,
+    #[builder(default, into)]
+    debug_header_secret: Option<String>,
+}
+
+impl Validate for LevelOverrideConfig {
+    type Error = ConfigError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        if self.trace_id.is_none()
+            && self.user_id.is_none()
+            && self.endpoint.is_none()
+            && self.debug_header_secret.is_none()
+        {
+            return Err(ConfigError(
+                "level overrides must specify at least one of trace-id, user-id, endpoint, or debug-header-secret"
+                    .to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for LevelOverrideConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::LevelOverrideConfig::deserialize(deserializer)?;
+        let builder = LevelOverrideConfig::builder()
+            .level(raw.level)
+            .trace_id(raw.trace_id)
+            .user_id(raw.user_id)
+            .endpoint(raw.endpoint)
+            .debug_header_secret(raw.debug_header_secret);
+
+        builder.build().map_err(Error::custom)
+    }
+}
+
+impl LevelOverrideConfig {
+    /// Returns the verbosity filter applied to service logs emitted by matching requests.
+    ///
+    /// Required.
+    #[inline]
+    pub fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    /// If set, the override only applies to requests with this trace ID.
+    #[inline]
+    pub fn trace_id(&self) -> Option<&str> {
+        self.trace_id.as_deref()
+    }
+
+    /// If set, the override only applies to requests made by this user, as identified by the request's
+    /// (unverified) auth token.
+    #[inline]
+    pub fn user_id(&self) -> Option<&str> {
+        self.user_id.as_deref()
+    }
+
+    /// If set, the override only applies to requests to the endpoint with this name.
+    #[inline]
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    /// If set, the override only applies to requests with a valid `X-Witchcraft-Debug` header signed with this secret.
+    ///
+    /// The header's value has the form `{expiration}.{signature}`, where `expiration` is the Unix timestamp in seconds
+    /// after which the header is no longer valid and `signature` is the unpadded URL-safe base64 encoding of the
+    /// HMAC-SHA256 of `expiration` keyed by the secret.
+    #[inline]
+    pub fn debug_header_secret(&self) -> Option<&str> {
+        self.debug_header_secret.as_deref()
+    }
+}
+
+/// Redaction configuration.
+///
+/// Redaction rules are applied to the unsafe parameters of service and request logs, the unsafe messages and stacktraces
+/// of service logs, and the request and result fields and parameters of audit logs, before they are written. Messages
+/// and stacktraces are matched under the `message` and `stacktrace` keys.
+#[derive(Clone, PartialEq, Debug, Default)]
+#[staged_builder]
+pub struct RedactionConfig {
+    #[builder(default)]
+    test_mode: bool,
+    #[builder(list(item(type = RedactionRuleConfig)))]
+    rules: Vec<RedactionRuleConfig>,
+}
+
+impl<'de> Deserialize<'de> for RedactionConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::RedactionConfig::deserialize(deserializer)?;
+        let mut builder = RedactionConfig::builder();
+        if let Some(test_mode) = raw.test_mode {
+            builder = builder.test_mode(test_mode);
+        }
+        if let Some(rules) = raw.rules {
+            builder = builder.rules(rules);
+        }
+
+        Ok(builder.build())
+    }
+}
+
+impl RedactionConfig {
+    /// If `true`, rules are evaluated but not applied.
+    ///
+    /// Instead, the names of the rules which would have modified a service or request log are added to its
+    /// `redactionRulesFired` parameter.
+    ///
+    /// Defaults to `false`.
+    #[inline]
+    pub fn test_mode(&self) -> bool {
+        self.test_mode
+    }
+
+    /// Returns the redaction rules, which are applied in order.
+    #[inline]
+    pub fn rules(&self) -> &[RedactionRuleConfig] {
+        &self.rules
+    }
+}
+
+/// A rule redacting log parameters.
+///
+/// A rule with only a key pattern applies its action to the entire value of each parameter with a matching name. A rule
+/// with a value pattern applies its action to each match of the pattern within the string components of parameter
+/// values, optionally restricted to parameters with names matching the key pattern.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+#[builder(validate)]
+pub struct RedactionRuleConfig {
+    #[builder(into)]
+    name: String,
+    #[builder(default, into)]
+    key_pattern: Option<String>,
+    #[builder(default, into)]
+    value_pattern: Option<String>,
+    action: RedactionAction,
+}
+
+impl Validate for RedactionRuleConfig {
+    type Error = ConfigError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        if self.key_pattern.is_none() && self.value_pattern.is_none() {
+            return Err(ConfigError(format!(
+                "redaction rule {} must have a key-pattern or value-pattern",
+                self.name,
+            )));
+        }
+
+        for pattern in self.key_pattern.iter().chain(&self.value_pattern) {
+            if let Err(e) = Regex::new(pattern) {
+                return Err(ConfigError(format!(
+                    "redaction rule {} has an invalid pattern: {e}",
+                    self.name,
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for RedactionRuleConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::RedactionRuleConfig::deserialize(deserializer)?;
+        let builder = RedactionRuleConfig::builder()
+            .name(raw.name)
+            .action(raw.action)
+            .key_pattern(raw.key_pattern)
+            .value_pattern(raw.value_pattern);
+
+        builder.build().map_err(Error::custom)
+    }
+}
+
+impl RedactionRuleConfig {
+    /// Returns the name of the rule, used in metrics and test mode reports.
+    ///
+    /// Required.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns a regex matched against parameter names.
+    #[inline]
+    pub fn key_pattern(&self) -> Option<&str> {
+        self.key_pattern.as_deref()
+    }
+
+    /// Returns a regex matched against string components of parameter values.
+    #[inline]
+    pub fn value_pattern(&self) -> Option<&str> {
+        self.value_pattern.as_deref()
+    }
+
+    /// Returns the action taken when the rule matches.
+    ///
+    /// Required.
+    #[inline]
+    pub fn action(&self) -> RedactionAction {
+        self.action
+    }
+}
+
+/// An action taken by a redaction rule.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum RedactionAction {
+    /// Removes the parameter entirely.
+    Drop,
+    /// Replaces the matched text with the hex-encoded SHA-256 hash of the text.
+    Hash,
+    /// Replaces the matched text with a fixed placeholder.
+    Mask,
+}
+
+/// Metric log configuration.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+#[builder(validate)]
+pub struct MetricLoggingConfig {
+    #[builder(default = Duration::from_secs(30))]
+    interval: Duration,
+    #[builder(list(item(type = MetricFilterConfig)))]
+    filters: Vec<MetricFilterConfig>,
+    #[builder(default = false)]
+    skip_unchanged: bool,
+}
+
+impl Default for MetricLoggingConfig {
+    fn default() -> Self {
+        MetricLoggingConfig::builder().build().unwrap()
+    }
+}
+
+impl Validate for MetricLoggingConfig {
+    type Error = ConfigError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        if self.interval.is_zero() {
+            return Err(ConfigError(
+                "metric log interval must be positive".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for MetricLoggingConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::MetricLoggingConfig::deserialize(deserializer)?;
+        let mut builder = MetricLoggingConfig::builder();
+        if let Some(interval) = raw.interval {
+            builder = builder.interval(interval);
+        }
+        if let Some(filters) = raw.filters {
+            builder = builder.filters(filters);
+        }
+        if let Some(skip_unchanged) = raw.skip_unchanged {
+            builder = builder.skip_unchanged(skip_unchanged);
+        }
+
+        builder.build().map_err(Error::custom)
+    }
+}
+
+impl MetricLoggingConfig {
+    /// Returns the interval between metric reports.
+    ///
+    /// Defaults to 30 seconds.
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns filters determining which metrics are logged.
+    ///
+    /// The first matching filter applies. Metrics matching no filter are logged.
+    #[inline]
+    pub fn filters(&self) -> &[MetricFilterConfig] {
+        &self.filters
+    }
+
+    /// If true, metrics which have not changed since they were last logged are skipped.
+    ///
+    /// Gauges are unchanged if their value is the same, and all other metrics if their count is the same.
+    ///
+    /// Defaults to `false`.
+    #[inline]
+    pub fn skip_unchanged(&self) -> bool {
+        self.skip_unchanged
+    }
+}
+
+/// A filter determining if metrics matching all of its criteria are logged.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+pub struct MetricFilterConfig {
+    action: MetricFilterAction,
+    #[builder(default, into)]
+    name_prefix: Option<String>,
+    #[builder(map(key(type = String, into), value(type = String, into)))]
+    tags: HashMap<String, String>,
+}
+
+impl<'de> Deserialize<'de> for MetricFilterConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::MetricFilterConfig::deserialize(deserializer)?;
+        let mut builder = MetricFilterConfig::builder()
+            .action(raw.action)
+            .name_prefix(raw.name_prefix);
+        if let Some(tags) = raw.tags {
+            builder = builder.tags(tags);
+        }
+
+        Ok(builder.build())
+    }
+}
+
+impl MetricFilterConfig {
+    /// Returns the action taken for matching metrics.
+    ///
+    /// Required.
+    #[inline]
+    pub fn action(&self) -> MetricFilterAction {
+        self.action
+    }
+
+    /// If set, the filter only applies to metrics with names starting with this prefix.
+    #[inline]
+    pub fn name_prefix(&self) -> Option<&str> {
+        self.name_prefix.as_deref()
+    }
+
+    /// Returns tags which must all be present with the specified values on metrics the filter applies to.
+    #[inline]
+    pub fn tags(&self) -> &HashMap<String, String> {
+        &self.tags
+    }
+}
+
+/// An action taken by a metric filter.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum MetricFilterAction {
+    /// Logs the metric.
+    Allow,
+    /// Skips the metric.
+    Deny,
+}
+
+/// Advanced trace sampling configuration.
+#[derive(Clone, PartialEq, Debug, Default)]
+#[staged_builder]
+pub struct TraceSamplingConfig {
+    #[builder(list(item(type = TraceSamplingRuleConfig)))]
+    rules: Vec<TraceSamplingRuleConfig>,
+    #[builder(default)]
+    tail: TailSamplingConfig,
+}
+
+impl<'de> Deserialize<'de> for TraceSamplingConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::TraceSamplingConfig::deserialize(deserializer)?;
+        let mut builder = TraceSamplingConfig::builder();
+        if let Some(rules) = raw.rules {
+            builder = builder.rules(rules);
+        }
+        if let Some(tail) = raw.tail {
+            builder = builder.tail(tail);
+        }
+
+        Ok(builder.build())
+    }
+}
+
+impl TraceSamplingConfig {
+    /// Returns rules overriding the sampling rate of fresh traces for requests to specific endpoints.
+    ///
+    /// The first matching rule applies. Requests matching no rule are sampled at [`LoggingConfig::trace_rate`].
+    #[inline]
+    pub fn rules(&self) -> &[TraceSamplingRuleConfig] {
+        &self.rules
+    }
+
+    /// Returns tail-based sampling configuration.
+    #[inline]
+    pub fn tail(&self) -> &TailSamplingConfig {
+        &self.tail
+    }
+}
+
+/// A rule setting the sampling rate of fresh traces for requests matching all of its criteria.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+#[builder(validate)]
+pub struct TraceSamplingRuleConfig {
+    #[builder(default, into)]
+    service: Option<String>,
+    #[builder(default, into)]
+    endpoint: Option<String>,
+    rate: f32,
+}
+
+impl Validate for TraceSamplingRuleConfig {
+    type Error = ConfigError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        if !(0.0..=1.0).contains(&self.rate) {
+            return Err(ConfigError(
+                "trace sampling rule rate must be between 0 and 1, inclusive".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for TraceSamplingRuleConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::TraceSamplingRuleConfig::deserialize(deserializer)?;
+        let builder = TraceSamplingRuleConfig::builder()
+            .rate(raw.rate)
+            .service(raw.service)
+            .endpoint(raw.endpoint);
+
+        builder.build().map_err(Error::custom)
+    }
+}
+
+impl TraceSamplingRuleConfig {
+    /// If set, the rule only applies to requests to endpoints of the service with this name.
+    #[inline]
+    pub fn service(&self) -> Option<&str> {
+        self.service.as_deref()
+    }
+
+    /// If set, the rule only applies to requests to the endpoint with this name.
+    #[inline]
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    /// Returns the rate at which matching fresh traces will be sampled between 0 and 1, inclusive.
+    ///
+    /// Required.
+    #[inline]
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+}
+
+/// Tail-based sampling configuration.
+///
+/// When enabled, the spans the server creates for requests which were not selected by head-based sampling are buffered
+/// until the request completes. They are then reported if the request failed with a server error or exceeded the
+/// latency threshold, and discarded otherwise. The deferred decision is local to this service, so these requests are
+/// propagated to downstream services as unsampled. Spans created directly through `zipkin` rather than `tracing`,
+/// including those of outgoing requests, are unsampled as well and are not buffered.
+#[derive(Clone, PartialEq, Debug)]
+#[staged_builder]
+#[builder(validate)]
+pub struct TailSamplingConfig {
+    #[builder(default = false)]
+    enabled: bool,
+    #[builder(default = true)]
+    errors: bool,
+    #[builder(default, into)]
+    latency_threshold: Option<Duration>,
+    #[builder(default = 1_000)]
+    max_pending_traces: usize,
+}
+
+impl Validate for TailSamplingConfig {
+    type Error = ConfigError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        if self.max_pending_traces == 0 {
+            return Err(ConfigError(
+                "tail sampling max-pending-traces must be positive".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for TailSamplingConfig {
+    #[inline]
+    fn default() -> Self {
+        TailSamplingConfig::builder().build().unwrap()
+    }
+}
+
+impl<'de> Deserialize<'de> for TailSamplingConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = de::TailSamplingConfig::deserialize(deserializer)?;
+        let mut builder = TailSamplingConfig::builder().latency_threshold(raw.latency_threshold);
+        if let Some(enabled) = raw.enabled {
+            builder = builder.enabled(enabled);
+        }
+        if let Some(errors) = raw.errors {
+            builder = builder.errors(errors);
+        }
+        if let Some(max_pending_traces) = raw.max_pending_traces {
+            builder = builder.max_pending_traces(max_pending_traces);
+        }
+
+        builder.build().map_err(Error::custom)
+    }
+}
+
+impl TailSamplingConfig {
+    /// Determines if tail-based sampling is enabled.
+    ///
+    /// Defaults to `false`.
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Determines if requests failing with a 5xx status code are sampled.
+    ///
+    /// Defaults to `true`.
+    #[inline]
+    pub fn errors(&self) -> bool {
+        self.errors
+    }
+
+    /// Returns the duration after which requests are sampled.
+    ///
+    /// Defaults to `None`, which disables latency-based sampling.
+    #[inline]
+    pub fn latency_threshold(&self) -> Option<Duration> {
+        self.latency_threshold
+    }
+
+    /// Returns the maximum number of incomplete traces whose spans are buffered at once.
+    ///
+    /// The oldest trace is discarded when the limit is exceeded.
+    ///
+    /// Defaults to 1,000.
+    #[inline]
+    pub fn max_pending_traces(&self) -> usize {
+        self.max_pending_traces
+    }
 }
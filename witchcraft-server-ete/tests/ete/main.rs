@@ -13,16 +13,19 @@
 // limitations under the License.
 use bytes::Bytes;
 use conjure_object::Any;
+use conjure_serde::json;
 use http::{HeaderMap, HeaderValue};
 use http_body_util::{BodyExt, Empty, Full};
 use hyper::body::{Body, Frame};
 use hyper::{Request, StatusCode};
 use server::Server;
+use std::collections::BTreeMap;
 use std::pin::Pin;
 use std::str;
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 use tokio::time;
+use witchcraft_server::logging::api::AuditResult;
 
 mod server;
 
@@ -284,6 +287,110 @@ async fn diagnostic_types_diagnostic() {
     .await;
 }
 
+#[tokio::test]
+async fn diagnostic_types_detailed() {
+    Server::with(|server| async move {
+        let request = Request::builder()
+            .uri("/witchcraft-ete/debug/diagnostic/diagnostic.types.v1?detailed=true")
+            .header("Authorization", "Bearer debug")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = str::from_utf8(&body).unwrap();
+        assert!(body.contains(r#"{"type":"metric.names.v1","params":[{"name":"prefix","#));
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn diagnostic_unsupported_param() {
+    Server::with(|server| async move {
+        let request = Request::builder()
+            .uri("/witchcraft-ete/debug/diagnostic/diagnostic.types.v1?bogus=true")
+            .header("Authorization", "Bearer debug")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn openmetrics_bad_auth() {
+    Server::with(|server| async move {
+        let request = Request::builder()
+            .uri("/witchcraft-ete/metrics")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn openmetrics() {
+    Server::with(|server| async move {
+        let request = Request::builder()
+            .uri("/witchcraft-ete/metrics")
+            .header("Authorization", "Bearer metrics")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("Content-Type").unwrap(),
+            "application/openmetrics-text; version=1.0.0; charset=utf-8"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = str::from_utf8(&body).unwrap();
+        assert!(body.contains("# TYPE process_uptime gauge\n"));
+        assert!(body.ends_with("# EOF\n"));
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
 #[tokio::test]
 #[cfg(target_os = "linux")]
 async fn thread_dump_diagnostic() {
@@ -324,6 +431,389 @@ async fn thread_dump_diagnostic() {
     .await;
 }
 
+#[tokio::test]
+async fn task_dump_diagnostic() {
+    Server::with(|server| async move {
+        let request = Request::builder()
+            .uri("/witchcraft-ete/debug/diagnostic/rust.task.dump.v1")
+            .header("Authorization", "Bearer debug")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("Content-Type").unwrap(),
+            "text/plain"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = str::from_utf8(&body).unwrap();
+        // The diagnostic request is itself in flight
+        assert!(body.contains("Endpoint: DebugService.diagnostic\n"));
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn runtime_config_diagnostic() {
+    Server::with(|server| async move {
+        let request = Request::builder()
+            .uri("/witchcraft-ete/debug/diagnostic/config.runtime.v1")
+            .header("Authorization", "Bearer debug")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("Safe-Loggable").unwrap(), "false");
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = json::client_from_slice::<BTreeMap<String, Any>>(&body).unwrap();
+        let config = body["config"]
+            .clone()
+            .deserialize_into::<BTreeMap<String, Any>>()
+            .unwrap();
+        let diagnostics = config["diagnostics"]
+            .clone()
+            .deserialize_into::<BTreeMap<String, Option<String>>>()
+            .unwrap();
+        assert_eq!(
+            diagnostics["debug-shared-secret"].as_deref(),
+            Some("[REDACTED]")
+        );
+        assert_eq!(
+            diagnostics["metrics-shared-secret"].as_deref(),
+            Some("[REDACTED]")
+        );
+        // defaults are filled in
+        let logging = config["logging"]
+            .clone()
+            .deserialize_into::<BTreeMap<String, Any>>()
+            .unwrap();
+        assert_eq!(
+            logging["level"]
+                .clone()
+                .deserialize_into::<String>()
+                .unwrap(),
+            "INFO"
+        );
+        assert!(body.contains_key("sha256"));
+        assert!(body.contains_key("loadedAt"));
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn health_history_diagnostic() {
+    Server::with(|server| async move {
+        let panics = loop {
+            let request = Request::builder()
+                .uri("/witchcraft-ete/debug/diagnostic/health.check.history.v1")
+                .header("Authorization", "Bearer debug")
+                .body(Empty::<Bytes>::new())
+                .unwrap();
+            let response = server
+                .client()
+                .await
+                .unwrap()
+                .send_request(request)
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let mut history =
+                json::client_from_slice::<BTreeMap<String, Vec<BTreeMap<String, Any>>>>(&body)
+                    .unwrap();
+            let panics = history.remove("PANICS").unwrap();
+            if !panics.is_empty() {
+                break panics;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        };
+
+        assert_eq!(field(&panics[0], "oldState"), "REPAIRING");
+        assert_eq!(field(&panics[0], "newState"), "HEALTHY");
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn endpoints_diagnostic() {
+    Server::with(|server| async move {
+        let request = Request::builder()
+            .uri("/witchcraft-ete/debug/diagnostic/server.endpoints.v1")
+            .header("Authorization", "Bearer debug")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("Content-Type").unwrap(),
+            "application/json"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let endpoints = json::client_from_slice::<Vec<BTreeMap<String, Any>>>(&body).unwrap();
+        let diagnostic = endpoints
+            .iter()
+            .find(|e| field(e, "endpointName") == "diagnostic")
+            .unwrap();
+        assert_eq!(field(diagnostic, "serviceName"), "DebugService");
+        assert_eq!(field(diagnostic, "method"), "GET");
+        assert_eq!(
+            field(diagnostic, "template"),
+            "/witchcraft-ete/debug/diagnostic/{diagnostic_type}"
+        );
+        assert_eq!(field(diagnostic, "pathPrefix"), "/witchcraft-ete");
+        assert_eq!(field(diagnostic, "listener"), "service");
+        assert!(!diagnostic["blocking"]
+            .clone()
+            .deserialize_into::<bool>()
+            .unwrap());
+
+        let slow_headers = endpoints
+            .iter()
+            .find(|e| field(e, "endpointName") == "slowHeaders")
+            .unwrap();
+        assert_eq!(field(slow_headers, "pathPrefix"), "/witchcraft-ete/api");
+        assert!(slow_headers["metrics"]
+            .clone()
+            .deserialize_into::<bool>()
+            .unwrap());
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
+async fn active_requests(server: &Server) -> Vec<BTreeMap<String, Any>> {
+    let request = Request::builder()
+        .uri("/witchcraft-ete/debug/diagnostic/server.active.requests.v1")
+        .header("Authorization", "Bearer debug")
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+    let response = server
+        .client()
+        .await
+        .unwrap()
+        .send_request(request)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "application/json"
+    );
+    assert_eq!(response.headers().get("Safe-Loggable").unwrap(), "false");
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    json::client_from_slice(&body).unwrap()
+}
+
+fn field(request: &BTreeMap<String, Any>, name: &str) -> String {
+    request[name].clone().deserialize_into().unwrap()
+}
+
+#[tokio::test]
+async fn active_requests_diagnostic() {
+    Server::with(|server| async move {
+        let requests = active_requests(&server).await;
+
+        // The diagnostic request is itself in flight
+        assert_eq!(requests.len(), 1);
+        assert_eq!(field(&requests[0], "method"), "GET");
+        assert_eq!(
+            field(&requests[0], "path"),
+            "/witchcraft-ete/debug/diagnostic/{diagnostic_type}"
+        );
+        assert_eq!(field(&requests[0], "endpoint"), "DebugService.diagnostic");
+        assert!(requests[0].contains_key("peer"));
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn cancel_active_request() {
+    Server::with(|server| async move {
+        let request = Request::builder()
+            .uri("/witchcraft-ete/api/test/slowHeaders?delayMillis=3000")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let mut client = server.client().await.unwrap();
+        let start = Instant::now();
+        let slow_request = tokio::spawn(async move { client.send_request(request).await });
+
+        let request_id = loop {
+            let requests = active_requests(&server).await;
+            if let Some(request) = requests.iter().find(|r| {
+                r.contains_key("endpoint") && field(r, "endpoint").ends_with(".slowHeaders")
+            }) {
+                break field(request, "requestId");
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        };
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!(
+                "/witchcraft-ete/debug/requests/{request_id}/cancel"
+            ))
+            .header("Authorization", "Bearer debug")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = slow_request.await.unwrap().unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(start.elapsed() < Duration::from_secs(3));
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!(
+                "/witchcraft-ete/debug/requests/{request_id}/cancel"
+            ))
+            .header("Authorization", "Bearer debug")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/witchcraft-ete/debug/requests/not-a-request-id/cancel")
+            .header("Authorization", "Bearer debug")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn heap_profile_diagnostic() {
+    Server::builder()
+        .heap_profiling()
+        .with(|server| async move {
+            let request = Request::builder()
+                .uri("/witchcraft-ete/debug/diagnostic/rust.heap.profile.v1")
+                .header("Authorization", "Bearer debug")
+                .body(Empty::<Bytes>::new())
+                .unwrap();
+            let response = server
+                .client()
+                .await
+                .unwrap()
+                .send_request(request)
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response.headers().get("Content-Type").unwrap(),
+                "application/octet-stream"
+            );
+
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            // gzip magic
+            assert_eq!(body[..2], [0x1f, 0x8b]);
+
+            server.shutdown().await;
+        })
+        .await;
+}
+
+#[tokio::test]
+#[cfg(target_os = "linux")]
+async fn cpu_profile_diagnostic() {
+    // FIXME https://github.com/palantir/witchcraft-rust-server/issues/74
+    if std::env::var_os("CI").is_some() {
+        return;
+    }
+
+    Server::with(|server| async move {
+        let request = Request::builder()
+            .uri("/witchcraft-ete/debug/diagnostic/rust.cpu.profile.v1")
+            .header("Authorization", "Bearer debug")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("Content-Type").unwrap(),
+            "text/plain"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = str::from_utf8(&body).unwrap();
+        for line in body.lines() {
+            let (_, count) = line.rsplit_once(' ').unwrap();
+            count.parse::<u64>().unwrap();
+        }
+
+        server.shutdown().await;
+    })
+    .await;
+}
+
 #[tokio::test]
 async fn audit_logs() {
     Server::with(|server| async move {
@@ -348,6 +838,56 @@ async fn audit_logs() {
     .await;
 }
 
+#[tokio::test]
+async fn set_log_level() {
+    Server::with(|server| async move {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/witchcraft-ete/debug/logging/level?level=debug&target=witchcraft_server&ttl=1m")
+            .header("Authorization", "Bearer debug")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/witchcraft-ete/debug/logging/level?level=debug")
+            .header("Authorization", "Bearer bogus")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = server
+            .client()
+            .await
+            .unwrap()
+            .send_request(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let logs = server.shutdown().await;
+        assert_eq!(logs.audit.len(), 1);
+        assert_eq!(logs.audit[0].name(), "SET_LOG_LEVEL");
+        assert_eq!(logs.audit[0].deployment(), "ete");
+        assert_eq!(logs.audit[0].result(), &AuditResult::Success);
+        assert_eq!(logs.audit[0].origin(), Some("127.0.0.1"));
+        assert_eq!(
+            logs.audit[0].request_fields()["target"]
+                .clone()
+                .deserialize_into::<String>()
+                .unwrap(),
+            "witchcraft_server"
+        );
+    })
+    .await;
+}
+
 #[tokio::test]
 async fn trailers() {
     Server::builder()
@@ -102,6 +102,7 @@ impl Server {
         Builder {
             management_port: None,
             http2: false,
+            heap_profiling: false,
         }
     }
 
@@ -124,7 +125,11 @@ impl Server {
             .parent()
             .unwrap()
             .join("../witchcraft-server-ete");
-        let mut child = Command::new(binary)
+        let mut command = Command::new(binary);
+        if builder.heap_profiling {
+            command.env("MALLOC_CONF", "prof:true");
+        }
+        let mut child = command
             .current_dir(dir.path())
             .env("HANDLER_TYPE", handler_type)
             .stdout(Stdio::piped())
@@ -275,11 +280,11 @@ impl Server {
 
         let stdout = self.stdout_rx.take().unwrap().await.unwrap();
         for line in stdout.lines() {
-            if line.contains("service.1") {
+            if line.contains(r#""type":"service.1""#) {
                 logs.service.push(json::server_from_str(line).unwrap());
-            } else if line.contains("request.2") {
+            } else if line.contains(r#""type":"request.2""#) {
                 logs.request.push(json::server_from_str(line).unwrap());
-            } else if line.contains("audit.3") {
+            } else if line.contains(r#""type":"audit.3""#) {
                 logs.audit.push(json::server_from_str(line).unwrap());
             }
         }
@@ -292,6 +297,7 @@ impl Server {
 pub struct Builder {
     management_port: Option<u16>,
     http2: bool,
+    heap_profiling: bool,
 }
 
 impl Builder {
@@ -305,6 +311,11 @@ impl Builder {
         self
     }
 
+    pub fn heap_profiling(mut self) -> Self {
+        self.heap_profiling = true;
+        self
+    }
+
     pub async fn with<F, G>(self, test: F)
     where
         F: Fn(Server) -> G,
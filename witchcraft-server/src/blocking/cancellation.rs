@@ -11,44 +11,54 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 /// A type tracking the cancellation state of a request.
 ///
 /// This type will be added to the extensions of each request made to a blocking endpoint.
 #[derive(Clone, Debug)]
 pub struct Cancellation {
-    cancelled: Arc<AtomicBool>,
+    token: CancellationToken,
 }
 
 impl Cancellation {
-    pub(crate) fn new() -> (Cancellation, CancellationGuard) {
-        let cancelled = Arc::new(AtomicBool::new(false));
-        (
-            Cancellation {
-                cancelled: cancelled.clone(),
-            },
-            CancellationGuard { cancelled },
-        )
+    pub(crate) fn new() -> Cancellation {
+        Cancellation {
+            token: CancellationToken::new(),
+        }
     }
 
-    /// Returns `true` if the client of a request has cancelled it.
+    /// Returns a guard which cancels the request when dropped.
+    pub(crate) fn guard(&self) -> CancellationGuard {
+        CancellationGuard {
+            token: self.token.clone(),
+        }
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    pub(crate) async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+
+    /// Returns `true` if the client of a request or a server administrator has cancelled it.
     ///
     /// Long running blocking endpoint handlers should periodically check this to determine if they should continue
     /// working or not.
     #[inline]
     pub fn is_cancelled(&self) -> bool {
-        self.cancelled.load(Ordering::Relaxed)
+        self.token.is_cancelled()
     }
 }
 
 pub struct CancellationGuard {
-    cancelled: Arc<AtomicBool>,
+    token: CancellationToken,
 }
 
 impl Drop for CancellationGuard {
     fn drop(&mut self) {
-        self.cancelled.store(true, Ordering::Relaxed);
+        self.token.cancel();
     }
 }
@@ -18,6 +18,7 @@ use crate::blocking::{Cancellation, RequestBody, ResponseWriter};
 use crate::body::ClientIo;
 use crate::endpoint::{errors, WitchcraftEndpoint};
 use crate::health::endpoint_500s::EndpointHealth;
+use crate::logging;
 use crate::server::RawBody;
 use crate::service::endpoint_metrics::EndpointMetrics;
 use crate::service::handler::{BodyWriteAborted, EmptyBody};
@@ -100,15 +101,28 @@ impl WitchcraftEndpoint for ConjureBlockingEndpoint {
         Some(&self.health)
     }
 
+    fn blocking(&self) -> bool {
+        true
+    }
+
+    fn path_prefix(&self) -> &str {
+        ""
+    }
+
     async fn handle(
         &self,
         mut req: Request<RawBody>,
     ) -> Response<BoxBody<Bytes, BodyWriteAborted>> {
-        let (cancellation, guard) = Cancellation::new();
+        let cancellation = req
+            .extensions_mut()
+            .remove::<Cancellation>()
+            .unwrap_or_else(Cancellation::new);
+        let guard = cancellation.guard();
         req.extensions_mut().insert(cancellation);
 
         let trace_context = zipkin::current();
         let snapshot = mdc::snapshot();
+        let level = logging::request_level();
         let (sender, receiver) = oneshot::channel();
         let endpoint = self.inner.clone();
         let handle = Handle::current();
@@ -116,6 +130,7 @@ impl WitchcraftEndpoint for ConjureBlockingEndpoint {
         let blocking = move || {
             let _guard = trace_context.map(zipkin::set_current);
             mdc::set(snapshot);
+            logging::set_request_level(level);
 
             let req = req.map(|inner| RequestBody::new(inner, handle.clone()));
             let mut response_extensions = Extensions::new();
@@ -0,0 +1,281 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Renderings of the server's typed configuration with defaults filled in.
+//!
+//! Fields the server knows to be secret are redacted. Fields configuring HTTP clients are not rendered since their types
+//! are defined outside of the server.
+use crate::configs::REDACTED;
+use serde_json::{json, Map, Value};
+use std::time::{Duration, SystemTime};
+use witchcraft_server_config::install::{
+    InstallConfig, KeystoreConfig, LogTypeConfig, MetricsReporterConfig, ServerConfig,
+    TraceExporterConfig, TracePropagationFormat,
+};
+use witchcraft_server_config::runtime::{
+    HealthCheckOverrideConfig, HealthCheckState, LevelOverrideConfig, LoggingConfig,
+    MetricFilterAction, MetricFilterConfig, RedactionAction, RedactionRuleConfig, RuntimeConfig,
+    TailSamplingConfig, TraceSamplingRuleConfig,
+};
+
+pub fn install(config: &InstallConfig) -> Value {
+    json!({
+        "product-name": config.product_name(),
+        "product-version": config.product_version(),
+        "deployment": config.deployment(),
+        "port": config.port(),
+        "management-port": config.management_port().unwrap_or(config.port()),
+        "keystore": keystore(config.keystore()),
+        "client-auth-truststore": config.client_auth_truststore().map(|c| json!({
+            "path": c.path(),
+        })),
+        "context-path": config.context_path(),
+        "use-console-log": config.use_console_log(),
+        "server": server(config.server()),
+        "logging": json!({
+            "types": config
+                .logging()
+                .types()
+                .iter()
+                .map(|(type_, c)| (type_.clone(), log_type(c)))
+                .collect::<Map<_, _>>(),
+            "trace-exporter": config.logging().trace_exporter().map(trace_exporter),
+        }),
+        "metrics": json!({
+            "openmetrics-endpoint": config.metrics().openmetrics_endpoint(),
+            "reporter": config.metrics().reporter().map(metrics_reporter),
+        }),
+    })
+}
+
+fn keystore(config: &KeystoreConfig) -> Value {
+    json!({
+        "key-path": config.key_path(),
+        "cert-path": config.cert_path(),
+    })
+}
+
+fn server(config: &ServerConfig) -> Value {
+    json!({
+        "processors": config.processors(),
+        "min-threads": config.min_threads(),
+        "max-threads": config.max_threads(),
+        "max-connections": config.max_connections(),
+        "io-threads": config.io_threads(),
+        "idle-thread-timeout": duration(config.idle_thread_timeout()),
+        "shutdown-timeout": duration(config.shutdown_timeout()),
+        "gzip": config.gzip(),
+        "http2": config.http2(),
+        "idle-connection-timeout": duration(
+            config.idle_connection_timeout().unwrap_or(Duration::from_secs(60)),
+        ),
+        "trace-propagation": config
+            .trace_propagation()
+            .iter()
+            .map(|format| match format {
+                TracePropagationFormat::W3c => "w3c",
+                TracePropagationFormat::B3 => "b3",
+                _ => "unknown",
+            })
+            .collect::<Vec<_>>(),
+        "request-cancellation-endpoint": config.request_cancellation_endpoint(),
+    })
+}
+
+fn log_type(config: &LogTypeConfig) -> Value {
+    json!({
+        "queue-size": config.queue_size(),
+        "send-timeout": config.send_timeout().map(duration),
+        "max-file-size-bytes": config.max_file_size_bytes(),
+        "total-size-cap-bytes": config.total_size_cap_bytes(),
+        "retention-days": config.retention_days(),
+        "compress": config.compress(),
+    })
+}
+
+fn trace_exporter(config: &TraceExporterConfig) -> Value {
+    json!({
+        "batch-size": config.batch_size(),
+        "queue-size": config.queue_size(),
+        "flush-interval": duration(config.flush_interval()),
+    })
+}
+
+fn metrics_reporter(config: &MetricsReporterConfig) -> Value {
+    json!({
+        "interval": duration(config.interval()),
+        "statsd": config.statsd().map(|c| json!({
+            "address": c.address(),
+            "prefix": c.prefix(),
+            "max-packet-size": c.max_packet_size(),
+        })),
+    })
+}
+
+pub fn runtime(config: &RuntimeConfig) -> Value {
+    json!({
+        "diagnostics": json!({
+            "debug-shared-secret": secret(Some(config.diagnostics().debug_shared_secret())),
+            "metrics-shared-secret": secret(config.diagnostics().metrics_shared_secret()),
+            "heap-profiling-active": config.diagnostics().heap_profiling_active(),
+        }),
+        "health-checks": json!({
+            "shared-secret": secret(Some(config.health_checks().shared_secret())),
+            "overrides": config
+                .health_checks()
+                .overrides()
+                .iter()
+                .map(|(type_, c)| (type_.clone(), health_check_override(c)))
+                .collect::<Map<_, _>>(),
+        }),
+        "logging": logging(config.logging()),
+    })
+}
+
+fn health_check_override(config: &HealthCheckOverrideConfig) -> Value {
+    json!({
+        "disabled": config.disabled(),
+        "max-state": config.max_state().map(|state| match state {
+            HealthCheckState::Healthy => "healthy",
+            HealthCheckState::Deferring => "deferring",
+            HealthCheckState::Suspended => "suspended",
+            HealthCheckState::Repairing => "repairing",
+            HealthCheckState::Warning => "warning",
+            HealthCheckState::Error => "error",
+            HealthCheckState::Terminal => "terminal",
+            _ => "unknown",
+        }),
+        "muted-until": config.muted_until().map(time),
+    })
+}
+
+fn logging(config: &LoggingConfig) -> Value {
+    json!({
+        "level": config.level(),
+        "loggers": config.loggers(),
+        "trace-rate": float(config.trace_rate()),
+        "level-overrides": config
+            .level_overrides()
+            .iter()
+            .map(level_override)
+            .collect::<Vec<_>>(),
+        "rate-limit": config.rate_limit().map(|c| json!({
+            "max-logs": c.max_logs(),
+            "interval": duration(c.interval()),
+        })),
+        "redaction": json!({
+            "test-mode": config.redaction().test_mode(),
+            "rules": config
+                .redaction()
+                .rules()
+                .iter()
+                .map(redaction_rule)
+                .collect::<Vec<_>>(),
+        }),
+        "trace-sampling": json!({
+            "rules": config
+                .trace_sampling()
+                .rules()
+                .iter()
+                .map(trace_sampling_rule)
+                .collect::<Vec<_>>(),
+            "tail": tail_sampling(config.trace_sampling().tail()),
+        }),
+        "metrics": json!({
+            "interval": duration(config.metrics().interval()),
+            "filters": config
+                .metrics()
+                .filters()
+                .iter()
+                .map(metric_filter)
+                .collect::<Vec<_>>(),
+            "skip-unchanged": config.metrics().skip_unchanged(),
+        }),
+    })
+}
+
+fn level_override(config: &LevelOverrideConfig) -> Value {
+    json!({
+        "level": config.level(),
+        "trace-id": config.trace_id(),
+        "user-id": config.user_id(),
+        "endpoint": config.endpoint(),
+        "debug-header-secret": secret(config.debug_header_secret()),
+    })
+}
+
+fn redaction_rule(config: &RedactionRuleConfig) -> Value {
+    json!({
+        "name": config.name(),
+        "key-pattern": config.key_pattern(),
+        "value-pattern": config.value_pattern(),
+        "action": match config.action() {
+            RedactionAction::Drop => "drop",
+            RedactionAction::Hash => "hash",
+            RedactionAction::Mask => "mask",
+            _ => "unknown",
+        },
+    })
+}
+
+fn trace_sampling_rule(config: &TraceSamplingRuleConfig) -> Value {
+    json!({
+        "service": config.service(),
+        "endpoint": config.endpoint(),
+        "rate": float(config.rate()),
+    })
+}
+
+fn tail_sampling(config: &TailSamplingConfig) -> Value {
+    json!({
+        "enabled": config.enabled(),
+        "errors": config.errors(),
+        "latency-threshold": config.latency_threshold().map(duration),
+        "max-pending-traces": config.max_pending_traces(),
+    })
+}
+
+fn metric_filter(config: &MetricFilterConfig) -> Value {
+    json!({
+        "action": match config.action() {
+            MetricFilterAction::Allow => "allow",
+            MetricFilterAction::Deny => "deny",
+            _ => "unknown",
+        },
+        "name-prefix": config.name_prefix(),
+        "tags": config.tags(),
+    })
+}
+
+fn secret(value: Option<&str>) -> Value {
+    match value {
+        Some(_) => Value::from(REDACTED),
+        None => Value::Null,
+    }
+}
+
+fn duration(duration: Duration) -> String {
+    humantime::format_duration(duration).to_string()
+}
+
+fn time(time: SystemTime) -> String {
+    humantime::format_rfc3339(time).to_string()
+}
+
+// Widening the value directly would render e.g. 0.0005 as 0.0005000000237487257.
+fn float(value: f32) -> Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .map_or(Value::Null, Value::from)
+}
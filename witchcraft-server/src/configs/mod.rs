@@ -0,0 +1,487 @@
+// Copyright 2022 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use arc_swap::ArcSwapOption;
+use conjure_error::Error;
+use conjure_object::{DateTime, Utc};
+use refreshable::{RefreshHandle, Refreshable};
+use serde::de::DeserializeOwned;
+use serde_encrypted_value::{Key, ReadOnly};
+use serde_yaml::Value;
+use sha2::digest::Output;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fs, io};
+use tokio::runtime::Handle;
+use tokio::{task, time};
+use witchcraft_log::{error, info};
+use witchcraft_server_config::install::InstallConfig;
+use witchcraft_server_config::runtime::RuntimeConfig;
+
+mod effective;
+
+const RELOAD_INTERVAL: Duration = Duration::from_secs(3);
+const INSTALL_YML: &str = "var/conf/install.yml";
+const RUNTIME_YML: &str = "var/conf/runtime.yml";
+const ENCRYPTED_CONFIG_VALUE_KEY: &str = "var/conf/encrypted-config-value.key";
+const REDACTED: &str = "[REDACTED]";
+// Values of fields named any of these, or with names ending in `-` followed by any of these, are redacted even if
+// they're specified in plaintext. Other fields like `key-path` are left visible.
+const SENSITIVE_KEYS: &[&str] = &[
+    "secret",
+    "secrets",
+    "password",
+    "passphrase",
+    "token",
+    "tokens",
+    "credentials",
+    "auth",
+    "authorization",
+    "api-key",
+    "private-key",
+];
+
+static INSTALL_CONFIG: ArcSwapOption<LoadedConfig> = ArcSwapOption::const_empty();
+static RUNTIME_CONFIG: ArcSwapOption<LoadedConfig> = ArcSwapOption::const_empty();
+
+/// A redacted snapshot of a configuration file as of its last successful load.
+pub struct LoadedConfig {
+    /// The effective configuration, with encrypted, file-backed, and sensitive values redacted.
+    ///
+    /// The server's own settings are rendered from the typed configuration with defaults filled in, and overlaid onto
+    /// the document as written so that settings the server doesn't know about are still included.
+    pub config: Value,
+    pub hash: Output<Sha256>,
+    /// The hashes of the files referenced by the configuration, or `None` if a file could not be read.
+    pub files: BTreeMap<PathBuf, Option<Output<Sha256>>>,
+    pub time: DateTime<Utc>,
+}
+
+impl LoadedConfig {
+    fn new(raw: &[u8], files: &ConfigFiles, effective: serde_json::Value) -> Self {
+        let mut config = serde_yaml::from_slice(raw).unwrap_or(Value::Null);
+        redact(&mut config, false);
+        if let Ok(effective) = serde_yaml::to_value(effective) {
+            overlay(&mut config, effective);
+        }
+
+        LoadedConfig {
+            config,
+            hash: files.root_hash,
+            files: files
+                .ok_files
+                .iter()
+                .map(|(path, hash)| (path.clone(), Some(*hash)))
+                .chain(files.err_files.iter().map(|path| (path.clone(), None)))
+                .collect(),
+            time: Utc::now(),
+        }
+    }
+}
+
+/// Returns the install configuration loaded by [`load_install`], if it has been called.
+pub fn loaded_install() -> Option<Arc<LoadedConfig>> {
+    INSTALL_CONFIG.load_full()
+}
+
+/// Returns the most recent runtime configuration successfully loaded by [`load_runtime`], if it has been called.
+pub fn loaded_runtime() -> Option<Arc<LoadedConfig>> {
+    RUNTIME_CONFIG.load_full()
+}
+
+fn redact(value: &mut Value, sensitive: bool) {
+    match value {
+        Value::String(s) => {
+            let encrypted = s.starts_with("${enc:") && s.ends_with('}');
+            let file = s.starts_with("${file:") && s.ends_with('}');
+            if sensitive || encrypted || file {
+                *s = REDACTED.to_string();
+            }
+        }
+        Value::Bool(_) | Value::Number(_) => {
+            if sensitive {
+                *value = Value::String(REDACTED.to_string());
+            }
+        }
+        Value::Sequence(values) => values.iter_mut().for_each(|v| redact(v, sensitive)),
+        Value::Mapping(values) => {
+            for (key, value) in values {
+                redact(value, sensitive || is_sensitive(key));
+            }
+        }
+        Value::Tagged(tagged) => redact(&mut tagged.value, sensitive),
+        Value::Null => {}
+    }
+}
+
+fn is_sensitive(key: &Value) -> bool {
+    let Some(key) = key.as_str() else {
+        return false;
+    };
+    let key = kebab_case(key);
+
+    SENSITIVE_KEYS.iter().any(|s| {
+        key.strip_suffix(s)
+            .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('-'))
+    })
+}
+
+// Fields may also be written in snake or camel case by services' own configuration types.
+fn kebab_case(key: &str) -> String {
+    let mut kebab = String::with_capacity(key.len());
+    for c in key.chars() {
+        if c.is_ascii_uppercase() && !kebab.is_empty() {
+            kebab.push('-');
+        }
+        kebab.push(match c {
+            '_' => '-',
+            c => c.to_ascii_lowercase(),
+        });
+    }
+    kebab
+}
+
+/// Overlays the rendering of the typed configuration onto the redacted document.
+///
+/// Values redacted from the document stay redacted, since the typed configuration holds their resolved values.
+fn overlay(document: &mut Value, effective: Value) {
+    match (document, effective) {
+        (Value::String(s), _) if s == REDACTED => {}
+        (Value::Mapping(document), Value::Mapping(effective)) => {
+            for (key, value) in effective {
+                match document.get_mut(&key) {
+                    Some(existing) => overlay(existing, value),
+                    None => {
+                        document.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Sequence(document), Value::Sequence(effective))
+            if document.len() == effective.len() =>
+        {
+            for (existing, value) in document.iter_mut().zip(effective) {
+                overlay(existing, value);
+            }
+        }
+        (document, effective) => *document = effective,
+    }
+}
+
+pub fn load_install<T>() -> Result<T, Error>
+where
+    T: AsRef<InstallConfig> + DeserializeOwned,
+{
+    let key = load_key()?;
+    let bytes = load_file(INSTALL_YML)?;
+    let (value, files) = parse(&bytes, key.as_ref());
+    let value: T = value?;
+
+    let effective = effective::install(value.as_ref());
+    INSTALL_CONFIG.store(Some(Arc::new(LoadedConfig::new(&bytes, &files, effective))));
+
+    Ok(value)
+}
+
+pub fn load_runtime<T>(
+    runtime: &Handle,
+    config_ok: &Arc<AtomicBool>,
+) -> Result<Refreshable<T, Error>, Error>
+where
+    T: AsRef<RuntimeConfig> + DeserializeOwned + PartialEq + 'static + Sync + Send,
+{
+    let key = load_key()?;
+    let bytes = load_file(RUNTIME_YML)?;
+    let (value, files) = parse(&bytes, key.as_ref());
+    let value: T = value?;
+
+    let effective = effective::runtime(value.as_ref());
+    RUNTIME_CONFIG.store(Some(Arc::new(LoadedConfig::new(&bytes, &files, effective))));
+
+    let (refreshable, handle) = Refreshable::new(value);
+
+    runtime.spawn(runtime_reload(files, key, handle, config_ok.clone()));
+
+    Ok(refreshable)
+}
+
+struct ConfigFiles {
+    root_hash: Output<Sha256>,
+    ok_files: HashMap<PathBuf, Output<Sha256>>,
+    err_files: HashSet<PathBuf>,
+}
+
+impl ConfigFiles {
+    fn up_to_date(&self, root_bytes: &[u8]) -> bool {
+        if self.root_hash != Sha256::digest(root_bytes) {
+            return false;
+        }
+
+        for (path, hash) in &self.ok_files {
+            match fs::read(path) {
+                Ok(bytes) => {
+                    if *hash != Sha256::digest(&bytes) {
+                        return false;
+                    }
+                }
+                Err(_) => return false,
+            }
+        }
+
+        for path in &self.err_files {
+            if fs::read(path).is_ok() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn add(&mut self, path: &Path, r: &io::Result<Vec<u8>>) {
+        match r {
+            Ok(bytes) => {
+                self.ok_files
+                    .insert(path.to_path_buf(), Sha256::digest(bytes));
+            }
+            Err(_) => {
+                self.err_files.insert(path.to_path_buf());
+            }
+        }
+    }
+}
+
+fn load_key() -> Result<Option<Key<ReadOnly>>, Error> {
+    Key::from_file(ENCRYPTED_CONFIG_VALUE_KEY).map_err(Error::internal_safe)
+}
+
+fn load_file(path: &str) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|e| Error::internal_safe(e).with_safe_param("path", path))
+}
+
+fn parse<T>(raw: &[u8], key: Option<&Key<ReadOnly>>) -> (Result<T, Error>, ConfigFiles)
+where
+    T: DeserializeOwned,
+{
+    let mut files = ConfigFiles {
+        root_hash: Sha256::digest(raw),
+        ok_files: HashMap::new(),
+        err_files: HashSet::new(),
+    };
+    let mut callback = |path: &Path, r: &io::Result<Vec<u8>>| files.add(path, r);
+
+    let de = serde_yaml::Deserializer::from_slice(raw);
+    let de = serde_encrypted_value::Deserializer::new(de, key);
+    let de = serde_file_value::Deserializer::new(de, &mut callback);
+
+    let value = T::deserialize(de).map_err(Error::internal);
+    (value, files)
+}
+
+async fn runtime_reload<T>(
+    mut files: ConfigFiles,
+    key: Option<Key<ReadOnly>>,
+    mut handle: RefreshHandle<T, Error>,
+    config_ok: Arc<AtomicBool>,
+) where
+    T: AsRef<RuntimeConfig> + DeserializeOwned + PartialEq + 'static + Sync + Send,
+{
+    loop {
+        time::sleep(RELOAD_INTERVAL).await;
+
+        // it's okay to use block_in_place here since we know this future is running as its own task.
+        task::block_in_place(|| {
+            let new_bytes = match fs::read(RUNTIME_YML) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    error!(
+                        "error reading runtime config",
+                        error: Error::internal_safe(e)
+                    );
+                    config_ok.store(false, Ordering::Relaxed);
+                    return;
+                }
+            };
+
+            if files.up_to_date(&new_bytes) {
+                return;
+            }
+
+            let (value, new_files) = parse(&new_bytes, key.as_ref());
+            files = new_files;
+            let value: T = match value {
+                Ok(value) => value,
+                Err(e) => {
+                    error!("error parsing runtime config", error: e);
+                    config_ok.store(false, Ordering::Relaxed);
+                    return;
+                }
+            };
+
+            let effective = effective::runtime(value.as_ref());
+            match handle.refresh(value) {
+                Ok(()) => {
+                    RUNTIME_CONFIG.store(Some(Arc::new(LoadedConfig::new(
+                        &new_bytes, &files, effective,
+                    ))));
+                    config_ok.store(true, Ordering::Relaxed);
+                }
+                Err(errors) => {
+                    for error in errors {
+                        error!("error reloading runtime config", error: error);
+                    }
+                    config_ok.store(false, Ordering::Relaxed);
+                }
+            }
+
+            info!("reloaded runtime config");
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn redact_secrets() {
+        let mut config = serde_yaml::from_str::<Value>(
+            r#"
+plain: foo
+encrypted: ${enc:abcd}
+nested:
+  - ${file:/mnt/secrets/token}
+  - ${foo}
+diagnostics:
+  debug-shared-secret: debug
+  metrics-shared-secret: metrics
+health-checks:
+  shared-secret: health
+api-tokens:
+  - foo
+  - bar
+tokens:
+  - name: foo
+    value: bar
+keystore:
+  key-path: var/security/key.pem
+  key-store-password: 1234
+  trust-store:
+    path: var/security/ca.pem
+    password: changeit
+proxy:
+  credentials:
+    username: admin
+    password: hunter2
+  auth: hunter2
+clientSecret: abcd
+monkey: banana
+"#,
+        )
+        .unwrap();
+        redact(&mut config, false);
+
+        let expected = serde_yaml::from_str::<Value>(
+            r#"
+plain: foo
+encrypted: "[REDACTED]"
+nested:
+  - "[REDACTED]"
+  - ${foo}
+diagnostics:
+  debug-shared-secret: "[REDACTED]"
+  metrics-shared-secret: "[REDACTED]"
+health-checks:
+  shared-secret: "[REDACTED]"
+api-tokens:
+  - "[REDACTED]"
+  - "[REDACTED]"
+tokens:
+  - name: "[REDACTED]"
+    value: "[REDACTED]"
+keystore:
+  key-path: var/security/key.pem
+  key-store-password: "[REDACTED]"
+  trust-store:
+    path: var/security/ca.pem
+    password: "[REDACTED]"
+proxy:
+  credentials:
+    username: "[REDACTED]"
+    password: "[REDACTED]"
+  auth: "[REDACTED]"
+clientSecret: "[REDACTED]"
+monkey: banana
+"#,
+        )
+        .unwrap();
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn effective_runtime() {
+        let raw = r#"
+diagnostics:
+  debug-shared-secret: ${enc:abcd}
+health-checks:
+  shared-secret: health
+logging:
+  level: debug
+  trace-sampling:
+    rules:
+      - endpoint: ping
+        rate: 0.5
+service-discovery:
+  services:
+    foo:
+      uris: [https://foo]
+custom:
+  enabled: true
+"#;
+        let config = serde_yaml::from_str::<RuntimeConfig>(raw).unwrap();
+        let files = ConfigFiles {
+            root_hash: Sha256::digest(raw),
+            ok_files: HashMap::new(),
+            err_files: HashSet::new(),
+        };
+        let loaded = LoadedConfig::new(raw.as_bytes(), &files, effective::runtime(&config));
+
+        let config = &loaded.config;
+        assert_eq!(config["diagnostics"]["debug-shared-secret"], REDACTED);
+        assert_eq!(config["diagnostics"]["metrics-shared-secret"], Value::Null);
+        assert_eq!(config["health-checks"]["shared-secret"], REDACTED);
+        assert_eq!(config["logging"]["level"], "DEBUG");
+        assert_eq!(config["logging"]["trace-rate"], 0.0005);
+        assert_eq!(
+            config["logging"]["trace-sampling"]["rules"][0]["endpoint"],
+            "ping"
+        );
+        assert_eq!(
+            config["logging"]["trace-sampling"]["rules"][0]["service"],
+            Value::Null
+        );
+        assert_eq!(
+            config["logging"]["trace-sampling"]["tail"]["enabled"],
+            false
+        );
+        assert_eq!(config["logging"]["metrics"]["interval"], "30s");
+        assert_eq!(
+            config["service-discovery"]["services"]["foo"]["uris"][0],
+            "https://foo"
+        );
+        assert_eq!(config["custom"]["enabled"], true);
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::debug::Diagnostic;
+use crate::service::active_requests::{ActiveRequest, ActiveRequests};
+use bytes::Bytes;
+use conjure_error::Error;
+use conjure_object::{DateTime, Utc, Uuid};
+use conjure_serde::json;
+use http::HeaderValue;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A diagnostic which returns a JSON description of each request currently being handled by the server.
+///
+/// Entries are ordered from oldest to newest. Since they include user IDs and peer addresses, the diagnostic is not
+/// safe to log.
+pub struct ActiveRequestsDiagnostic {
+    requests: Arc<ActiveRequests>,
+}
+
+impl ActiveRequestsDiagnostic {
+    pub fn new(requests: &Arc<ActiveRequests>) -> Self {
+        ActiveRequestsDiagnostic {
+            requests: requests.clone(),
+        }
+    }
+}
+
+impl Diagnostic for ActiveRequestsDiagnostic {
+    fn type_(&self) -> &str {
+        "server.active.requests.v1"
+    }
+
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static("application/json")
+    }
+
+    fn safe_loggable(&self) -> bool {
+        false
+    }
+
+    fn result(&self) -> Result<Bytes, Error> {
+        let now = Instant::now();
+        let requests = self
+            .requests
+            .snapshot()
+            .iter()
+            .map(|r| ActiveRequestInfo::new(r, now))
+            .collect::<Vec<_>>();
+
+        Ok(Bytes::from(json::to_vec(&requests).unwrap()))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ActiveRequestInfo {
+    request_id: String,
+    method: String,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    endpoint: Option<String>,
+    trace_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peer: Option<String>,
+    start_time: DateTime<Utc>,
+    elapsed_millis: u64,
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+impl ActiveRequestInfo {
+    fn new(request: &ActiveRequest, now: Instant) -> Self {
+        ActiveRequestInfo {
+            request_id: request.request_id.to_string(),
+            method: request.method.to_string(),
+            path: request.path().to_string(),
+            endpoint: request.endpoint_name(),
+            trace_id: request.trace_id.to_string(),
+            user_id: request.user_id,
+            peer: request.peer.map(|p| p.to_string()),
+            start_time: request.start_time,
+            elapsed_millis: now.duration_since(request.start).as_millis() as u64,
+            bytes_read: request.bytes_read(),
+            bytes_written: request.bytes_written(),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::configs::{self, LoadedConfig};
+use crate::debug::Diagnostic;
+use bytes::Bytes;
+use conjure_error::{Error, FailedPrecondition};
+use conjure_object::{DateTime, Utc};
+use conjure_serde::json;
+use http::HeaderValue;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// A diagnostic which returns a configuration file as it was last successfully loaded by the server.
+///
+/// The server's own settings are rendered from the typed configuration with defaults filled in, and overlaid onto the
+/// YAML document as written so that services' own settings are included as well. Encrypted and file-backed values, the
+/// server's secrets, and the values of fields with sensitive names like `password` or `api-token` are redacted. The
+/// hashes of the referenced files are included to identify which versions were loaded. Since the configuration may
+/// still contain sensitive values, the diagnostic is not safe to log.
+pub struct ConfigDiagnostic {
+    type_: &'static str,
+    loaded: fn() -> Option<Arc<LoadedConfig>>,
+}
+
+impl ConfigDiagnostic {
+    pub fn install() -> Self {
+        ConfigDiagnostic {
+            type_: "config.install.v1",
+            loaded: configs::loaded_install,
+        }
+    }
+
+    pub fn runtime() -> Self {
+        ConfigDiagnostic {
+            type_: "config.runtime.v1",
+            loaded: configs::loaded_runtime,
+        }
+    }
+}
+
+impl Diagnostic for ConfigDiagnostic {
+    fn type_(&self) -> &str {
+        self.type_
+    }
+
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static("application/json")
+    }
+
+    fn safe_loggable(&self) -> bool {
+        false
+    }
+
+    fn result(&self) -> Result<Bytes, Error> {
+        // Servers using custom config loaders don't record their configuration.
+        let loaded = (self.loaded)().ok_or_else(|| {
+            Error::service_safe(
+                "configuration was not loaded by the server",
+                FailedPrecondition::new(),
+            )
+        })?;
+
+        let response = ConfigResponse {
+            config: serde_json::to_value(&loaded.config).map_err(Error::internal_safe)?,
+            sha256: format!("{:x}", loaded.hash),
+            files: loaded
+                .files
+                .iter()
+                .map(|(path, hash)| ConfigFile {
+                    path: path.display().to_string(),
+                    sha256: hash.map(|hash| format!("{hash:x}")),
+                })
+                .collect(),
+            loaded_at: loaded.time,
+        };
+
+        Ok(Bytes::from(json::to_vec(&response).unwrap()))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigResponse {
+    config: serde_json::Value,
+    sha256: String,
+    files: Vec<ConfigFile>,
+    loaded_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct ConfigFile {
+    path: String,
+    /// Absent if the file could not be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+}
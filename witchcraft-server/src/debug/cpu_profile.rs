@@ -0,0 +1,442 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::debug::{Diagnostic, DiagnosticBody, DiagnosticParam, DiagnosticParams};
+use crate::minidump::modules::LoadedModules;
+use crate::minidump::symbol_provider::{Arena, WitchcraftSymbolProvider};
+use bytes::Bytes;
+use conjure_error::{Conflict, Error, InvalidArgument};
+use http::HeaderValue;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::cell::UnsafeCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::os::raw::{c_int, c_void};
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::time::Duration;
+use std::{fs, io, mem, ptr, thread};
+use witchcraft_log::warn;
+
+const DURATION: &str = "duration";
+const DEFAULT_DURATION: Duration = Duration::from_secs(10);
+const MAX_DURATION: Duration = Duration::from_secs(5 * 60);
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(10);
+const MAX_SAMPLES: usize = 50_000;
+const MAX_DEPTH: usize = 64;
+const MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+static HANDLER: OnceCell<()> = OnceCell::new();
+static SAMPLES: AtomicPtr<Samples> = AtomicPtr::new(ptr::null_mut());
+static ACTIVE_HANDLERS: AtomicUsize = AtomicUsize::new(0);
+
+/// A diagnostic which returns a CPU profile of the server in the folded stack format used by tools like [inferno].
+///
+/// The profile is collected with a `SIGPROF` timer which interrupts the thread consuming CPU every 10 milliseconds of CPU
+/// time and records its return addresses. The stacks are symbolized once the profile is complete. Each line of the
+/// output consists of a thread name followed by the frames of a stack from outermost to innermost, separated by `;`,
+/// and the number of times the stack was sampled.
+///
+/// Stacks are walked by following frame pointers, since the unwinder is not async-signal-safe. Frames of code compiled
+/// without frame pointers will be missing, so the server should be built with `-C force-frame-pointers=yes` for
+/// complete stacks.
+///
+/// The `ITIMER_PROF` timer and `SIGPROF` handler are shared by the whole process, so profiles can't be collected while
+/// anything else in the process (e.g. gperftools) is using them.
+///
+/// The `duration` parameter controls how long the profile is collected for, and defaults to 10 seconds.
+///
+/// It is only supported on Linux.
+///
+/// [inferno]: https://github.com/jonhoo/inferno
+pub struct CpuProfileDiagnostic {
+    lock: Mutex<()>,
+}
+
+impl CpuProfileDiagnostic {
+    pub fn new() -> Self {
+        CpuProfileDiagnostic {
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn profile(&self, duration: Duration) -> Result<Bytes, Error> {
+        // Profiles are expensive to collect, so we only run one at a time.
+        let Some(_guard) = self.lock.try_lock() else {
+            return Err(Error::service_safe(
+                "a CPU profile is already being collected",
+                Conflict::new(),
+            ));
+        };
+
+        profile(duration).map(Bytes::from)
+    }
+}
+
+impl Diagnostic for CpuProfileDiagnostic {
+    fn type_(&self) -> &str {
+        "rust.cpu.profile.v1"
+    }
+
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static("text/plain")
+    }
+
+    fn safe_loggable(&self) -> bool {
+        true
+    }
+
+    fn params(&self) -> Vec<DiagnosticParam> {
+        vec![DiagnosticParam::new(
+            DURATION,
+            "The duration of the profile, e.g. `30s`. Defaults to 10 seconds, and may not exceed 5 minutes.",
+        )]
+    }
+
+    fn result(&self) -> Result<Bytes, Error> {
+        self.profile(DEFAULT_DURATION)
+    }
+
+    fn result_with_params(&self, params: &DiagnosticParams) -> Result<DiagnosticBody, Error> {
+        let duration = params
+            .parse::<humantime::Duration>(DURATION)?
+            .map_or(DEFAULT_DURATION, Duration::from);
+        if duration.is_zero() || duration > MAX_DURATION {
+            return Err(Error::service_safe(
+                "invalid CPU profile duration",
+                InvalidArgument::new(),
+            )
+            .with_safe_param("duration", humantime::format_duration(duration).to_string())
+            .with_safe_param(
+                "maxDuration",
+                humantime::format_duration(MAX_DURATION).to_string(),
+            ));
+        }
+
+        self.profile(duration).map(DiagnosticBody::Fixed)
+    }
+}
+
+fn profile(duration: Duration) -> Result<String, Error> {
+    HANDLER
+        .get_or_try_init(install_handler)
+        .map_err(Error::internal_safe)?;
+
+    // The timer fires once per interval of CPU time consumed by the process as a whole, so there can be at most one
+    // sample per interval for each CPU.
+    let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+    let intervals = (duration.as_millis() / SAMPLE_INTERVAL.as_millis()) as usize + 1;
+    let samples = Samples::new(usize::min(intervals * cpus, MAX_SAMPLES));
+
+    SAMPLES.store(&samples as *const Samples as *mut Samples, Ordering::SeqCst);
+    let result = set_timer(SAMPLE_INTERVAL).map(|()| thread::sleep(duration));
+    let _ = set_timer(Duration::ZERO);
+    SAMPLES.store(ptr::null_mut(), Ordering::SeqCst);
+    // Handlers which observed the samples before they were unpublished may still be writing to them.
+    while ACTIVE_HANDLERS.load(Ordering::SeqCst) != 0 {
+        thread::yield_now();
+    }
+    result.map_err(Error::internal_safe)?;
+
+    let requested = samples.next.load(Ordering::Relaxed);
+    let recorded = usize::min(requested, samples.samples.len());
+    if requested > recorded {
+        warn!(
+            "CPU profile sample buffer was full",
+            safe: { dropped: requested - recorded },
+        );
+    }
+
+    let mut stacks = HashMap::<_, u64>::new();
+    for sample in &samples.samples[..recorded] {
+        let sample = unsafe { &*sample.get() };
+        // Every frame but the innermost is a return address, so we look up the preceding instruction to find the call
+        // site.
+        let frames = sample.frames[..sample.depth]
+            .iter()
+            .enumerate()
+            .map(|(i, &address)| {
+                if i == 0 {
+                    address
+                } else {
+                    address.saturating_sub(1)
+                }
+            })
+            .collect::<Vec<_>>();
+        *stacks.entry((sample.tid, frames)).or_default() += 1;
+    }
+
+    let modules = LoadedModules::new();
+    let arena = Arena::new();
+    let symbol_provider = WitchcraftSymbolProvider::new(&arena);
+
+    let mut thread_names = HashMap::new();
+    let mut symbols = HashMap::new();
+    let mut folded_stacks = BTreeMap::new();
+    for ((tid, frames), count) in stacks {
+        let mut folded = thread_names
+            .entry(tid)
+            .or_insert_with(|| thread_name(tid))
+            .clone();
+
+        for address in frames.iter().rev() {
+            let names = symbols.entry(*address).or_insert_with(|| {
+                let names = modules
+                    .find(*address as u64)
+                    .map(|module| symbol_provider.symbolize(module, *address as u64))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|symbol| sanitize(&symbol.function))
+                    .collect::<Vec<_>>();
+                if names.is_empty() {
+                    vec!["???".to_string()]
+                } else {
+                    names
+                }
+            });
+
+            for name in names {
+                folded.push(';');
+                folded.push_str(name);
+            }
+        }
+
+        *folded_stacks.entry(folded).or_insert(0) += count;
+    }
+
+    let mut out = String::new();
+    for (stack, count) in folded_stacks {
+        writeln!(out, "{stack} {count}").unwrap();
+    }
+
+    Ok(out)
+}
+
+fn thread_name(tid: libc::pid_t) -> String {
+    match fs::read_to_string(format!("/proc/self/task/{tid}/comm")) {
+        Ok(name) => sanitize(name.trim_end_matches('\n')),
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+fn install_handler() -> io::Result<()> {
+    unsafe {
+        let mut action = mem::zeroed::<libc::sigaction>();
+        action.sa_sigaction = handle_sigprof as *const () as usize;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+
+        // The handler is left installed since a signal may still be pending after the timer is disarmed. It does
+        // nothing when a profile isn't being collected.
+        if libc::sigaction(libc::SIGPROF, &action, ptr::null_mut()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+fn set_timer(interval: Duration) -> io::Result<()> {
+    let interval = libc::timeval {
+        tv_sec: interval.as_secs() as libc::time_t,
+        tv_usec: interval.subsec_micros() as libc::suseconds_t,
+    };
+    let timer = libc::itimerval {
+        it_interval: interval,
+        it_value: interval,
+    };
+
+    if unsafe { libc::setitimer(libc::ITIMER_PROF, &timer, ptr::null_mut()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+// Everything reachable from the signal handler must be async-signal-safe, so samples are written into a preallocated
+// buffer and symbolization is deferred until the profile is complete.
+struct Samples {
+    next: AtomicUsize,
+    samples: Box<[UnsafeCell<Sample>]>,
+}
+
+// Each slot is claimed by exactly one handler.
+unsafe impl Sync for Samples {}
+
+impl Samples {
+    fn new(capacity: usize) -> Self {
+        Samples {
+            next: AtomicUsize::new(0),
+            samples: (0..capacity)
+                .map(|_| {
+                    UnsafeCell::new(Sample {
+                        tid: 0,
+                        depth: 0,
+                        frames: [0; MAX_DEPTH],
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    unsafe fn record(&self, context: *const libc::ucontext_t) {
+        let Some(sample) = self.samples.get(self.next.fetch_add(1, Ordering::Relaxed)) else {
+            return;
+        };
+        let sample = &mut *sample.get();
+        sample.tid = libc::gettid();
+        sample.depth = 0;
+
+        let Some(registers) = registers(context) else {
+            return;
+        };
+        sample.frames[0] = registers.pc;
+        sample.depth = 1;
+
+        // Each frame record holds the caller's frame pointer followed by the return address. Records live on the stack
+        // above the interrupted stack pointer, and each is above the last, so anything else means the chain is broken.
+        let mut lower = registers.sp;
+        let mut fp = registers.fp;
+        while sample.depth < MAX_DEPTH
+            && fp >= lower
+            && fp - lower <= MAX_FRAME_SIZE
+            && fp % mem::align_of::<usize>() == 0
+        {
+            let Some([next_fp, return_address]) = read_frame(fp) else {
+                break;
+            };
+            if return_address == 0 {
+                break;
+            }
+
+            sample.frames[sample.depth] = return_address;
+            sample.depth += 1;
+
+            lower = fp + mem::size_of::<[usize; 2]>();
+            fp = next_fp;
+        }
+    }
+}
+
+struct Sample {
+    tid: libc::pid_t,
+    depth: usize,
+    frames: [usize; MAX_DEPTH],
+}
+
+unsafe extern "C" fn handle_sigprof(_: c_int, _: *mut libc::siginfo_t, context: *mut c_void) {
+    ACTIVE_HANDLERS.fetch_add(1, Ordering::SeqCst);
+
+    if let Some(samples) = SAMPLES.load(Ordering::SeqCst).as_ref() {
+        let errno = *libc::__errno_location();
+        samples.record(context.cast());
+        *libc::__errno_location() = errno;
+    }
+
+    ACTIVE_HANDLERS.fetch_sub(1, Ordering::SeqCst);
+}
+
+// A frame pointer may be garbage if it passes through code compiled without frame pointers, so frame records are read
+// with a syscall which fails rather than faulting on unmapped memory.
+unsafe fn read_frame(fp: usize) -> Option<[usize; 2]> {
+    let mut frame = [0usize; 2];
+    let local = libc::iovec {
+        iov_base: frame.as_mut_ptr().cast(),
+        iov_len: mem::size_of_val(&frame),
+    };
+    let remote = libc::iovec {
+        iov_base: fp as *mut c_void,
+        iov_len: mem::size_of_val(&frame),
+    };
+
+    let read = libc::process_vm_readv(libc::getpid(), &local, 1, &remote, 1, 0);
+    if read == mem::size_of_val(&frame) as isize {
+        Some(frame)
+    } else {
+        None
+    }
+}
+
+struct Registers {
+    pc: usize,
+    sp: usize,
+    fp: usize,
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn registers(context: *const libc::ucontext_t) -> Option<Registers> {
+    let gregs = &(*context).uc_mcontext.gregs;
+    Some(Registers {
+        pc: gregs[libc::REG_RIP as usize] as usize,
+        sp: gregs[libc::REG_RSP as usize] as usize,
+        fp: gregs[libc::REG_RBP as usize] as usize,
+    })
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn registers(context: *const libc::ucontext_t) -> Option<Registers> {
+    let mcontext = &(*context).uc_mcontext;
+    Some(Registers {
+        pc: mcontext.pc as usize,
+        sp: mcontext.sp as usize,
+        fp: mcontext.regs[29] as usize,
+    })
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+unsafe fn registers(_: *const libc::ucontext_t) -> Option<Registers> {
+    None
+}
+
+fn sanitize(name: &str) -> String {
+    name.replace([';', '\n'], ":")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::hint;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    #[inline(never)]
+    fn cpu_profile_spin(done: &AtomicBool) {
+        while !done.load(Ordering::Relaxed) {
+            hint::spin_loop();
+        }
+    }
+
+    #[test]
+    fn profile_busy_thread() {
+        let done = Arc::new(AtomicBool::new(false));
+        let thread = thread::Builder::new()
+            .name("profile-spin".to_string())
+            .spawn({
+                let done = done.clone();
+                move || cpu_profile_spin(&done)
+            })
+            .unwrap();
+
+        let profile = profile(Duration::from_millis(500));
+        done.store(true, Ordering::Relaxed);
+        thread.join().unwrap();
+
+        let profile = profile.unwrap();
+        assert!(
+            profile
+                .lines()
+                .any(|l| l.starts_with("profile-spin;") && l.contains("cpu_profile_spin")),
+            "{profile}"
+        );
+    }
+}
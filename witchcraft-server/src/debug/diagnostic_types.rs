@@ -12,15 +12,23 @@ use std::sync::Weak;
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use crate::debug::{Diagnostic, DiagnosticRegistry};
+use crate::debug::{
+    Diagnostic, DiagnosticBody, DiagnosticParam, DiagnosticParams, DiagnosticRegistry,
+};
 use bytes::Bytes;
 use conjure_error::Error;
 use conjure_serde::json;
 use http::HeaderValue;
+use serde::Serialize;
+
+const DETAILED: &str = "detailed";
 
 const DIAGNOSTIC_TYPES_V1: &str = "diagnostic.types.v1";
 
 /// A diagnostic which returns a list of all registered diagnostics.
+///
+/// If the `detailed` parameter is set, each entry is instead an object containing the diagnostic's type and the
+/// parameters it accepts.
 pub struct DiagnosticTypesDiagnostic {
     registry: Weak<DiagnosticRegistry>,
 }
@@ -29,6 +37,33 @@ impl DiagnosticTypesDiagnostic {
     pub fn new(registry: Weak<DiagnosticRegistry>) -> Self {
         DiagnosticTypesDiagnostic { registry }
     }
+
+    fn types(&self) -> Vec<DiagnosticType> {
+        let mut types = vec![DiagnosticType {
+            type_: DIAGNOSTIC_TYPES_V1.to_string(),
+            params: self.params(),
+        }];
+        if let Some(registry) = self.registry.upgrade() {
+            let diagnostics = registry
+                .diagnostics
+                .lock()
+                .iter()
+                .filter(|(type_, _)| *type_ != DIAGNOSTIC_TYPES_V1)
+                .map(|(type_, diagnostic)| (type_.clone(), diagnostic.clone()))
+                .collect::<Vec<_>>();
+            types.extend(
+                diagnostics
+                    .into_iter()
+                    .map(|(type_, diagnostic)| DiagnosticType {
+                        type_,
+                        params: diagnostic.params(),
+                    }),
+            );
+        }
+        types.sort_unstable_by(|a, b| a.type_.cmp(&b.type_));
+
+        types
+    }
 }
 
 impl Diagnostic for DiagnosticTypesDiagnostic {
@@ -44,13 +79,32 @@ impl Diagnostic for DiagnosticTypesDiagnostic {
         true
     }
 
+    fn params(&self) -> Vec<DiagnosticParam> {
+        vec![DiagnosticParam::new(
+            DETAILED,
+            "If true, describe the parameters accepted by each diagnostic. Defaults to false.",
+        )]
+    }
+
     fn result(&self) -> Result<Bytes, Error> {
-        let mut types: Vec<String> = Vec::new();
-        types.push(DIAGNOSTIC_TYPES_V1.to_string());
-        if let Some(registry) = self.registry.upgrade() {
-            types.extend(registry.diagnostics.lock().keys().cloned());
+        let types = self.types();
+        let body = json::to_vec(&types.iter().map(|t| &t.type_).collect::<Vec<_>>()).unwrap();
+        Ok(Bytes::from(body))
+    }
+
+    fn result_with_params(&self, params: &DiagnosticParams) -> Result<DiagnosticBody, Error> {
+        if !params.parse(DETAILED)?.unwrap_or(false) {
+            return self.result().map(DiagnosticBody::Fixed);
         }
-        types.sort_unstable();
-        Ok(Bytes::from(json::to_vec(&types).unwrap()).clone())
+
+        let body = json::to_vec(&self.types()).unwrap();
+        Ok(DiagnosticBody::Fixed(Bytes::from(body)))
     }
 }
+
+#[derive(Serialize)]
+struct DiagnosticType {
+    #[serde(rename = "type")]
+    type_: String,
+    params: Vec<DiagnosticParam>,
+}
@@ -11,20 +11,39 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use crate::debug::DiagnosticRegistry;
+use crate::debug::{DiagnosticBody, DiagnosticParams, DiagnosticRegistry};
+use crate::extensions::{AuditLogEntry, PeerAddr};
+use crate::logging;
+use crate::logging::api::{AuditLogV3, AuditProducer, AuditResult, UserId};
+use crate::service::active_requests::{ActiveRequests, CancelOutcome};
+use crate::service::request_id::RequestId;
+use crate::service::unverified_jwt::UnverifiedJwt;
 use bytes::Bytes;
-use conjure_error::{Error, NotFound, PermissionDenied};
-use conjure_http::server::{AsyncResponseBody, AsyncSerializeResponse, ConjureRuntime};
+use conjure_error::{Conflict, Error, InvalidArgument, NotFound, PermissionDenied};
+use conjure_http::server::{
+    AsyncResponseBody, AsyncSerializeResponse, AsyncWriteBody, BoxAsyncWriteBody, ConjureRuntime,
+    FromStrDecoder, FromStrOptionDecoder, RequestContext,
+};
 use conjure_http::{conjure_endpoints, endpoint};
 use conjure_object::BearerToken;
+use conjure_object::{Utc, Uuid};
+use futures_util::{Stream, StreamExt};
 use http::header::{HeaderName, CONTENT_TYPE};
 use http::{HeaderMap, HeaderValue, Response};
 use refreshable::Refreshable;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use subtle::ConstantTimeEq;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio::task;
+use witchcraft_log::{info, LevelFilter};
+use witchcraft_server_config::install::InstallConfig;
 use witchcraft_server_config::runtime::RuntimeConfig;
 
+const DEFAULT_LOG_LEVEL_TTL: Duration = Duration::from_secs(10 * 60);
+const MAX_LOG_LEVEL_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
 #[allow(clippy::declare_interior_mutable_const)]
 const SAFE_LOGGABLE: HeaderName = HeaderName::from_static("safe-loggable");
 #[allow(clippy::declare_interior_mutable_const)]
@@ -33,30 +52,66 @@ const TRUE_VALUE: HeaderValue = HeaderValue::from_static("true");
 const FALSE_VALUE: HeaderValue = HeaderValue::from_static("false");
 
 #[conjure_endpoints]
-pub trait DebugService {
+pub trait DebugService<#[response_writer] O>
+where
+    O: AsyncWrite + Send,
+{
     #[endpoint(path = "/debug/diagnostic/{diagnostic_type}", method = GET, produces = DiagnosticResponseSerializer)]
     async fn diagnostic(
         &self,
         #[auth] token: BearerToken,
         #[path(safe)] diagnostic_type: String,
+        #[context] context: RequestContext<'_>,
     ) -> Result<DiagnosticResponse, Error>;
 }
 
+#[conjure_endpoints]
+pub trait RequestCancellationService {
+    #[endpoint(path = "/debug/requests/{request_id}/cancel", method = POST)]
+    async fn cancel_request(
+        &self,
+        #[auth] token: BearerToken,
+        #[path(safe)] request_id: String,
+    ) -> Result<(), Error>;
+}
+
+#[conjure_endpoints]
+pub trait LogLevelService {
+    #[endpoint(path = "/debug/logging/level", method = POST)]
+    async fn set_log_level(
+        &self,
+        #[auth] token: BearerToken,
+        #[query(name = "level", decoder = FromStrDecoder, safe)] level: String,
+        #[query(name = "target", decoder = FromStrOptionDecoder, safe)] target: Option<String>,
+        #[query(name = "ttl", decoder = FromStrOptionDecoder, safe)] ttl: Option<String>,
+        #[context] context: RequestContext<'_>,
+    ) -> Result<(), Error>;
+}
+
 pub struct DiagnosticResponse {
     conent_type: HeaderValue,
     safe_loggable: bool,
-    body: Bytes,
+    body: DiagnosticBody,
 }
 
 enum DiagnosticResponseSerializer {}
 
-impl<W> AsyncSerializeResponse<DiagnosticResponse, W> for DiagnosticResponseSerializer {
+impl<W> AsyncSerializeResponse<DiagnosticResponse, W> for DiagnosticResponseSerializer
+where
+    W: AsyncWrite + Send,
+{
     fn serialize(
         _: &ConjureRuntime,
         _: &HeaderMap,
         value: DiagnosticResponse,
     ) -> Result<Response<AsyncResponseBody<W>>, Error> {
-        let mut response = Response::new(AsyncResponseBody::Fixed(value.body));
+        let body = match value.body {
+            DiagnosticBody::Fixed(body) => AsyncResponseBody::Fixed(body),
+            DiagnosticBody::Streaming(stream) => {
+                AsyncResponseBody::Streaming(BoxAsyncWriteBody::new(StreamingBody(stream)))
+            }
+        };
+        let mut response = Response::new(body);
         response
             .headers_mut()
             .insert(CONTENT_TYPE, value.conent_type);
@@ -71,6 +126,21 @@ impl<W> AsyncSerializeResponse<DiagnosticResponse, W> for DiagnosticResponseSeri
     }
 }
 
+struct StreamingBody(Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>);
+
+impl<W> AsyncWriteBody<W> for StreamingBody
+where
+    W: AsyncWrite + Send,
+{
+    async fn write_body(mut self, mut w: Pin<&mut W>) -> Result<(), Error> {
+        while let Some(chunk) = self.0.next().await {
+            w.write_all(&chunk?).await.map_err(Error::internal_safe)?;
+        }
+
+        Ok(())
+    }
+}
+
 pub struct DebugResource {
     debug_secret: Refreshable<String, Error>,
     diagnostics: Arc<DiagnosticRegistry>,
@@ -89,19 +159,32 @@ impl DebugResource {
     }
 }
 
-impl DebugService for DebugResource {
+fn check_secret(
+    debug_secret: &Refreshable<String, Error>,
+    token: &BearerToken,
+) -> Result<(), Error> {
+    let expected = debug_secret.get();
+    if !bool::from(token.as_str().as_bytes().ct_eq(expected.as_bytes())) {
+        return Err(Error::service_safe(
+            "invalid diagnostic check secret",
+            PermissionDenied::new(),
+        ));
+    }
+
+    Ok(())
+}
+
+impl<O> DebugService<O> for DebugResource
+where
+    O: AsyncWrite + Send,
+{
     async fn diagnostic(
         &self,
         token: BearerToken,
         diagnostic_type: String,
+        context: RequestContext<'_>,
     ) -> Result<DiagnosticResponse, Error> {
-        let expected = self.debug_secret.get();
-        if !bool::from(token.as_str().as_bytes().ct_eq(expected.as_bytes())) {
-            return Err(Error::service_safe(
-                "invalid diagnostic check secret",
-                PermissionDenied::new(),
-            ));
-        }
+        check_secret(&self.debug_secret, &token)?;
 
         let diagnostic = match self.diagnostics.get(&diagnostic_type) {
             Some(diagnostic) => diagnostic,
@@ -113,9 +196,22 @@ impl DebugService for DebugResource {
             }
         };
 
+        let params = DiagnosticParams::from_query(context.request_uri().query().unwrap_or(""));
+        let supported = diagnostic.params();
+        if let Some(name) = params
+            .names()
+            .find(|name| !supported.iter().any(|p| p.name() == *name))
+        {
+            return Err(Error::service_safe(
+                "unsupported diagnostic parameter",
+                InvalidArgument::new(),
+            )
+            .with_safe_param("parameter", name));
+        }
+
         let body = task::spawn_blocking({
             let diagnostic = diagnostic.clone();
-            move || diagnostic.result()
+            move || diagnostic.result_with_params(&params)
         })
         .await
         .unwrap()?;
@@ -127,3 +223,160 @@ impl DebugService for DebugResource {
         })
     }
 }
+
+pub struct RequestCancellationResource {
+    debug_secret: Refreshable<String, Error>,
+    requests: Arc<ActiveRequests>,
+}
+
+impl RequestCancellationResource {
+    pub fn new<R>(runtime: &Refreshable<R, Error>, requests: &Arc<ActiveRequests>) -> Self
+    where
+        R: AsRef<RuntimeConfig> + PartialEq + 'static + Sync + Send,
+    {
+        RequestCancellationResource {
+            debug_secret: runtime
+                .map(|c| c.as_ref().diagnostics().debug_shared_secret().to_string()),
+            requests: requests.clone(),
+        }
+    }
+}
+
+impl RequestCancellationService for RequestCancellationResource {
+    async fn cancel_request(&self, token: BearerToken, request_id: String) -> Result<(), Error> {
+        check_secret(&self.debug_secret, &token)?;
+
+        let parsed_request_id = request_id.parse::<RequestId>().map_err(|e| {
+            Error::service_safe(e, InvalidArgument::new()).with_safe_param("requestId", &request_id)
+        })?;
+
+        match self.requests.cancel(&parsed_request_id) {
+            CancelOutcome::Cancelled => {}
+            CancelOutcome::NotFound => {
+                return Err(
+                    Error::service_safe("active request not found", NotFound::new())
+                        .with_safe_param("requestId", request_id),
+                );
+            }
+            CancelOutcome::Responded => {
+                return Err(Error::service_safe(
+                    "active request has already produced a response",
+                    Conflict::new(),
+                )
+                .with_safe_param("requestId", request_id));
+            }
+        }
+
+        info!("cancelled active request", safe: { requestId: request_id });
+        Ok(())
+    }
+}
+
+pub struct LogLevelResource {
+    debug_secret: Refreshable<String, Error>,
+    deployment: String,
+    host: String,
+    product: String,
+    product_version: String,
+}
+
+impl LogLevelResource {
+    pub fn new<R>(install: &InstallConfig, runtime: &Refreshable<R, Error>) -> Self
+    where
+        R: AsRef<RuntimeConfig> + PartialEq + 'static + Sync + Send,
+    {
+        LogLevelResource {
+            debug_secret: runtime
+                .map(|c| c.as_ref().diagnostics().debug_shared_secret().to_string()),
+            deployment: install.deployment().unwrap_or("unknown").to_string(),
+            host: nix::unistd::gethostname()
+                .ok()
+                .and_then(|h| h.into_string().ok())
+                .unwrap_or_else(|| "unknown".to_string()),
+            product: install.product_name().to_string(),
+            product_version: install.product_version().to_string(),
+        }
+    }
+}
+
+impl LogLevelService for LogLevelResource {
+    async fn set_log_level(
+        &self,
+        token: BearerToken,
+        level: String,
+        target: Option<String>,
+        ttl: Option<String>,
+        context: RequestContext<'_>,
+    ) -> Result<(), Error> {
+        check_secret(&self.debug_secret, &token)?;
+
+        let parsed_level = level.parse::<LevelFilter>().map_err(|e| {
+            Error::service_safe(e, InvalidArgument::new()).with_safe_param("level", &level)
+        })?;
+
+        let ttl = match ttl {
+            Some(ttl) => ttl
+                .parse::<humantime::Duration>()
+                .map_err(|e| {
+                    Error::service_safe(e, InvalidArgument::new()).with_safe_param("ttl", &ttl)
+                })?
+                .into(),
+            None => DEFAULT_LOG_LEVEL_TTL,
+        };
+        if ttl.is_zero() || ttl > MAX_LOG_LEVEL_TTL {
+            return Err(
+                Error::service_safe("invalid log level TTL", InvalidArgument::new())
+                    .with_safe_param("ttl", humantime::format_duration(ttl).to_string())
+                    .with_safe_param(
+                        "maxTtl",
+                        humantime::format_duration(MAX_LOG_LEVEL_TTL).to_string(),
+                    ),
+            );
+        }
+
+        let result = logging::set_temporary_level(target.clone(), parsed_level, ttl);
+
+        let extensions = context.request_extensions();
+        let mut log = AuditLogV3::builder()
+            .type_("audit.3")
+            .deployment(&*self.deployment)
+            .host(&*self.host)
+            .product(&*self.product)
+            .product_version(&*self.product_version)
+            .producer_type(AuditProducer::Server)
+            .event_id(Uuid::new_v4())
+            .time(Utc::now())
+            .name("SET_LOG_LEVEL")
+            .result(match result {
+                Ok(()) => AuditResult::Success,
+                Err(_) => AuditResult::Error,
+            })
+            .uid(
+                extensions
+                    .get::<UnverifiedJwt>()
+                    .map(|jwt| UserId(jwt.unverified_user_id().to_string())),
+            )
+            .origin(
+                extensions
+                    .get::<PeerAddr>()
+                    .map(|addr| addr.ip().to_string()),
+            )
+            .insert_request_fields("level", &level)
+            .insert_request_fields("ttl", humantime::format_duration(ttl).to_string());
+        if let Some(target) = &target {
+            log = log.insert_request_fields("target", target);
+        }
+        logging::audit_log(AuditLogEntry::v3(log.build())).await?;
+        result?;
+
+        info!(
+            "temporarily overrode log level",
+            safe: {
+                level: level,
+                target: target,
+                ttl: humantime::format_duration(ttl).to_string(),
+            },
+        );
+        Ok(())
+    }
+}
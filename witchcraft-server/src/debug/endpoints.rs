@@ -0,0 +1,97 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::debug::Diagnostic;
+use crate::endpoint::inventory::{EndpointInfo, EndpointInventory};
+use bytes::Bytes;
+use conjure_error::Error;
+use conjure_serde::json;
+use http::HeaderValue;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// A diagnostic which returns a JSON description of every endpoint registered with the server.
+pub struct EndpointsDiagnostic {
+    inventory: Arc<EndpointInventory>,
+}
+
+impl EndpointsDiagnostic {
+    pub fn new(inventory: &Arc<EndpointInventory>) -> Self {
+        EndpointsDiagnostic {
+            inventory: inventory.clone(),
+        }
+    }
+}
+
+impl Diagnostic for EndpointsDiagnostic {
+    fn type_(&self) -> &str {
+        "server.endpoints.v1"
+    }
+
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static("application/json")
+    }
+
+    fn safe_loggable(&self) -> bool {
+        true
+    }
+
+    fn result(&self) -> Result<Bytes, Error> {
+        let mut endpoints = self
+            .inventory
+            .snapshot()
+            .iter()
+            .map(EndpointResponse::new)
+            .collect::<Vec<_>>();
+        endpoints.sort_by(|a, b| {
+            (a.listener, &a.template, &a.method).cmp(&(b.listener, &b.template, &b.method))
+        });
+
+        Ok(Bytes::from(json::to_vec(&endpoints).unwrap()))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EndpointResponse {
+    service_name: String,
+    endpoint_name: String,
+    method: String,
+    template: String,
+    path_prefix: String,
+    deprecated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deprecation_message: Option<String>,
+    listener: &'static str,
+    blocking: bool,
+    metrics: bool,
+    health: bool,
+}
+
+impl EndpointResponse {
+    fn new(info: &EndpointInfo) -> Self {
+        EndpointResponse {
+            service_name: info.service_name.clone(),
+            endpoint_name: info.name.clone(),
+            method: info.method.to_string(),
+            template: info.template.clone(),
+            path_prefix: info.path_prefix.clone(),
+            deprecated: info.deprecated.is_some(),
+            deprecation_message: info.deprecated.clone(),
+            listener: info.listener.tag(),
+            blocking: info.blocking,
+            metrics: info.metrics,
+            health: info.health,
+        }
+    }
+}
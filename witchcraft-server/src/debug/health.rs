@@ -0,0 +1,86 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::debug::Diagnostic;
+use crate::health::api::CheckType;
+use crate::health::{HealthCheckRegistry, HealthState};
+use bytes::Bytes;
+use conjure_error::Error;
+use conjure_object::{DateTime, Utc};
+use conjure_serde::json;
+use http::HeaderValue;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A diagnostic which returns a JSON description of the recent state transitions of each health check.
+///
+/// Transitions are ordered from oldest to newest, and only the most recent are retained. Since they include check
+/// messages, the diagnostic is not safe to log.
+pub struct HealthHistoryDiagnostic {
+    health_checks: Arc<HealthCheckRegistry>,
+}
+
+impl HealthHistoryDiagnostic {
+    pub fn new(health_checks: &Arc<HealthCheckRegistry>) -> Self {
+        HealthHistoryDiagnostic {
+            health_checks: health_checks.clone(),
+        }
+    }
+}
+
+impl Diagnostic for HealthHistoryDiagnostic {
+    fn type_(&self) -> &str {
+        "health.check.history.v1"
+    }
+
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static("application/json")
+    }
+
+    fn safe_loggable(&self) -> bool {
+        false
+    }
+
+    fn result(&self) -> Result<Bytes, Error> {
+        let history = self
+            .health_checks
+            .transitions()
+            .into_iter()
+            .map(|(type_, transitions)| {
+                let transitions = transitions
+                    .into_iter()
+                    .map(|t| TransitionInfo {
+                        time: t.time,
+                        old_state: t.old_state,
+                        new_state: t.new_state,
+                        message: t.message,
+                    })
+                    .collect();
+                (type_, transitions)
+            })
+            .collect::<BTreeMap<CheckType, Vec<_>>>();
+
+        Ok(Bytes::from(json::to_vec(&history).unwrap()))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransitionInfo {
+    time: DateTime<Utc>,
+    old_state: HealthState,
+    new_state: HealthState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
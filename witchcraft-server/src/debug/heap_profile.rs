@@ -0,0 +1,387 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::debug::pprof::Profile;
+use crate::debug::Diagnostic;
+use crate::minidump::modules::LoadedModules;
+use crate::minidump::symbol_provider::{Arena, WitchcraftSymbolProvider};
+use bytes::Bytes;
+use conjure_error::{Error, FailedPrecondition};
+use http::HeaderValue;
+use minidump::Module;
+use refreshable::{Refreshable, Subscription};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::time::SystemTime;
+use symbolic::common::ByteView;
+use tempfile::NamedTempFile;
+use tikv_jemalloc_ctl::{profiling, raw};
+use witchcraft_log::warn;
+
+/// A diagnostic which returns a heap profile of the server in the gzip-compressed [pprof] format.
+///
+/// The profile is generated from jemalloc's allocation sampling, which must be enabled when the process starts (e.g.
+/// by setting the `MALLOC_CONF` environment variable to `prof:true`). Sampling can be paused and resumed at runtime
+/// with the `diagnostics.heap-profiling-active` field of the runtime configuration.
+///
+/// Requires jemalloc, and is only supported on Linux.
+///
+/// [pprof]: https://github.com/google/pprof
+pub struct HeapProfileDiagnostic {
+    _subscription: Subscription<Option<bool>, Error>,
+}
+
+impl HeapProfileDiagnostic {
+    pub fn new(active: &Refreshable<Option<bool>, Error>) -> Self {
+        let subscription = active.subscribe(|active| {
+            let Some(active) = *active else {
+                return;
+            };
+
+            if let Err(e) = set_active(active) {
+                warn!("unable to update jemalloc heap profiling state", error: e);
+            }
+        });
+
+        HeapProfileDiagnostic {
+            _subscription: subscription,
+        }
+    }
+}
+
+impl Diagnostic for HeapProfileDiagnostic {
+    fn type_(&self) -> &str {
+        "rust.heap.profile.v1"
+    }
+
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static("application/octet-stream")
+    }
+
+    fn safe_loggable(&self) -> bool {
+        true
+    }
+
+    fn result(&self) -> Result<Bytes, Error> {
+        if !profiling::prof::read().map_err(Error::internal_safe)? {
+            return Err(Error::service_safe(
+                "jemalloc heap profiling is not enabled",
+                FailedPrecondition::new(),
+            ));
+        }
+
+        let target_file = NamedTempFile::new_in("var/data/tmp").map_err(Error::internal_safe)?;
+        let path = CString::new(target_file.path().as_os_str().as_bytes()).unwrap();
+        unsafe { raw::write(b"prof.dump\0", path.as_ptr()) }.map_err(Error::internal_safe)?;
+        let dump = fs::read_to_string(target_file.path()).map_err(Error::internal_safe)?;
+
+        let dump = parse(&dump)?;
+        let profile = build_profile(&dump);
+
+        Ok(Bytes::from(profile))
+    }
+}
+
+fn set_active(active: bool) -> Result<(), Error> {
+    if !profiling::prof::read().map_err(Error::internal_safe)? {
+        return Err(Error::internal_safe(
+            "jemalloc heap profiling is not enabled",
+        ));
+    }
+
+    unsafe { raw::write(b"prof.active\0", active) }.map_err(Error::internal_safe)
+}
+
+#[derive(Debug, PartialEq)]
+struct HeapDump {
+    period: u64,
+    samples: Vec<Sample>,
+    mappings: Vec<Mapping>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Sample {
+    addresses: Vec<u64>,
+    objects: u64,
+    bytes: u64,
+}
+
+#[derive(Debug, PartialEq)]
+struct Mapping {
+    start: u64,
+    end: u64,
+    offset: u64,
+    executable: bool,
+    path: String,
+}
+
+// See the HEAP PROFILE FORMAT section of jemalloc's documentation for details.
+fn parse(dump: &str) -> Result<HeapDump, Error> {
+    let invalid = || Error::internal_safe("invalid jemalloc heap profile");
+
+    let mut lines = dump.lines();
+    let period = lines
+        .next()
+        .and_then(|l| l.strip_prefix("heap_v2/"))
+        .and_then(|p| p.parse().ok())
+        .ok_or_else(invalid)?;
+
+    let mut samples = vec![];
+    let mut addresses = None;
+    for line in lines.by_ref() {
+        if line == "MAPPED_LIBRARIES:" {
+            break;
+        }
+
+        if let Some(backtrace) = line.strip_prefix('@') {
+            let backtrace = backtrace
+                .split_whitespace()
+                .map(|a| u64::from_str_radix(a.trim_start_matches("0x"), 16))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?;
+            addresses = Some(backtrace);
+        } else if let Some(counts) = line.trim_start().strip_prefix("t*:") {
+            // Skip the aggregate line preceding the first backtrace.
+            let Some(addresses) = addresses.take() else {
+                continue;
+            };
+
+            let (objects, rest) = counts.split_once(':').ok_or_else(invalid)?;
+            let bytes = rest.split_whitespace().next().ok_or_else(invalid)?;
+            samples.push(Sample {
+                addresses,
+                objects: objects.trim().parse().map_err(|_| invalid())?,
+                bytes: bytes.parse().map_err(|_| invalid())?,
+            });
+        }
+    }
+
+    let mappings = lines.filter_map(parse_mapping).collect();
+
+    Ok(HeapDump {
+        period,
+        samples,
+        mappings,
+    })
+}
+
+// The mapped libraries section has the same format as /proc/self/maps.
+fn parse_mapping(line: &str) -> Option<Mapping> {
+    let mut parts = line.split_whitespace();
+    let (start, end) = parts.next()?.split_once('-')?;
+    let perms = parts.next()?;
+    let offset = parts.next()?;
+    let path = parts.nth(2)?;
+    if !path.starts_with('/') {
+        return None;
+    }
+
+    Some(Mapping {
+        start: u64::from_str_radix(start, 16).ok()?,
+        end: u64::from_str_radix(end, 16).ok()?,
+        offset: u64::from_str_radix(offset, 16).ok()?,
+        executable: perms.contains('x'),
+        path: path.to_string(),
+    })
+}
+
+fn build_profile(dump: &HeapDump) -> Vec<u8> {
+    let mut profile = Profile::new(
+        &[("inuse_objects", "count"), ("inuse_space", "bytes")],
+        ("space", "bytes"),
+        dump.period as i64,
+    );
+    if let Ok(time) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        profile.set_time_nanos(time.as_nanos() as i64);
+    }
+
+    let arena = Arena::new();
+    let symbolizer = Symbolizer::new(&dump.mappings, &arena);
+    for (i, mapping) in symbolizer.mappings.iter().enumerate() {
+        profile.add_mapping(
+            i as u64 + 1,
+            mapping.start,
+            mapping.end,
+            mapping.offset,
+            &mapping.path,
+            &symbolizer.build_id(mapping),
+        );
+    }
+
+    let mut locations = HashMap::new();
+    for sample in &dump.samples {
+        let location_ids = sample
+            .addresses
+            .iter()
+            .map(|address| {
+                let next_id = locations.len() as u64 + 1;
+                match locations.entry(*address) {
+                    Entry::Occupied(e) => *e.get(),
+                    Entry::Vacant(e) => {
+                        symbolizer.add_location(&mut profile, next_id, *address);
+                        *e.insert(next_id)
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let (objects, bytes) = unsample(sample, dump.period);
+        profile.add_sample(&location_ids, &[objects, bytes]);
+    }
+
+    profile.finish()
+}
+
+// jemalloc samples allocations with a probability dependent on their size, so we need to scale the sampled counts to
+// estimate the true values. This matches the logic in jeprof.
+fn unsample(sample: &Sample, period: u64) -> (i64, i64) {
+    if sample.objects == 0 || period == 0 {
+        return (sample.objects as i64, sample.bytes as i64);
+    }
+
+    let ratio = sample.bytes as f64 / sample.objects as f64 / period as f64;
+    let scale = 1. / (1. - (-ratio).exp());
+
+    (
+        (sample.objects as f64 * scale).round() as i64,
+        (sample.bytes as f64 * scale).round() as i64,
+    )
+}
+
+// The dump describes the current process, so its addresses can be symbolized against the modules loaded into it.
+struct Symbolizer<'a> {
+    mappings: Vec<&'a Mapping>,
+    modules: LoadedModules,
+    symbol_provider: WitchcraftSymbolProvider<'a>,
+}
+
+impl<'a> Symbolizer<'a> {
+    fn new(mappings: &'a [Mapping], arena: &'a Arena<ByteView<'a>>) -> Self {
+        Symbolizer {
+            mappings: mappings.iter().filter(|m| m.executable).collect(),
+            modules: LoadedModules::new(),
+            symbol_provider: WitchcraftSymbolProvider::new(arena),
+        }
+    }
+
+    fn build_id(&self, mapping: &Mapping) -> String {
+        self.modules
+            .find(mapping.start)
+            .and_then(|m| m.code_identifier())
+            .map_or_else(String::new, |id| id.to_string())
+    }
+
+    fn add_location(&self, profile: &mut Profile, id: u64, address: u64) {
+        let Some(i) = self
+            .mappings
+            .iter()
+            .position(|m| m.start <= address && address < m.end)
+        else {
+            profile.add_location(id, 0, address, &[]);
+            return;
+        };
+
+        // The addresses are return addresses, so we look up the preceding instruction to find the call site.
+        let symbols = address
+            .checked_sub(1)
+            .and_then(|probe| Some((self.modules.find(probe)?, probe)))
+            .map(|(module, probe)| self.symbol_provider.symbolize(module, probe))
+            .unwrap_or_default();
+
+        // pprof orders inlined functions from innermost to outermost.
+        let lines = symbols
+            .iter()
+            .rev()
+            .map(|symbol| {
+                (
+                    profile.function(&symbol.function, symbol.file.as_deref().unwrap_or("")),
+                    i64::from(symbol.line.unwrap_or(0)),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        profile.add_location(id, i as u64 + 1, address, &lines);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_dump() {
+        let dump = "\
+heap_v2/524288
+  t*: 28: 56637512 [0: 0]
+  t0: 20: 56000000 [0: 0]
+@ 0x55d4d2d1c4a1 0x55d4d2d1c3f0 0x7f1a2b3c4d5e
+  t*: 13: 6688 [0: 0]
+  t0: 13: 6688 [0: 0]
+@ 0x55d4d2d1c4a1
+  t*: 15: 56630824 [0: 0]
+
+MAPPED_LIBRARIES:
+55d4d2c00000-55d4d2d00000 r--p 00000000 08:01 1234 /opt/service/bin/server
+55d4d2d00000-55d4d2e00000 r-xp 00100000 08:01 1234 /opt/service/bin/server
+7ffd1a2b3000-7ffd1a2d4000 rw-p 00000000 00:00 0 [stack]
+";
+
+        let expected = HeapDump {
+            period: 524288,
+            samples: vec![
+                Sample {
+                    addresses: vec![0x55d4d2d1c4a1, 0x55d4d2d1c3f0, 0x7f1a2b3c4d5e],
+                    objects: 13,
+                    bytes: 6688,
+                },
+                Sample {
+                    addresses: vec![0x55d4d2d1c4a1],
+                    objects: 15,
+                    bytes: 56630824,
+                },
+            ],
+            mappings: vec![
+                Mapping {
+                    start: 0x55d4d2c00000,
+                    end: 0x55d4d2d00000,
+                    offset: 0,
+                    executable: false,
+                    path: "/opt/service/bin/server".to_string(),
+                },
+                Mapping {
+                    start: 0x55d4d2d00000,
+                    end: 0x55d4d2e00000,
+                    offset: 0x100000,
+                    executable: true,
+                    path: "/opt/service/bin/server".to_string(),
+                },
+            ],
+        };
+
+        assert_eq!(parse(dump).unwrap(), expected);
+    }
+
+    #[test]
+    fn unsample_large_allocations() {
+        let sample = Sample {
+            addresses: vec![],
+            objects: 1,
+            bytes: 100 * 524288,
+        };
+
+        assert_eq!(unsample(&sample, 524288), (1, 100 * 524288));
+    }
+}
@@ -11,17 +11,24 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use crate::debug::Diagnostic;
-use bytes::Bytes;
+use crate::debug::{Diagnostic, DiagnosticBody, DiagnosticParam, DiagnosticParams};
+use bytes::{BufMut, Bytes, BytesMut};
 use conjure_error::Error;
 use conjure_serde::json;
+use futures_util::stream;
 use http::HeaderValue;
-use serde::ser::{SerializeMap, SerializeSeq};
+use serde::ser::SerializeMap;
 use serde::{Serialize, Serializer};
 use std::sync::Arc;
-use witchcraft_metrics::{MetricId, MetricRegistry, Metrics, Tags};
+use std::vec;
+use witchcraft_metrics::{MetricId, MetricRegistry, Tags};
+
+const PREFIX: &str = "prefix";
+const CHUNK_SIZE: usize = 8 * 1024;
 
 /// A diagnostic which returns the JSON-formatted names of every metric in the server's registry.
+///
+/// The `prefix` parameter restricts the output to metrics with names starting with the prefix.
 pub struct MetricNamesDiagnostic {
     metrics: Arc<MetricRegistry>,
 }
@@ -32,6 +39,25 @@ impl MetricNamesDiagnostic {
             metrics: metrics.clone(),
         }
     }
+
+    // The matching IDs are collected up front, and only their JSON serialization is deferred until the client reads
+    // the response.
+    fn chunks(&self, prefix: &str) -> MetricNameChunks {
+        let ids = self
+            .metrics
+            .metrics()
+            .iter()
+            .map(|(id, _)| id)
+            .filter(|id| id.name().starts_with(prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        MetricNameChunks {
+            ids: ids.into_iter(),
+            state: State::Start,
+            separator: false,
+        }
+    }
 }
 
 impl Diagnostic for MetricNamesDiagnostic {
@@ -47,25 +73,69 @@ impl Diagnostic for MetricNamesDiagnostic {
         true
     }
 
+    fn params(&self) -> Vec<DiagnosticParam> {
+        vec![DiagnosticParam::new(
+            PREFIX,
+            "Only include metrics with names starting with this prefix.",
+        )]
+    }
+
     fn result(&self) -> Result<Bytes, Error> {
-        let metrics = self.metrics.metrics();
-        let body = json::to_vec(&MetricNames(metrics)).unwrap();
-        Ok(Bytes::from(body))
+        Ok(Bytes::from(self.chunks("").flatten().collect::<Vec<_>>()))
+    }
+
+    fn result_with_params(&self, params: &DiagnosticParams) -> Result<DiagnosticBody, Error> {
+        let chunks = self.chunks(params.get(PREFIX).unwrap_or(""));
+        Ok(DiagnosticBody::streaming(stream::iter(chunks.map(Ok))))
     }
 }
 
-struct MetricNames(Metrics);
+enum State {
+    Start,
+    Elements,
+    Done,
+}
 
-impl Serialize for MetricNames {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut s = serializer.serialize_seq(None)?;
-        for (id, _) in &self.0 {
-            s.serialize_element(&MetricName(id))?;
+/// Serializes a JSON array of metric names in chunks of roughly `CHUNK_SIZE` bytes.
+struct MetricNameChunks {
+    ids: vec::IntoIter<MetricId>,
+    state: State,
+    separator: bool,
+}
+
+impl Iterator for MetricNameChunks {
+    type Item = Bytes;
+
+    fn next(&mut self) -> Option<Bytes> {
+        let mut buf = BytesMut::new();
+
+        match self.state {
+            State::Start => {
+                buf.put_u8(b'[');
+                self.state = State::Elements;
+            }
+            State::Elements => {}
+            State::Done => return None,
         }
-        s.end()
+
+        while buf.len() < CHUNK_SIZE {
+            match self.ids.next() {
+                Some(id) => {
+                    if self.separator {
+                        buf.put_u8(b',');
+                    }
+                    self.separator = true;
+                    json::to_writer((&mut buf).writer(), &MetricName(&id)).unwrap();
+                }
+                None => {
+                    buf.put_u8(b']');
+                    self.state = State::Done;
+                    break;
+                }
+            }
+        }
+
+        Some(buf.freeze())
     }
 }
 
@@ -99,3 +169,48 @@ impl Serialize for MetricTags<'_> {
         s.end()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures_util::StreamExt;
+    use serde_json::Value;
+
+    async fn names(metrics: &Arc<MetricRegistry>, params: &DiagnosticParams) -> Value {
+        let DiagnosticBody::Streaming(body) = MetricNamesDiagnostic::new(metrics)
+            .result_with_params(params)
+            .unwrap()
+        else {
+            panic!("expected a streaming body");
+        };
+
+        let chunks = body.collect::<Vec<_>>().await;
+        let body = chunks
+            .into_iter()
+            .flat_map(|c| c.unwrap())
+            .collect::<Vec<_>>();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn chunked() {
+        let metrics = Arc::new(MetricRegistry::new());
+        for i in 0..1000 {
+            metrics.counter(MetricId::new(format!("foo.{i}")).with_tag("index", i.to_string()));
+        }
+        metrics.counter("bar");
+
+        let all = names(&metrics, &DiagnosticParams::new()).await;
+        assert_eq!(all.as_array().unwrap().len(), 1001);
+
+        let mut params = DiagnosticParams::new();
+        params.insert(PREFIX, "bar");
+        let filtered = names(&metrics, &params).await;
+        assert_eq!(filtered, serde_json::json!([{"name": "bar"}]));
+
+        let mut params = DiagnosticParams::new();
+        params.insert(PREFIX, "baz");
+        let empty = names(&metrics, &params).await;
+        assert_eq!(empty, serde_json::json!([]));
+    }
+}
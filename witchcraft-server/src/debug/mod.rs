@@ -14,26 +14,45 @@
 //! Debug endpoints.
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::error;
+use std::pin::Pin;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use bytes::Bytes;
-use conjure_error::Error;
+use conjure_error::{Error, InvalidArgument};
+use futures_util::Stream;
 use http::HeaderValue;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use regex::Regex;
+use serde::Serialize;
 
+pub(crate) mod active_requests;
+pub(crate) mod config;
+#[cfg(target_os = "linux")]
+pub(crate) mod cpu_profile;
 pub(crate) mod diagnostic_types;
 pub(crate) mod endpoint;
+pub(crate) mod endpoints;
+pub(crate) mod health;
+#[cfg(all(feature = "jemalloc", target_os = "linux"))]
+pub(crate) mod heap_profile;
 #[cfg(feature = "jemalloc")]
 pub(crate) mod heap_stats;
 pub(crate) mod metric_names;
+#[cfg(all(feature = "jemalloc", target_os = "linux"))]
+pub(crate) mod pprof;
+pub(crate) mod task_dump;
 #[cfg(target_os = "linux")]
 pub(crate) mod thread_dump;
 
 static TYPE_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"([a-z0-9]+\.)+v[0-9]+").unwrap());
 
 /// An SLS diagnostic. See the [SLS debug spec](https://github.palantir.build/deployability/sls-spec/blob/develop/docs/debug.md)
+///
+/// Simple diagnostics only need to implement [`Diagnostic::result`]. Diagnostics which accept query parameters or
+/// produce large outputs should also implement [`Diagnostic::params`] and [`Diagnostic::result_with_params`].
 pub trait Diagnostic {
     /// The type of the diagnostic. Must be lower cased, dot delimited, and end with a version.
     ///
@@ -48,8 +67,133 @@ pub trait Diagnostic {
     /// Whether the value is safe to log
     fn safe_loggable(&self) -> bool;
 
-    /// The bytes of the response to send
+    /// The bytes of the response to send.
     fn result(&self) -> Result<Bytes, Error>;
+
+    /// The query parameters accepted by the diagnostic.
+    ///
+    /// Requests containing any other parameters will be rejected. Defaults to none.
+    fn params(&self) -> Vec<DiagnosticParam> {
+        vec![]
+    }
+
+    /// The body of the response to send, given the request's query parameters.
+    ///
+    /// This is called from a blocking thread. Defaults to the value of [`Diagnostic::result`].
+    fn result_with_params(&self, params: &DiagnosticParams) -> Result<DiagnosticBody, Error> {
+        let _ = params;
+        self.result().map(DiagnosticBody::Fixed)
+    }
+}
+
+/// The body of a diagnostic response.
+pub enum DiagnosticBody {
+    /// A body which has been fully buffered in memory.
+    Fixed(Bytes),
+    /// A body which is written to the client incrementally as its chunks are produced.
+    Streaming(Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>),
+}
+
+impl DiagnosticBody {
+    /// Creates a streaming body.
+    pub fn streaming<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, Error>> + 'static + Send,
+    {
+        DiagnosticBody::Streaming(Box::pin(stream))
+    }
+}
+
+/// A description of a query parameter accepted by a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticParam {
+    name: String,
+    description: String,
+}
+
+impl DiagnosticParam {
+    /// Creates a new parameter description.
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        DiagnosticParam {
+            name: name.into(),
+            description: description.into(),
+        }
+    }
+
+    /// Returns the name of the parameter.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns a human-readable description of the parameter.
+    #[inline]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// The query parameters of a diagnostic request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticParams {
+    params: HashMap<String, Vec<String>>,
+}
+
+impl DiagnosticParams {
+    /// Creates an empty set of parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn from_query(query: &str) -> Self {
+        let mut params = Self::new();
+        for (name, value) in form_urlencoded::parse(query.as_bytes()) {
+            params.insert(name, value);
+        }
+        params
+    }
+
+    /// Adds a value for a parameter.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.params
+            .entry(name.into())
+            .or_default()
+            .push(value.into());
+    }
+
+    /// Returns the first value of a parameter.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.get_all(name).first().map(|s| &**s)
+    }
+
+    /// Returns all values of a parameter.
+    pub fn get_all(&self, name: &str) -> &[String] {
+        self.params.get(name).map_or(&[], |v| &**v)
+    }
+
+    /// Parses the first value of a parameter.
+    ///
+    /// Returns an `InvalidArgument` error if the value cannot be parsed.
+    pub fn parse<T>(&self, name: &str) -> Result<Option<T>, Error>
+    where
+        T: FromStr,
+        T::Err: Into<Box<dyn error::Error + Sync + Send>>,
+    {
+        let Some(value) = self.get(name) else {
+            return Ok(None);
+        };
+
+        value.parse().map(Some).map_err(|e| {
+            Error::service_safe(e, InvalidArgument::new())
+                .with_safe_param("parameter", name)
+                .with_unsafe_param("value", value)
+        })
+    }
+
+    /// Returns the names of all parameters.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.params.keys().map(|s| &**s)
+    }
 }
 
 /// A registry of diagnostics for the server.
@@ -109,3 +253,27 @@ impl DiagnosticRegistry {
         self.diagnostics.lock().get(type_).cloned()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn params() {
+        let params = DiagnosticParams::from_query("duration=30s&tag=a%3Db&tag=c&bogus=x");
+
+        assert_eq!(params.get("tag"), Some("a=b"));
+        assert_eq!(params.get_all("tag"), ["a=b", "c"]);
+        assert_eq!(params.get_all("missing"), [] as [String; 0]);
+        assert_eq!(
+            params
+                .parse::<humantime::Duration>("duration")
+                .unwrap()
+                .map(Duration::from),
+            Some(Duration::from_secs(30)),
+        );
+        assert_eq!(params.parse::<u32>("missing").unwrap(), None);
+        params.parse::<u32>("bogus").unwrap_err();
+    }
+}
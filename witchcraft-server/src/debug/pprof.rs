@@ -0,0 +1,240 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! A minimal encoder for the [pprof] profile format.
+//!
+//! [pprof]: https://github.com/google/pprof/blob/main/proto/profile.proto
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::io::Write;
+
+const VARINT: u64 = 0;
+const LENGTH_DELIMITED: u64 = 2;
+
+/// A pprof profile, encoded incrementally.
+///
+/// IDs of mappings, locations, and functions must be nonzero.
+pub struct Profile {
+    buf: Vec<u8>,
+    strings: Vec<String>,
+    string_ids: HashMap<String, i64>,
+    functions: HashMap<(i64, i64), u64>,
+}
+
+impl Profile {
+    pub fn new(sample_types: &[(&str, &str)], period_type: (&str, &str), period: i64) -> Self {
+        let mut profile = Profile {
+            buf: vec![],
+            strings: vec![],
+            string_ids: HashMap::new(),
+            functions: HashMap::new(),
+        };
+        // the first entry in the string table must be the empty string
+        profile.string("");
+
+        for (type_, unit) in sample_types {
+            let value_type = profile.value_type(type_, unit);
+            message(&mut profile.buf, 1, &value_type);
+        }
+
+        let period_type = profile.value_type(period_type.0, period_type.1);
+        message(&mut profile.buf, 11, &period_type);
+        int(&mut profile.buf, 12, period);
+
+        profile
+    }
+
+    fn string(&mut self, s: &str) -> i64 {
+        if let Some(id) = self.string_ids.get(s) {
+            return *id;
+        }
+
+        let id = self.strings.len() as i64;
+        self.strings.push(s.to_string());
+        self.string_ids.insert(s.to_string(), id);
+        id
+    }
+
+    fn value_type(&mut self, type_: &str, unit: &str) -> Vec<u8> {
+        let mut buf = vec![];
+        int(&mut buf, 1, self.string(type_));
+        int(&mut buf, 2, self.string(unit));
+        buf
+    }
+
+    pub fn set_time_nanos(&mut self, time_nanos: i64) {
+        int(&mut self.buf, 9, time_nanos);
+    }
+
+    pub fn add_mapping(
+        &mut self,
+        id: u64,
+        memory_start: u64,
+        memory_limit: u64,
+        file_offset: u64,
+        filename: &str,
+        build_id: &str,
+    ) {
+        let mut buf = vec![];
+        uint(&mut buf, 1, id);
+        uint(&mut buf, 2, memory_start);
+        uint(&mut buf, 3, memory_limit);
+        uint(&mut buf, 4, file_offset);
+        int(&mut buf, 5, self.string(filename));
+        int(&mut buf, 6, self.string(build_id));
+        message(&mut self.buf, 3, &buf);
+    }
+
+    /// Returns the ID of a function with the given name and source file, adding it if necessary.
+    pub fn function(&mut self, name: &str, filename: &str) -> u64 {
+        let key = (self.string(name), self.string(filename));
+        if let Some(id) = self.functions.get(&key) {
+            return *id;
+        }
+
+        let id = self.functions.len() as u64 + 1;
+        self.functions.insert(key, id);
+
+        let mut buf = vec![];
+        uint(&mut buf, 1, id);
+        int(&mut buf, 2, key.0);
+        int(&mut buf, 3, key.0);
+        int(&mut buf, 4, key.1);
+        message(&mut self.buf, 5, &buf);
+
+        id
+    }
+
+    /// Adds a location. Lines are `(function ID, line number)` pairs, ordered from innermost to outermost.
+    pub fn add_location(&mut self, id: u64, mapping_id: u64, address: u64, lines: &[(u64, i64)]) {
+        let mut buf = vec![];
+        uint(&mut buf, 1, id);
+        uint(&mut buf, 2, mapping_id);
+        uint(&mut buf, 3, address);
+        for (function_id, line) in lines {
+            let mut line_buf = vec![];
+            uint(&mut line_buf, 1, *function_id);
+            int(&mut line_buf, 2, *line);
+            message(&mut buf, 4, &line_buf);
+        }
+        message(&mut self.buf, 4, &buf);
+    }
+
+    /// Adds a sample. Locations are ordered from innermost to outermost.
+    pub fn add_sample(&mut self, location_ids: &[u64], values: &[i64]) {
+        let mut buf = vec![];
+        packed(&mut buf, 1, location_ids.iter().copied());
+        packed(&mut buf, 2, values.iter().map(|v| *v as u64));
+        message(&mut self.buf, 2, &buf);
+    }
+
+    /// Returns the gzip-compressed encoded profile.
+    pub fn finish(mut self) -> Vec<u8> {
+        for string in &self.strings {
+            bytes(&mut self.buf, 6, string.as_bytes());
+        }
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&self.buf).unwrap();
+        encoder.finish().unwrap()
+    }
+}
+
+fn varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn key(buf: &mut Vec<u8>, field: u64, wire_type: u64) {
+    varint(buf, (field << 3) | wire_type);
+}
+
+fn uint(buf: &mut Vec<u8>, field: u64, value: u64) {
+    if value != 0 {
+        key(buf, field, VARINT);
+        varint(buf, value);
+    }
+}
+
+fn int(buf: &mut Vec<u8>, field: u64, value: i64) {
+    uint(buf, field, value as u64);
+}
+
+fn bytes(buf: &mut Vec<u8>, field: u64, value: &[u8]) {
+    key(buf, field, LENGTH_DELIMITED);
+    varint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+fn message(buf: &mut Vec<u8>, field: u64, value: &[u8]) {
+    bytes(buf, field, value);
+}
+
+fn packed<I>(buf: &mut Vec<u8>, field: u64, values: I)
+where
+    I: IntoIterator<Item = u64>,
+{
+    let mut packed = vec![];
+    for value in values {
+        varint(&mut packed, value);
+    }
+    bytes(buf, field, &packed);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn encode() {
+        let mut profile = Profile::new(&[("space", "bytes")], ("space", "bytes"), 300);
+        let function = profile.function("foo", "");
+        assert_eq!(profile.function("foo", ""), function);
+        profile.add_location(1, 0, 0x10, &[(function, 2)]);
+        profile.add_sample(&[1], &[-1]);
+
+        let mut buf = vec![];
+        GzDecoder::new(&*profile.finish())
+            .read_to_end(&mut buf)
+            .unwrap();
+
+        #[rustfmt::skip]
+        let expected = [
+            // sample_type: {type: 1, unit: 2}
+            0x0a, 0x04, 0x08, 0x01, 0x10, 0x02,
+            // period_type: {type: 1, unit: 2}
+            0x5a, 0x04, 0x08, 0x01, 0x10, 0x02,
+            // period: 300
+            0x60, 0xac, 0x02,
+            // function: {id: 1, name: 3, system_name: 3}
+            0x2a, 0x06, 0x08, 0x01, 0x10, 0x03, 0x18, 0x03,
+            // location: {id: 1, address: 0x10, line: {function_id: 1, line: 2}}
+            0x22, 0x0a, 0x08, 0x01, 0x18, 0x10, 0x22, 0x04, 0x08, 0x01, 0x10, 0x02,
+            // sample: {location_id: [1], value: [-1]}
+            0x12, 0x0f, 0x0a, 0x01, 0x01, 0x12, 0x0a,
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01,
+            // string_table: ["", "space", "bytes", "foo"]
+            0x32, 0x00,
+            0x32, 0x05, b's', b'p', b'a', b'c', b'e',
+            0x32, 0x05, b'b', b'y', b't', b'e', b's',
+            0x32, 0x03, b'f', b'o', b'o',
+        ];
+        assert_eq!(buf, expected);
+    }
+}
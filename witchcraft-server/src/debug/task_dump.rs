@@ -0,0 +1,189 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::debug::Diagnostic;
+use crate::service::active_requests::{ActiveRequest, ActiveRequests};
+use bytes::Bytes;
+use conjure_error::Error;
+use http::HeaderValue;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task;
+
+/// A diagnostic which returns a description of each request currently being handled by the server.
+///
+/// Each entry includes the request's endpoint, trace ID, elapsed time, and the ID of the Tokio task handling it. If
+/// the server is built with `--cfg tokio_unstable --cfg tokio_taskdump` and Tokio's `taskdump` feature is enabled, it
+/// also includes a trace of the task's current await point. Task traces are only available on Linux x86, x86_64, and
+/// aarch64.
+pub struct TaskDumpDiagnostic {
+    requests: Arc<ActiveRequests>,
+}
+
+impl TaskDumpDiagnostic {
+    pub fn new(requests: &Arc<ActiveRequests>) -> Self {
+        TaskDumpDiagnostic {
+            requests: requests.clone(),
+        }
+    }
+}
+
+impl Diagnostic for TaskDumpDiagnostic {
+    fn type_(&self) -> &str {
+        "rust.task.dump.v1"
+    }
+
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static("text/plain")
+    }
+
+    fn safe_loggable(&self) -> bool {
+        true
+    }
+
+    fn result(&self) -> Result<Bytes, Error> {
+        let requests = self.requests.snapshot();
+        let traces = task_traces();
+
+        let now = Instant::now();
+        let mut buf = String::new();
+        for request in &requests {
+            format_request(&mut buf, request, now, &traces);
+        }
+
+        Ok(Bytes::from(buf))
+    }
+}
+
+fn format_request(
+    buf: &mut String,
+    request: &ActiveRequest,
+    now: Instant,
+    traces: &HashMap<task::Id, String>,
+) {
+    // millisecond precision is plenty and keeps the output readable
+    let elapsed = Duration::from_millis(now.duration_since(request.start).as_millis() as u64);
+
+    writeln!(buf, "Request {}", request.request_id).unwrap();
+    writeln!(
+        buf,
+        "  Endpoint: {}",
+        request.endpoint_name().as_deref().unwrap_or("<unmatched>")
+    )
+    .unwrap();
+    writeln!(buf, "  Trace ID: {}", request.trace_id).unwrap();
+    writeln!(buf, "  Elapsed: {elapsed:?}").unwrap();
+    if let Some(task_id) = request.task_id {
+        writeln!(buf, "  Task: {task_id}").unwrap();
+        if let Some(trace) = traces.get(&task_id) {
+            for line in trace.lines() {
+                writeln!(buf, "    {line}").unwrap();
+            }
+        }
+    }
+    writeln!(buf).unwrap();
+}
+
+// Tokio only supports task dumps on these platforms.
+#[cfg(all(
+    tokio_unstable,
+    tokio_taskdump,
+    target_os = "linux",
+    any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64")
+))]
+fn task_traces() -> HashMap<task::Id, String> {
+    use tokio::runtime::Handle;
+    use tokio::time;
+    use witchcraft_log::warn;
+
+    // Task dumps never complete if a worker thread is blocked, so we need to bound the time spent waiting.
+    const DUMP_TIMEOUT: Duration = Duration::from_secs(5);
+
+    let handle = Handle::current();
+    match handle.block_on(time::timeout(DUMP_TIMEOUT, handle.dump())) {
+        Ok(dump) => dump
+            .tasks()
+            .iter()
+            .map(|task| (task.id(), task.trace().to_string()))
+            .collect(),
+        Err(_) => {
+            warn!("timed out collecting Tokio task dump");
+            HashMap::new()
+        }
+    }
+}
+
+#[cfg(not(all(
+    tokio_unstable,
+    tokio_taskdump,
+    target_os = "linux",
+    any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64")
+)))]
+fn task_traces() -> HashMap<task::Id, String> {
+    HashMap::new()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::blocking::Cancellation;
+    use crate::service::request_id::RequestId;
+    use crate::service::test_util::TestEndpoint;
+    use conjure_object::Utc;
+    use http::Method;
+    use std::sync::atomic::{AtomicBool, AtomicU64};
+    use zipkin::TraceId;
+
+    #[test]
+    fn format() {
+        let request_id = RequestId::random();
+        let trace_id = TraceId::from([1; 8]);
+        let now = Instant::now();
+        let request = ActiveRequest {
+            request_id,
+            method: Method::GET,
+            endpoint: Some(Arc::new(TestEndpoint {
+                method: Method::GET,
+                path: vec![],
+                template: "/my/endpoint",
+                service_name: "MyService",
+                name: "myEndpoint",
+            })),
+            trace_id,
+            user_id: None,
+            peer: None,
+            start: now - Duration::from_millis(1500),
+            start_time: Utc::now(),
+            task_id: None,
+            bytes_read: Arc::new(AtomicU64::new(0)),
+            bytes_written: Arc::new(AtomicU64::new(0)),
+            cancellation: Cancellation::new(),
+            responded: Arc::new(AtomicBool::new(false)),
+        };
+
+        let mut buf = String::new();
+        format_request(&mut buf, &request, now, &HashMap::new());
+
+        let expected = format!(
+            "Request {request_id}
+  Endpoint: MyService.myEndpoint
+  Trace ID: 0101010101010101
+  Elapsed: 1.5s
+
+"
+        );
+        assert_eq!(buf, expected);
+    }
+}
@@ -97,6 +97,14 @@ impl WitchcraftEndpoint for ConjureEndpoint {
         self.health.as_ref()
     }
 
+    fn blocking(&self) -> bool {
+        false
+    }
+
+    fn path_prefix(&self) -> &str {
+        ""
+    }
+
     async fn handle(&self, req: Request<RawBody>) -> Response<BoxBody<Bytes, BodyWriteAborted>> {
         let req = req.map(RequestBody::new);
         let mut response_extensions = Extensions::new();
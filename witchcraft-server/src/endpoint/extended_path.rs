@@ -29,11 +29,12 @@ pub struct ExtendedPathEndpoint<T> {
     inner: T,
     path: Vec<PathSegment>,
     template: String,
+    path_prefix: String,
 }
 
 impl<T> ExtendedPathEndpoint<T>
 where
-    T: EndpointMetadata,
+    T: WitchcraftEndpoint,
 {
     pub fn new(inner: T, path_prefix: &str) -> Self {
         debug_assert!(path_prefix.starts_with('/') && !path_prefix.ends_with('/'));
@@ -47,11 +48,13 @@ where
             .collect();
 
         let template = format!("{path_prefix}{}", inner.template());
+        let path_prefix = format!("{path_prefix}{}", inner.path_prefix());
 
         ExtendedPathEndpoint {
             inner,
             path,
             template,
+            path_prefix,
         }
     }
 }
@@ -97,6 +100,14 @@ where
         self.inner.health()
     }
 
+    fn blocking(&self) -> bool {
+        self.inner.blocking()
+    }
+
+    fn path_prefix(&self) -> &str {
+        &self.path_prefix
+    }
+
     // manually implementing to avoid double boxing the inner future
     fn handle<'life0, 'async_trait>(
         &'life0 self,
@@ -0,0 +1,76 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::endpoint::WitchcraftEndpoint;
+use crate::server::Listener;
+use http::Method;
+use parking_lot::Mutex;
+
+/// A record of the endpoints served by each of the server's listeners.
+pub struct EndpointInventory {
+    endpoints: Mutex<Vec<EndpointInfo>>,
+}
+
+impl EndpointInventory {
+    pub fn new() -> Self {
+        EndpointInventory {
+            endpoints: Mutex::new(vec![]),
+        }
+    }
+
+    /// Records the endpoints about to be served by a listener.
+    pub fn register(
+        &self,
+        listener: Listener,
+        endpoints: &[Box<dyn WitchcraftEndpoint + Sync + Send>],
+    ) {
+        self.endpoints
+            .lock()
+            .extend(endpoints.iter().map(|e| EndpointInfo {
+                service_name: e.service_name().to_string(),
+                name: e.name().to_string(),
+                method: e.method(),
+                template: e.template().to_string(),
+                path_prefix: e.path_prefix().to_string(),
+                deprecated: e.deprecated().map(|s| s.to_string()),
+                listener,
+                blocking: e.blocking(),
+                metrics: e.metrics().is_some(),
+                health: e.health().is_some(),
+            }));
+    }
+
+    /// Returns a snapshot of the registered endpoints.
+    pub fn snapshot(&self) -> Vec<EndpointInfo> {
+        self.endpoints.lock().clone()
+    }
+}
+
+/// Information about a registered endpoint.
+#[derive(Clone)]
+pub struct EndpointInfo {
+    pub service_name: String,
+    pub name: String,
+    pub method: Method,
+    /// The full path template of the endpoint, including its prefix.
+    pub template: String,
+    pub path_prefix: String,
+    /// The deprecation message of the endpoint, if it is deprecated.
+    pub deprecated: Option<String>,
+    pub listener: Listener,
+    pub blocking: bool,
+    /// Determines if the endpoint's metrics are recorded.
+    pub metrics: bool,
+    /// Determines if the endpoint's error rate is tracked by the `ENDPOINT_FIVE_HUNDREDS` health check.
+    pub health: bool,
+}
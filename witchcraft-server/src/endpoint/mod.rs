@@ -26,6 +26,7 @@ use std::sync::Arc;
 pub mod conjure;
 pub mod errors;
 pub mod extended_path;
+pub mod inventory;
 
 #[async_trait]
 pub trait WitchcraftEndpoint: EndpointMetadata {
@@ -33,6 +34,12 @@ pub trait WitchcraftEndpoint: EndpointMetadata {
 
     fn health(&self) -> Option<&Arc<EndpointHealth>>;
 
+    /// Returns `true` if the endpoint's handler runs on the blocking thread pool.
+    fn blocking(&self) -> bool;
+
+    /// Returns the context path and API prefix prepended to the endpoint's own path template.
+    fn path_prefix(&self) -> &str;
+
     async fn handle(&self, req: Request<RawBody>) -> Response<BoxBody<Bytes, BodyWriteAborted>>;
 }
 
@@ -48,6 +55,14 @@ where
         (**self).health()
     }
 
+    fn blocking(&self) -> bool {
+        (**self).blocking()
+    }
+
+    fn path_prefix(&self) -> &str {
+        (**self).path_prefix()
+    }
+
     // manually implementing to avoid double boxing the inner future
     fn handle<'life0, 'async_trait>(
         &'life0 self,
@@ -19,7 +19,9 @@ use serde::Serialize;
 use staged_builder::staged_builder;
 use std::any::TypeId;
 use std::collections::BTreeMap;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[allow(warnings)]
 #[rustfmt::skip]
@@ -44,6 +46,13 @@ pub trait HealthCheck: 'static + Sync + Send {
     /// Performs the check, returning its result.
     fn result(&self) -> HealthCheckResult;
 
+    /// Returns the schedule the check will be run on.
+    ///
+    /// Defaults to running every 30 seconds with no timeout.
+    fn schedule(&self) -> HealthCheckSchedule {
+        HealthCheckSchedule::builder().build()
+    }
+
     // PrivacyToken can't be named outside of this crate, so it prevents anyone from overriding this
     // default implementation in another crate. That allows us to trust it to be correct in the
     // downcast methods below.
@@ -90,6 +99,65 @@ impl dyn HealthCheck {
     }
 }
 
+/// An asynchronous health check.
+///
+/// Unlike [`HealthCheck`], the check can await other operations such as requests to remote services without blocking
+/// a runtime thread.
+pub trait AsyncHealthCheck: 'static + Sync + Send {
+    /// Returns the check's type.
+    ///
+    /// The type must be `SCREAMING_SNAKE_CASE`.
+    fn type_(&self) -> &str;
+
+    /// Performs the check, returning its result.
+    fn result(&self) -> impl Future<Output = HealthCheckResult> + Send;
+
+    /// Returns the schedule the check will be run on.
+    ///
+    /// Defaults to running every 30 seconds with no timeout.
+    fn schedule(&self) -> HealthCheckSchedule {
+        HealthCheckSchedule::builder().build()
+    }
+}
+
+/// The schedule a health check is run on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[staged_builder]
+pub struct HealthCheckSchedule {
+    #[builder(default = Duration::from_secs(30))]
+    interval: Duration,
+    #[builder(default, into)]
+    timeout: Option<Duration>,
+    #[builder(default)]
+    jitter: Duration,
+}
+
+impl HealthCheckSchedule {
+    /// The amount of time between the completion of one run of the check and the start of the next.
+    ///
+    /// Defaults to 30 seconds.
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The maximum amount of time a run of the check can take before it is considered to have failed.
+    ///
+    /// A check which times out reports an `ERROR` state. Defaults to no timeout.
+    #[inline]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// The maximum amount of random delay added to the interval between runs of the check.
+    ///
+    /// Jitter spreads the runs of checks with the same interval out over time. Defaults to 0.
+    #[inline]
+    pub fn jitter(&self) -> Duration {
+        self.jitter
+    }
+}
+
 /// The result of a health check.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[staged_builder]
@@ -13,23 +13,33 @@
 // limitations under the License.
 use super::HealthState;
 use crate::health::api::{self, CheckType, HealthStatus};
-use crate::health::{HealthCheck, HealthCheckResult};
+use crate::health::{AsyncHealthCheck, HealthCheck, HealthCheckResult, HealthCheckSchedule};
+use crate::logging;
+use crate::logging::api::EventLogV2;
 use arc_swap::ArcSwap;
+use conjure_error::Error;
+use conjure_object::{DateTime, Utc};
+use futures_util::FutureExt;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
+use rand::Rng;
+use refreshable::Refreshable;
 use regex::Regex;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
-use std::panic::{self, AssertUnwindSafe};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, SystemTime};
 use tokio::runtime::Handle;
 use tokio::task::{self, JoinHandle};
-use tokio::time;
+use tokio::time::{self, Instant};
+use witchcraft_metrics::{MetricId, MetricRegistry};
+use witchcraft_server_config::runtime::{HealthCheckOverrideConfig, HealthCheckState};
 
-const CHECK_RUN_INTERVAL: Duration = Duration::from_secs(30);
 const STALENESS_THRESHOLD: Duration = Duration::from_secs(5 * 60);
 const HEALTH_CHECK_COMPUTATION_STALENESS_TYPE: &str = "HEALTH_CHECK_COMPUTATION_STALENESS";
+const MAX_TRANSITIONS: usize = 20;
 static TYPE_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new("^[A-Z_]+$").unwrap());
 
 struct TimestampedResult {
@@ -46,9 +56,86 @@ impl TimestampedResult {
     }
 }
 
+/// A change in the state of a health check.
+#[derive(Clone)]
+pub(crate) struct StateTransition {
+    pub time: DateTime<Utc>,
+    pub old_state: HealthState,
+    pub new_state: HealthState,
+    pub message: Option<String>,
+}
+
+struct CheckState {
+    type_: CheckType,
+    result: ArcSwap<TimestampedResult>,
+    transitions: Mutex<VecDeque<StateTransition>>,
+}
+
+impl CheckState {
+    fn new(type_: CheckType, result: HealthCheckResult) -> Self {
+        CheckState {
+            type_,
+            result: ArcSwap::new(Arc::new(TimestampedResult::new(result))),
+            transitions: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn update(&self, result: HealthCheckResult) {
+        let old_state = self.result.load().result.state().clone();
+        if old_state != *result.state() {
+            self.record_transition(old_state, &result);
+        }
+
+        self.result.store(Arc::new(TimestampedResult::new(result)));
+    }
+
+    fn record_transition(&self, old_state: HealthState, result: &HealthCheckResult) {
+        let transition = StateTransition {
+            time: Utc::now(),
+            old_state,
+            new_state: result.state().clone(),
+            message: result.message().map(|s| s.to_string()),
+        };
+
+        let mut event = EventLogV2::builder()
+            .type_("event.2")
+            .time(transition.time)
+            .event_name("health.check.transition")
+            .insert_values("checkType", &self.type_)
+            .insert_values("oldState", &transition.old_state)
+            .insert_values("newState", &transition.new_state);
+        if let Some(message) = &transition.message {
+            event = event.insert_unsafe_params("message", message);
+        }
+        logging::event_log(event.build());
+
+        let mut transitions = self.transitions.lock();
+        if transitions.len() == MAX_TRANSITIONS {
+            transitions.pop_front();
+        }
+        transitions.push_back(transition);
+    }
+
+    // The new check may have already recorded transitions of its own, so the old ones are prepended.
+    fn inherit_transitions(&self, previous: &CheckState) {
+        let mut transitions = previous.transitions.lock().clone();
+        let mut current = self.transitions.lock();
+        transitions.extend(current.drain(..));
+        while transitions.len() > MAX_TRANSITIONS {
+            transitions.pop_front();
+        }
+        *current = transitions;
+    }
+}
+
+enum RegisteredCheck {
+    Sync(Arc<dyn HealthCheck>),
+    Async,
+}
+
 struct InstalledCheck {
-    check: Arc<dyn HealthCheck>,
-    result: Arc<ArcSwap<TimestampedResult>>,
+    check: RegisteredCheck,
+    state: Arc<CheckState>,
     handle: JoinHandle<()>,
 }
 
@@ -61,20 +148,39 @@ impl Drop for InstalledCheck {
 /// A registry of health checks for the server.
 pub struct HealthCheckRegistry {
     checks: Mutex<HashMap<CheckType, InstalledCheck>>,
+    staleness: Arc<CheckState>,
     handle: Handle,
+    metrics: Arc<MetricRegistry>,
+    overrides: Refreshable<HashMap<String, HealthCheckOverrideConfig>, Error>,
 }
 
 impl HealthCheckRegistry {
-    pub(crate) fn new(handle: &Handle) -> Self {
+    pub(crate) fn new(
+        handle: &Handle,
+        metrics: &Arc<MetricRegistry>,
+        overrides: &Refreshable<HashMap<String, HealthCheckOverrideConfig>, Error>,
+    ) -> Self {
+        // Staleness is computed when the checks are run rather than on a schedule of its own, but its state is tracked
+        // like that of any other check.
+        let staleness = Arc::new(CheckState::new(
+            CheckType(HEALTH_CHECK_COMPUTATION_STALENESS_TYPE.to_string()),
+            staleness_result(vec![]),
+        ));
+        register_gauge(metrics, &staleness);
+
         HealthCheckRegistry {
             checks: Mutex::new(HashMap::new()),
+            staleness,
             handle: handle.clone(),
+            metrics: metrics.clone(),
+            overrides: overrides.map(|o| o.clone()),
         }
     }
 
     /// Registers a new health check.
     ///
-    /// If a check already exists with the same type, it will be replaced.
+    /// If a check already exists with the same type, it will be replaced. The new check inherits its history of state
+    /// transitions.
     ///
     /// # Panics
     ///
@@ -83,13 +189,48 @@ impl HealthCheckRegistry {
     where
         T: HealthCheck,
     {
-        let type_ = check.type_();
-        self.check_type(type_);
+        let type_ = check.type_().to_string();
+        self.check_type(&type_);
+
+        let installed = self.make_check(Arc::new(check));
+        self.replace(CheckType(type_), installed);
+    }
 
-        self.checks.lock().insert(
-            CheckType(type_.to_string()),
-            self.make_check(Arc::new(check)),
+    /// Registers a new asynchronous health check.
+    ///
+    /// If a check already exists with the same type, it will be replaced. The new check inherits its history of state
+    /// transitions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the check's type is not `SCREAMING_SNAKE_CASE`.
+    pub fn register_async<T>(&self, check: T)
+    where
+        T: AsyncHealthCheck,
+    {
+        let type_ = check.type_().to_string();
+        self.check_type(&type_);
+
+        let check = Arc::new(check);
+        let installed = self.install(
+            RegisteredCheck::Async,
+            type_.clone(),
+            check.schedule(),
+            move || {
+                let check = check.clone();
+                async move { check.result().await }
+            },
         );
+        self.replace(CheckType(type_), installed);
+    }
+
+    fn replace(&self, type_: CheckType, installed: InstalledCheck) {
+        let state = installed.state.clone();
+        let previous = self.checks.lock().insert(type_, installed);
+        // A replaced check keeps the history of the check it replaces.
+        if let Some(previous) = previous {
+            state.inherit_transitions(&previous.state);
+        }
     }
 
     /// Registers a new check if one does not already exist with the same type.
@@ -98,7 +239,8 @@ impl HealthCheckRegistry {
     ///
     /// # Panics
     ///
-    /// Panics if the check's type is not `SCREAMING_SNAKE_CASE`.
+    /// Panics if the check's type is not `SCREAMING_SNAKE_CASE`, or if an asynchronous check is already registered with
+    /// the same type.
     pub fn register_if_absent<T>(&self, check: T) -> Arc<dyn HealthCheck>
     where
         T: HealthCheck,
@@ -107,8 +249,17 @@ impl HealthCheckRegistry {
         self.check_type(type_);
 
         match self.checks.lock().entry(CheckType(type_.to_string())) {
-            Entry::Occupied(e) => e.get().check.clone(),
-            Entry::Vacant(e) => e.insert(self.make_check(Arc::new(check))).check.clone(),
+            Entry::Occupied(e) => match &e.get().check {
+                RegisteredCheck::Sync(check) => check.clone(),
+                RegisteredCheck::Async => {
+                    panic!("{type_} is already registered as an asynchronous check")
+                }
+            },
+            Entry::Vacant(e) => {
+                let check = Arc::new(check);
+                e.insert(self.make_check(check.clone()));
+                check
+            }
         }
     }
 
@@ -120,74 +271,212 @@ impl HealthCheckRegistry {
     }
 
     fn make_check(&self, check: Arc<dyn HealthCheck>) -> InstalledCheck {
+        // Runs are serialized so a check which hangs past its timeout doesn't pile up blocking threads.
+        let running = Arc::new(tokio::sync::Mutex::new(()));
+        self.install(
+            RegisteredCheck::Sync(check.clone()),
+            check.type_().to_string(),
+            check.schedule(),
+            move || {
+                let check = check.clone();
+                let running = running.clone();
+                async move {
+                    // The lock is held by the blocking task rather than this future, since the future is dropped
+                    // if the check times out but the blocking task keeps running.
+                    let running = running.lock_owned().await;
+                    let trace_context = zipkin::current();
+                    task::spawn_blocking(move || {
+                        let _running = running;
+                        let _guard = trace_context.map(zipkin::set_current);
+                        check.result()
+                    })
+                    .await
+                    .unwrap_or_else(|_| panic_error())
+                }
+            },
+        )
+    }
+
+    fn install<F, R>(
+        &self,
+        check: RegisteredCheck,
+        type_: String,
+        schedule: HealthCheckSchedule,
+        run: F,
+    ) -> InstalledCheck
+    where
+        F: FnMut() -> R + 'static + Send,
+        R: Future<Output = HealthCheckResult> + Send + 'static,
+    {
         let _guard = self.handle.enter();
 
-        let result = Arc::new(ArcSwap::new(Arc::new(TimestampedResult::new(
+        let state = Arc::new(CheckState::new(
+            CheckType(type_),
             computing_for_the_first_time(),
-        ))));
-        let handle = task::spawn(run_check(check.clone(), result.clone()));
+        ));
+        register_gauge(&self.metrics, &state);
+        let handle = task::spawn(run_check(schedule, state.clone(), run));
 
         InstalledCheck {
             check,
-            result,
+            state,
             handle,
         }
     }
 
+    /// Returns the recent state transitions of each check, from oldest to newest.
+    pub(crate) fn transitions(&self) -> BTreeMap<CheckType, Vec<StateTransition>> {
+        self.checks
+            .lock()
+            .iter()
+            .map(|(type_, check)| {
+                let transitions = check.state.transitions.lock().iter().cloned().collect();
+                (type_.clone(), transitions)
+            })
+            .chain(Some((
+                self.staleness.type_.clone(),
+                self.staleness.transitions.lock().iter().cloned().collect(),
+            )))
+            .collect()
+    }
+
     pub(crate) fn run_checks(&self) -> HealthStatus {
         let threshold = Instant::now() - STALENESS_THRESHOLD;
         let mut stale_checks = vec![];
+        let overrides = self.overrides.get();
+        let now = SystemTime::now();
 
         let status = HealthStatus::builder().extend_checks(self.checks.lock().iter().map(
             |(type_, check)| {
-                let result = check.result.load();
-                if result.time < threshold {
+                if check.state.result.load().time < threshold {
                     stale_checks.push(type_.clone());
                 }
 
-                let result = api::HealthCheckResult::builder()
-                    .type_(type_.clone())
-                    .state(result.result.state().clone())
-                    .message(result.result.message().map(|s| s.to_string()))
-                    .params(
-                        result
-                            .result
-                            .params()
-                            .iter()
-                            .map(|(k, v)| (k.clone(), v.clone())),
-                    )
-                    .build();
-
+                let result = apply_override(api_result(&check.state), &overrides, now);
                 (type_.clone(), result)
             },
         ));
 
-        let staleness_result = if stale_checks.is_empty() {
-            api::HealthCheckResult::builder()
-                .type_(CheckType(HEALTH_CHECK_COMPUTATION_STALENESS_TYPE.to_string()))
-                .state(HealthState::Healthy)
-                .message(format!("All healthcheck results have been computed within the last {STALENESS_THRESHOLD:?}"))
-                .build()
-        } else {
-            api::HealthCheckResult::builder()
-                .type_(CheckType(HEALTH_CHECK_COMPUTATION_STALENESS_TYPE.to_string()))
-                .state(HealthState::Warning)
-                .message(
-                    format!("Some healthcheck results have not been computed within the last {STALENESS_THRESHOLD:?}"),
-                )
-                .insert_params("staleHealthChecks", stale_checks)
-                .build()
-        };
+        self.staleness.update(staleness_result(stale_checks));
+        let staleness_result = apply_override(api_result(&self.staleness), &overrides, now);
 
         status
-            .insert_checks(
-                CheckType(HEALTH_CHECK_COMPUTATION_STALENESS_TYPE.to_string()),
-                staleness_result,
-            )
+            .insert_checks(self.staleness.type_.clone(), staleness_result)
+            .build()
+    }
+}
+
+fn register_gauge(metrics: &MetricRegistry, state: &Arc<CheckState>) {
+    metrics.replace_gauge(
+        MetricId::new("health.check.state").with_tag("type", state.type_.0.clone()),
+        {
+            let state = state.clone();
+            move || severity(state.result.load().result.state())
+        },
+    );
+}
+
+fn api_result(state: &CheckState) -> api::HealthCheckResult {
+    let result = state.result.load();
+    api::HealthCheckResult::builder()
+        .type_(state.type_.clone())
+        .state(result.result.state().clone())
+        .message(result.result.message().map(|s| s.to_string()))
+        .params(
+            result
+                .result
+                .params()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        )
+        .build()
+}
+
+fn staleness_result(stale_checks: Vec<CheckType>) -> HealthCheckResult {
+    if stale_checks.is_empty() {
+        HealthCheckResult::builder()
+            .state(HealthState::Healthy)
+            .message(format!(
+                "All healthcheck results have been computed within the last {STALENESS_THRESHOLD:?}"
+            ))
+            .build()
+    } else {
+        HealthCheckResult::builder()
+            .state(HealthState::Warning)
+            .message(format!(
+                "Some healthcheck results have not been computed within the last {STALENESS_THRESHOLD:?}"
+            ))
+            .insert_params("staleHealthChecks", stale_checks)
             .build()
     }
 }
 
+fn apply_override(
+    result: api::HealthCheckResult,
+    overrides: &HashMap<String, HealthCheckOverrideConfig>,
+    now: SystemTime,
+) -> api::HealthCheckResult {
+    let Some(config) = overrides.get(&result.type_().0) else {
+        return result;
+    };
+
+    let muted_until = config.muted_until().filter(|t| *t > now);
+    let (action, state) = if config.disabled() {
+        ("DISABLED", HealthState::Healthy)
+    } else if muted_until.is_some() {
+        ("MUTED", HealthState::Healthy)
+    } else if let Some(max_state) = config.max_state() {
+        (
+            "DOWNGRADED",
+            result.state().clone().min(health_state(max_state)),
+        )
+    } else {
+        return result;
+    };
+
+    let mut builder = api::HealthCheckResult::builder()
+        .type_(result.type_().clone())
+        .state(state)
+        .message(result.message().map(|s| s.to_string()))
+        .params(result.params().clone())
+        .insert_params("healthCheckOverride", action)
+        .insert_params("originalState", result.state());
+    if let Some(muted_until) = muted_until {
+        builder = builder.insert_params(
+            "mutedUntil",
+            humantime::format_rfc3339(muted_until).to_string(),
+        );
+    }
+
+    builder.build()
+}
+
+fn health_state(state: HealthCheckState) -> HealthState {
+    match state {
+        HealthCheckState::Healthy => HealthState::Healthy,
+        HealthCheckState::Deferring => HealthState::Deferring,
+        HealthCheckState::Suspended => HealthState::Suspended,
+        HealthCheckState::Repairing => HealthState::Repairing,
+        HealthCheckState::Warning => HealthState::Warning,
+        HealthCheckState::Error => HealthState::Error,
+        HealthCheckState::Terminal => HealthState::Terminal,
+        _ => HealthState::Error,
+    }
+}
+
+// The value of the `health.check.state` gauge, increasing with severity.
+fn severity(state: &HealthState) -> u8 {
+    match state {
+        HealthState::Healthy => 0,
+        HealthState::Deferring => 1,
+        HealthState::Suspended => 2,
+        HealthState::Repairing => 3,
+        HealthState::Warning => 4,
+        HealthState::Error => 5,
+        HealthState::Terminal => 6,
+    }
+}
+
 fn computing_for_the_first_time() -> HealthCheckResult {
     HealthCheckResult::builder()
         .state(HealthState::Repairing)
@@ -205,21 +494,268 @@ fn panic_error() -> HealthCheckResult {
         .build()
 }
 
-async fn run_check(check: Arc<dyn HealthCheck>, state: Arc<ArcSwap<TimestampedResult>>) {
+fn timed_out(timeout: Duration) -> HealthCheckResult {
+    HealthCheckResult::builder()
+        .state(HealthState::Error)
+        .message("Healthcheck evaluation timed out".to_string())
+        .insert_params("timeout", format!("{timeout:?}"))
+        .build()
+}
+
+async fn run_check<F, R>(schedule: HealthCheckSchedule, state: Arc<CheckState>, mut run: F)
+where
+    F: FnMut() -> R,
+    R: Future<Output = HealthCheckResult>,
+{
     loop {
-        // it's okay to use block_in_place here since we know this future is running as its own task.
-        let result = task::block_in_place(|| {
-            let _span = zipkin::new_trace().with_name(&format!("healthcheck: {}", check.type_()));
-            panic::catch_unwind(AssertUnwindSafe(|| check.result()))
-        });
+        let span = zipkin::new_trace()
+            .with_name(&format!("healthcheck: {}", state.type_))
+            .detach();
+        let future = span.bind(AssertUnwindSafe(run()).catch_unwind());
+
+        let result = match schedule.timeout() {
+            Some(timeout) => match time::timeout(timeout, future).await {
+                Ok(result) => result,
+                Err(_) => Ok(timed_out(timeout)),
+            },
+            None => future.await,
+        };
 
         let result = match result {
             Ok(result) => result,
             Err(_) => panic_error(),
         };
 
-        state.store(Arc::new(TimestampedResult::new(result)));
+        state.update(result);
+
+        let mut delay = schedule.interval();
+        if !schedule.jitter().is_zero() {
+            delay += rand::thread_rng().gen_range(Duration::ZERO..schedule.jitter());
+        }
+        time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+
+    struct SlowCheck;
+
+    impl AsyncHealthCheck for SlowCheck {
+        fn type_(&self) -> &str {
+            "SLOW"
+        }
+
+        async fn result(&self) -> HealthCheckResult {
+            time::sleep(Duration::from_secs(10)).await;
+            HealthCheckResult::builder()
+                .state(HealthState::Healthy)
+                .build()
+        }
+
+        fn schedule(&self) -> HealthCheckSchedule {
+            HealthCheckSchedule::builder()
+                .interval(Duration::from_secs(60))
+                .timeout(Duration::from_secs(1))
+                .build()
+        }
+    }
+
+    struct HangingCheck {
+        runs: Arc<AtomicUsize>,
+        release: Mutex<mpsc::Receiver<()>>,
+    }
+
+    impl HealthCheck for HangingCheck {
+        fn type_(&self) -> &str {
+            "HANGING"
+        }
+
+        fn result(&self) -> HealthCheckResult {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let _ = self.release.lock().recv();
+            HealthCheckResult::builder()
+                .state(HealthState::Healthy)
+                .build()
+        }
+
+        fn schedule(&self) -> HealthCheckSchedule {
+            HealthCheckSchedule::builder()
+                .interval(Duration::from_secs(60))
+                .timeout(Duration::from_secs(1))
+                .build()
+        }
+    }
+
+    fn registry(overrides: HashMap<String, HealthCheckOverrideConfig>) -> HealthCheckRegistry {
+        let (overrides, _) = Refreshable::new(overrides);
+        HealthCheckRegistry::new(
+            &Handle::current(),
+            &Arc::new(MetricRegistry::new()),
+            &overrides,
+        )
+    }
+
+    fn state(registry: &HealthCheckRegistry, type_: &str) -> HealthState {
+        registry.run_checks().checks()[&CheckType(type_.to_string())]
+            .state()
+            .clone()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn async_check_timeout() {
+        let registry = registry(HashMap::new());
+        registry.register_async(SlowCheck);
+        assert_eq!(state(&registry, "SLOW"), HealthState::Repairing);
+
+        time::sleep(Duration::from_millis(1500)).await;
+        assert_eq!(state(&registry, "SLOW"), HealthState::Error);
+    }
+
+    // A running blocking task stops the paused clock from auto-advancing, so time is advanced manually.
+    async fn advance(duration: Duration) {
+        settle().await;
+        time::advance(duration).await;
+        settle().await;
+    }
+
+    async fn settle() {
+        for _ in 0..10 {
+            task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sync_check_timeout() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let (tx, rx) = mpsc::channel();
+        let registry = registry(HashMap::new());
+        registry.register(HangingCheck {
+            runs: runs.clone(),
+            release: Mutex::new(rx),
+        });
+
+        advance(Duration::from_millis(1500)).await;
+        assert_eq!(state(&registry, "HANGING"), HealthState::Error);
+
+        // the next run times out waiting for the hung one rather than starting another
+        advance(Duration::from_secs(61)).await;
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(state(&registry, "HANGING"), HealthState::Error);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        drop(tx);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn async_check_staleness() {
+        let registry = registry(HashMap::new());
+        registry.register_async(SlowCheck);
+
+        time::sleep(Duration::from_millis(1500)).await;
+        assert_eq!(
+            state(&registry, HEALTH_CHECK_COMPUTATION_STALENESS_TYPE),
+            HealthState::Healthy,
+        );
+
+        // the check task is stopped, so its result isn't updated
+        registry
+            .checks
+            .lock()
+            .get(&CheckType("SLOW".to_string()))
+            .unwrap()
+            .handle
+            .abort();
+        time::sleep(STALENESS_THRESHOLD).await;
+        assert_eq!(
+            state(&registry, HEALTH_CHECK_COMPUTATION_STALENESS_TYPE),
+            HealthState::Warning,
+        );
+
+        let transitions = registry.transitions();
+        let transitions =
+            &transitions[&CheckType(HEALTH_CHECK_COMPUTATION_STALENESS_TYPE.to_string())];
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].old_state, HealthState::Healthy);
+        assert_eq!(transitions[0].new_state, HealthState::Warning);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn overrides() {
+        let registry = registry(HashMap::from([
+            (
+                "SLOW".to_string(),
+                HealthCheckOverrideConfig::builder()
+                    .max_state(HealthCheckState::Warning)
+                    .build(),
+            ),
+            (
+                HEALTH_CHECK_COMPUTATION_STALENESS_TYPE.to_string(),
+                HealthCheckOverrideConfig::builder()
+                    .muted_until(SystemTime::now() + Duration::from_secs(60))
+                    .build(),
+            ),
+        ]));
+        registry.register_async(SlowCheck);
+        time::sleep(Duration::from_millis(1500)).await;
+
+        let status = registry.run_checks();
+        let slow = &status.checks()[&CheckType("SLOW".to_string())];
+        assert_eq!(*slow.state(), HealthState::Warning);
+        assert_eq!(
+            slow.params()["healthCheckOverride"]
+                .clone()
+                .deserialize_into::<String>()
+                .unwrap(),
+            "DOWNGRADED",
+        );
+        assert_eq!(
+            slow.params()["originalState"]
+                .clone()
+                .deserialize_into::<HealthState>()
+                .unwrap(),
+            HealthState::Error,
+        );
+
+        let staleness =
+            &status.checks()[&CheckType(HEALTH_CHECK_COMPUTATION_STALENESS_TYPE.to_string())];
+        assert_eq!(*staleness.state(), HealthState::Healthy);
+        assert!(staleness.params().contains_key("mutedUntil"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn transitions() {
+        let registry = registry(HashMap::new());
+        registry.register_async(SlowCheck);
+        time::sleep(Duration::from_millis(1500)).await;
+
+        let transitions = registry.transitions();
+        let transitions = &transitions[&CheckType("SLOW".to_string())];
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].old_state, HealthState::Repairing);
+        assert_eq!(transitions[0].new_state, HealthState::Error);
+        assert_eq!(
+            transitions[0].message.as_deref(),
+            Some("Healthcheck evaluation timed out"),
+        );
+
+        // the state doesn't change on the next run
+        time::sleep(Duration::from_secs(61)).await;
+        assert_eq!(
+            registry.transitions()[&CheckType("SLOW".to_string())].len(),
+            1
+        );
 
-        time::sleep(CHECK_RUN_INTERVAL).await;
+        // a replacement check keeps the history of the old one
+        registry.register_async(SlowCheck);
+        time::sleep(Duration::from_millis(1500)).await;
+        let transitions = registry.transitions();
+        let transitions = &transitions[&CheckType("SLOW".to_string())];
+        assert_eq!(transitions.len(), 2);
+        assert_eq!(transitions[1].old_state, HealthState::Repairing);
+        assert_eq!(transitions[1].new_state, HealthState::Error);
     }
 }
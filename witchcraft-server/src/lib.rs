@@ -152,6 +152,23 @@
 //! [`HealthCheckRegistry`] returned by the [`Witchcraft::health_checks`] method. Requests to this endpoint must be
 //! authenticated with the `health-checks.shared-secret` bearer token in runtime configuration.
 //!
+//! Health checks are run periodically in the background, every 30 seconds by default. Checks which need to wait on
+//! remote services should implement [`health::AsyncHealthCheck`] rather than [`health::HealthCheck`]. Either kind of
+//! check can override its interval, add random jitter to it, and set a timeout after which the check reports an error
+//! state. If a check has not completed within the last 5 minutes, the `HEALTH_CHECK_COMPUTATION_STALENESS` check
+//! reports a warning.
+//!
+//! Operators can override the results of specific checks in the `health-checks.overrides` section of the runtime
+//! configuration, keyed by check type. A check can be `disabled`, `muted-until` a timestamp, or downgraded to a
+//! `max-state`. Overridden checks are still reported, with the override and the check's original state included in
+//! its parameters.
+//!
+//! The current state of each check is reported by the `health.check.state` gauge, tagged with the check's `type`. Its
+//! value increases with severity, from `0` for `HEALTHY` to `6` for `TERMINAL`. Every time a check changes state, a
+//! `health.check.transition` event is written to the event log. Neither is affected by overrides. A check replaced by
+//! registering another of the same type keeps its history of transitions. The state of the
+//! `HEALTH_CHECK_COMPUTATION_STALENESS` check is updated each time the server's health is queried.
+//!
 //! The server registers several built-in health checks:
 //!
 //! * `CONFIG_RELOAD` - Reports an error state if the runtime configuration failed to reload properly.
@@ -166,21 +183,69 @@
 //! The `/debug/diagnostic/{diagnosticType}` endpoint returns diagnostic information. Requests to this endpoint must be
 //! authenticated with the `diagnostics.debug-shared-secret` bearer token in the runtime configuration.
 //!
-//! Several diagnostic types are defined:
+//! Diagnostics can accept query parameters, and requests with parameters not supported by the diagnostic will be
+//! rejected. Several diagnostic types are defined:
 //!
-//! * `diagnostic.types.v1` - Returns a JSON-encoded list of all valid diagnostic types.
+//! * `diagnostic.types.v1` - Returns a JSON-encoded list of all valid diagnostic types. If the `detailed=true`
+//!     parameter is set, each entry also describes the parameters accepted by the diagnostic.
 //! * `rust.heap.status.v1` - Returns detailed statistics about the state of the heap. Requires the `jemalloc` feature
 //!     (enabled by default).
-//! * `metric.names.v1` - Returns a JSON-encoded list of the names of all metrics registered with the server.
+//! * `rust.heap.profile.v1` - Returns a heap profile of live allocations in the gzip-compressed [pprof] format.
+//!     Requires the `jemalloc` feature, and for profiling to be enabled at startup by setting the `MALLOC_CONF`
+//!     environment variable to `prof:true`. Sampling can be paused and resumed without a restart with the
+//!     `diagnostics.heap-profiling-active` field in the runtime configuration; starting the process with
+//!     `MALLOC_CONF=prof:true,prof_active:false` allows it to be activated only when needed to track down a leak.
+//!     Only supported when running on Linux.
+//! * `metric.names.v1` - Returns a JSON-encoded list of the names of all metrics registered with the server. The
+//!     `prefix` parameter restricts the list to metrics with names starting with the prefix.
 //! * `rust.thread.dump.v1` - Returns a stack trace of every thread in the process. Only supported when running on
 //!     Linux.
+//! * `rust.task.dump.v1` - Returns the endpoint, trace ID, elapsed time, and Tokio task ID of each request currently
+//!     being handled by the server. If the server is built with `--cfg tokio_unstable --cfg tokio_taskdump` and Tokio's
+//!     `taskdump` feature enabled, it also includes a trace of each request's current await point on Linux x86,
+//!     x86_64, and aarch64.
+//! * `rust.cpu.profile.v1` - Samples the stacks of threads consuming CPU and returns a CPU profile in the folded stack
+//!     format, suitable for rendering as a flame graph. The `duration` parameter sets how long to sample for, and
+//!     defaults to 10 seconds. Stacks are walked with frame pointers, so the server should be built with
+//!     `-C force-frame-pointers=yes` for complete stacks. The profiler uses the process-wide `ITIMER_PROF` timer and
+//!     `SIGPROF` signal, and conflicts with anything else using them. Only supported when running on Linux.
+//! * `server.active.requests.v1` - Returns a JSON-encoded list of the requests currently being handled by the server,
+//!     including their method, path template, trace ID, user ID, peer address, start time, and the number of body bytes
+//!     read and written so far.
+//! * `config.install.v1` and `config.runtime.v1` - Return the effective install and runtime configuration as it was
+//!     last successfully loaded. The server's own settings include their defaults, while other settings, including
+//!     `service-discovery`, are returned as written. Encrypted and file-backed values, the server's shared secrets, and
+//!     the values of fields named like `password`, `secret`, `token`, `credentials`, or `auth` (including everything
+//!     nested beneath them) are redacted. The response also includes the SHA-256 hashes of the configuration file and
+//!     of each file it references, and the time it was loaded. Only supported when the server's configuration is
+//!     loaded by [`init`] rather than custom loaders.
+//! * `server.endpoints.v1` - Returns a JSON-encoded list of every endpoint registered with the server, including its
+//!     service and endpoint names, method, path template and prefix, deprecation status, listener, whether it is
+//!     blocking or async, and whether its metrics and error rate are tracked.
+//! * `health.check.history.v1` - Returns the most recent state transitions of each health check, including the time of
+//!     the transition, the old and new states, and the check's message.
+//!
+//! If `server.request-cancellation-endpoint` is enabled in the install configuration, an in-flight request can be
+//! cancelled with a `POST` to `/debug/requests/{requestId}/cancel`, authenticated in the same way. The request will
+//! immediately complete with a 503 response, which is not counted as an endpoint error, and blocking endpoints will
+//! observe the cancellation via [`blocking::Cancellation`]. Requests whose endpoint has already produced a response cannot be cancelled, and the
+//! endpoint returns a 409 instead.
+//!
+//! The service log level can be temporarily overridden with a `POST` to `/debug/logging/level`, authenticated in the
+//! same way. The `level` query parameter is required, the optional `target` parameter restricts the override to a
+//! logger and its children, and the optional `ttl` parameter controls how long the override lasts before the levels
+//! from the runtime configuration are restored (10 minutes by default, and at most 24 hours). Each change is recorded
+//! in the audit log.
+//!
+//! [pprof]: https://github.com/google/pprof
 //!
 //! # Logging
 //!
 //! `witchcraft-server` emits JSON-encoded logs following the [witchcraft-api spec]. By default, logs will be written to
 //! a file in `var/log` corresponding to the type of log message (`service.log`, `request.log`, etc). These files are
-//! automatically rotated and compressed based on a non-configurable policy. If running in a Docker container or if the
-//! `use-console-log` setting is enabled in the install configuration, logs will instead be written to standard out.
+//! automatically rotated and compressed, and the size and retention limits of each log type can be overridden in the
+//! `logging` section of the install configuration. If running in a Docker container or if the `use-console-log`
+//! setting is enabled in the install configuration, logs will instead be written to standard out.
 //!
 //! [witchcraft-api spec]: https://github.com/palantir/witchcraft-api
 //!
@@ -191,6 +256,11 @@
 //! Witchcraft service should use [`witchcraft_log`] instead for better integration. See the documentation of that crate
 //! for more details.
 //!
+//! Events emitted via the [`tracing`] crate can be captured as well by installing the subscriber returned by
+//! [`logging::tracing_subscriber`]. It is not installed automatically so that services remain free to configure their
+//! own. The event's message is logged as an unsafe message, and the other fields of the event and its enclosing spans
+//! are included as unsafe parameters. Spans created while a trace is active are additionally recorded in the trace log.
+//!
 //! ## Request
 //!
 //! The request log records an entry for each HTTP request processed by the server. Parameters marked marked as safe by
@@ -204,14 +274,43 @@
 //! defaults to 0.005%. Server logic can create additional spans with the [`zipkin`] crate. See the documentation of
 //! that crate for more details.
 //!
+//! The `logging.trace-sampling` field can override the sampling rate for individual endpoints, and can enable tail-based
+//! sampling, which buffers the spans the server records for requests not otherwise selected for sampling and reports
+//! them if the request fails with a server error or exceeds a latency threshold. The deferred decision is not
+//! propagated to other services, so spans created directly through `zipkin`, including those of outgoing requests, are
+//! not buffered.
+//!
+//! Both [W3C Trace Context] and Zipkin B3 propagation headers are accepted, with W3C preferred when both are present.
+//! The accepted formats can be changed with the `server.trace-propagation` field in the server's install configuration.
+//!
+//! Sampled spans can additionally be exported to an [OpenTelemetry] collector over OTLP/HTTP by configuring the
+//! `logging.trace-exporter` field in the server's install configuration.
+//!
 //! [Zipkin]: https://zipkin.io/
+//! [OpenTelemetry]: https://opentelemetry.io/
+//! [W3C Trace Context]: https://www.w3.org/TR/trace-context/
 //!
 //! ## Metric
 //!
 //! The metric log contains the values of metrics reporting the state of various components of the server. Metrics are
-//! recorded every 30 seconds. Server logic can create additional metrics with the [`MetricRegistry`] returned by the
+//! recorded every 30 seconds by default. The interval can be changed via the `logging.metrics.interval` field of the
+//! server's runtime configuration, and `logging.metrics.filters` can be used to allow or deny metrics by name prefix
+//! and tags. Metrics which haven't changed since they were last logged can be skipped by enabling
+//! `logging.metrics.skip-unchanged`. Server logic can create additional metrics with the [`MetricRegistry`] returned by the
 //! [`Witchcraft::metrics`] method. See the documentation of the [`witchcraft_metrics`] crate for more details.
 //!
+//! Metrics can additionally be scraped in the [OpenMetrics] text format from the `/metrics` endpoint, served on the
+//! management port if one is configured, by enabling `metrics.openmetrics-endpoint` in the server's install
+//! configuration. If the `diagnostics.metrics-shared-secret` field is set in the server's runtime configuration,
+//! requests must provide it as a bearer token.
+//!
+//! Metrics can also be pushed to a [DogStatsD] agent over UDP or to an OpenTelemetry collector via OTLP/HTTP by
+//! configuring `metrics.reporter` in the server's install configuration. Metric tags are mapped to DogStatsD tags and
+//! OTLP attributes respectively, and values are pushed every 30 seconds by default.
+//!
+//! [OpenMetrics]: https://openmetrics.io/
+//! [DogStatsD]: https://docs.datadoghq.com/developers/dogstatsd/
+//!
 //! # Metrics
 //!
 //! The server reports a variety of metrics by default:
@@ -228,6 +327,14 @@
 //!
 //! * `logging.queue (type: <log_type>)` (gauge) - The number of log messages queued for output.
 //!
+//! * `metrics.reporter.failures (reporter: <reporter>)` (meter) - The rate at which pushes to a metrics reporter
+//!     fail.
+//!
+//! ## Tracing
+//!
+//! * `tracing.sampled (reason: <reason>)` (meter) - The rate at which traces are sampled, by the reason for the
+//!     decision: `upstream`, `rate`, `rule`, `error`, or `latency`.
+//!
 //! ## Process
 //!
 //! * `process.heap` (gauge) - The total number of bytes allocated from the heap. Requires the `jemalloc` feature
@@ -248,6 +355,48 @@
 //! * `process.threads` (gauge) - The number of threads in the process.
 //! * `process.filedescriptor` (gauge) - The number of file descriptors held open by the process divided by the maximum
 //!     number of files the server may hold open.
+//! * `process.memory.rss` (gauge) - The number of bytes of the process's memory resident in RAM.
+//! * `process.memory.pss` (gauge) - The process's proportional share of resident memory in bytes, with memory shared
+//!     with other processes divided between them. Updated at most every 10 seconds.
+//! * `process.context-switches (type: <type>)` (gauge) - The number of `voluntary` and `involuntary` context switches
+//!     of the process.
+//! * `process.sockets (state: <state>)` (gauge) - The number of TCP sockets held open by the process in each state
+//!     (e.g. `established`, `listen`, or `close-wait`).
+//!
+//! ## Cgroup
+//!
+//! These metrics are reported on Linux when the process's cgroup can be located. Both cgroup v1 and v2 are supported.
+//!
+//! * `cgroup.cpu.quota` (gauge) - The cgroup's CPU quota in cores. Omitted while the quota is unlimited.
+//! * `cgroup.cpu.throttled-time` (gauge) - The total number of microseconds the cgroup has been throttled.
+//! * `cgroup.cpu.throttled-periods` (gauge) - The number of scheduling periods in which the cgroup was throttled.
+//! * `cgroup.memory.limit` (gauge) - The cgroup's memory limit in bytes. Omitted while the memory is unlimited.
+//! * `cgroup.memory.usage` (gauge) - The cgroup's memory usage in bytes.
+//! * `cgroup.memory.oom-kills` (gauge) - The number of processes in the cgroup killed by the OOM killer.
+//!
+//! ## Runtime
+//!
+//! Metrics describing the Tokio runtime are tagged with the `runtime` they describe, currently always `server`. Some
+//! are only available when the server is built with `RUSTFLAGS="--cfg tokio_unstable"`.
+//!
+//! * `tokio.workers (runtime: <runtime>)` (gauge) - The number of worker threads in the runtime.
+//! * `tokio.alive-tasks (runtime: <runtime>)` (gauge) - The number of tasks currently alive in the runtime.
+//! * `tokio.global-queue-depth (runtime: <runtime>)` (gauge) - The number of tasks in the runtime's global queue.
+//! * `tokio.busy-ratio (runtime: <runtime>)` (gauge) - The fraction of time the runtime's workers were busy over the
+//!     last 30 seconds.
+//! * `tokio.local-queue-depth (runtime: <runtime>)` (gauge) - The number of tasks in the workers' local queues.
+//!     Requires `tokio_unstable`.
+//! * `tokio.blocking-threads (runtime: <runtime>)` (gauge) - The number of threads in the runtime's blocking pool.
+//!     Requires `tokio_unstable`.
+//! * `tokio.idle-blocking-threads (runtime: <runtime>)` (gauge) - The number of idle threads in the runtime's blocking
+//!     pool. Requires `tokio_unstable`.
+//! * `tokio.blocking-queue-depth (runtime: <runtime>)` (gauge) - The number of tasks waiting for a blocking thread.
+//!     Requires `tokio_unstable`.
+//! * `tokio.spawned-tasks (runtime: <runtime>)` (gauge) - The number of tasks spawned in the runtime since it started.
+//!     Requires `tokio_unstable`.
+//! * `tokio.poll-time.p50 (runtime: <runtime>)` and `tokio.poll-time.p99 (runtime: <runtime>)` (gauge) - Estimates of
+//!     the median and 99th percentile time in microseconds taken to poll a task over the last 30 seconds.
+//!     Requires `tokio_unstable`.
 //!
 //! ## Connection
 //!
@@ -295,7 +444,10 @@ use conjure_http::server::{AsyncService, ConjureRuntime};
 use conjure_runtime::{Agent, ClientFactory, HostMetricsRegistry, UserAgent};
 use debug::endpoint::DebugResource;
 use debug::endpoint::DebugServiceEndpoints;
+use debug::endpoint::{LogLevelResource, LogLevelServiceEndpoints};
+use debug::endpoint::{RequestCancellationResource, RequestCancellationServiceEndpoints};
 use futures_util::{stream, Stream, StreamExt};
+use metrics::endpoint::{MetricsResource, MetricsServiceEndpoints};
 use refreshable::Refreshable;
 use serde::de::DeserializeOwned;
 use status::StatusResource;
@@ -315,16 +467,26 @@ pub use witchcraft_server_config as config;
 #[doc(inline)]
 pub use witchcraft_server_macros::main;
 
+use crate::debug::active_requests::ActiveRequestsDiagnostic;
+use crate::debug::config::ConfigDiagnostic;
+#[cfg(target_os = "linux")]
+use crate::debug::cpu_profile::CpuProfileDiagnostic;
 use crate::debug::diagnostic_types::DiagnosticTypesDiagnostic;
+use crate::debug::endpoints::EndpointsDiagnostic;
+use crate::debug::health::HealthHistoryDiagnostic;
+#[cfg(all(feature = "jemalloc", target_os = "linux"))]
+use crate::debug::heap_profile::HeapProfileDiagnostic;
 #[cfg(feature = "jemalloc")]
 use crate::debug::heap_stats::HeapStatsDiagnostic;
 use crate::debug::metric_names::MetricNamesDiagnostic;
+use crate::debug::task_dump::TaskDumpDiagnostic;
 #[cfg(all(
     target_os = "linux",
     any(target_arch = "x86_64", target_arch = "aarch64")
 ))]
 use crate::debug::thread_dump::ThreadDumpDiagnostic;
 use crate::debug::DiagnosticRegistry;
+use crate::endpoint::inventory::EndpointInventory;
 use crate::health::config_reload::ConfigReloadHealthCheck;
 use crate::health::endpoint_500s::Endpoint500sHealthCheck;
 use crate::health::panics::PanicsHealthCheck;
@@ -332,6 +494,7 @@ use crate::health::service_dependency::ServiceDependencyHealthCheck;
 use crate::health::HealthCheckRegistry;
 use crate::readiness::ReadinessCheckRegistry;
 use crate::server::Listener;
+use crate::service::active_requests::ActiveRequests;
 use crate::shutdown_hooks::ShutdownHooks;
 
 pub mod blocking;
@@ -344,6 +507,7 @@ pub mod health;
 pub mod logging;
 mod metrics;
 mod minidump;
+mod otlp;
 pub mod readiness;
 mod server;
 mod service;
@@ -415,13 +579,15 @@ where
     let install_config = load_install()?;
 
     let thread_id = AtomicUsize::new(0);
-    let runtime = runtime::Builder::new_multi_thread()
+    let mut builder = runtime::Builder::new_multi_thread();
+    builder
         .enable_all()
         .thread_name_fn(move || format!("runtime-{}", thread_id.fetch_add(1, Ordering::Relaxed)))
         .worker_threads(install_config.as_ref().server().io_threads())
-        .thread_keep_alive(install_config.as_ref().server().idle_thread_timeout())
-        .build()
-        .map_err(Error::internal_safe)?;
+        .thread_keep_alive(install_config.as_ref().server().idle_thread_timeout());
+    #[cfg(tokio_unstable)]
+    builder.enable_metrics_poll_time_histogram();
+    let runtime = builder.build().map_err(Error::internal_safe)?;
 
     let handle = runtime.handle().clone();
     let runtime = runtime_guard.insert(RuntimeGuard {
@@ -445,23 +611,43 @@ where
 
     handle.block_on(minidump::init())?;
 
-    metrics::init(&metrics);
+    metrics::init(&metrics, &handle);
+    handle.block_on(metrics::reporter::init(&metrics, install_config.as_ref()))?;
 
     let host_metrics = Arc::new(HostMetricsRegistry::new());
 
-    let health_checks = Arc::new(HealthCheckRegistry::new(&handle));
+    let health_checks = Arc::new(HealthCheckRegistry::new(
+        &handle,
+        &metrics,
+        &runtime_config.map(|c| c.as_ref().health_checks().overrides().clone()),
+    ));
     health_checks.register(ServiceDependencyHealthCheck::new(&host_metrics));
     health_checks.register(PanicsHealthCheck::new());
     health_checks.register(ConfigReloadHealthCheck::new(runtime_config_ok));
 
     let readiness_checks = Arc::new(ReadinessCheckRegistry::new());
 
+    let active_requests = Arc::new(ActiveRequests::new());
+    let endpoint_inventory = Arc::new(EndpointInventory::new());
+
     let diagnostics = Arc::new(DiagnosticRegistry::new());
     diagnostics.register(MetricNamesDiagnostic::new(&metrics));
     #[cfg(feature = "jemalloc")]
     diagnostics.register(HeapStatsDiagnostic);
+    #[cfg(all(feature = "jemalloc", target_os = "linux"))]
+    diagnostics.register(HeapProfileDiagnostic::new(
+        &runtime_config.map(|c| c.as_ref().diagnostics().heap_profiling_active()),
+    ));
     #[cfg(target_os = "linux")]
     diagnostics.register(ThreadDumpDiagnostic);
+    #[cfg(target_os = "linux")]
+    diagnostics.register(CpuProfileDiagnostic::new());
+    diagnostics.register(TaskDumpDiagnostic::new(&active_requests));
+    diagnostics.register(ActiveRequestsDiagnostic::new(&active_requests));
+    diagnostics.register(ConfigDiagnostic::install());
+    diagnostics.register(ConfigDiagnostic::runtime());
+    diagnostics.register(EndpointsDiagnostic::new(&endpoint_inventory));
+    diagnostics.register(HealthHistoryDiagnostic::new(&health_checks));
     diagnostics.register(DiagnosticTypesDiagnostic::new(Arc::downgrade(&diagnostics)));
     let client_factory = ClientFactory::builder()
         .config(runtime_config.map(|c| c.as_ref().service_discovery().clone()))
@@ -479,6 +665,8 @@ where
         readiness_checks,
         client_factory,
         diagnostics: diagnostics.clone(),
+        active_requests,
+        endpoint_inventory,
         handle: handle.clone(),
         install_config: install_config.as_ref().clone(),
         thread_pool: None,
@@ -502,6 +690,35 @@ where
         DebugServiceEndpoints::new(DebugResource::new(&runtime_config, &diagnostics));
     witchcraft.app(debug_endpoints);
 
+    let log_level_endpoints = LogLevelServiceEndpoints::new(LogLevelResource::new(
+        install_config.as_ref(),
+        &runtime_config,
+    ));
+    witchcraft.app(log_level_endpoints);
+
+    if install_config
+        .as_ref()
+        .server()
+        .request_cancellation_endpoint()
+    {
+        let cancellation_endpoints = RequestCancellationServiceEndpoints::new(
+            RequestCancellationResource::new(&runtime_config, &witchcraft.active_requests),
+        );
+        witchcraft.app(cancellation_endpoints);
+    }
+
+    if install_config.as_ref().metrics().openmetrics_endpoint() {
+        let metrics_endpoints = MetricsServiceEndpoints::new(MetricsResource::new(
+            &runtime_config,
+            &witchcraft.metrics,
+        ));
+        witchcraft.endpoints(
+            None,
+            metrics_endpoints.endpoints(&witchcraft.conjure_runtime),
+            false,
+        );
+    }
+
     // server::start clears out the previously-registered endpoints so the existing Witchcraft
     // is ready to reuse for the main port afterwards.
     if let Some(management_port) = install_config.as_ref().management_port() {
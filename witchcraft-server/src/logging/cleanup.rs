@@ -16,19 +16,33 @@ use std::path::Path;
 use std::time::{Duration, SystemTime};
 use tokio::fs;
 use witchcraft_log::{error, info, warn};
+use witchcraft_server_config::install::InstallConfig;
 
-// Archived logs are retained for 30 days, so anything more than 31 days old is definitely eligible for cleanup
-const MAX_AGE: Duration = Duration::from_secs(31 * 24 * 60 * 60);
+// The longest default retention period of any log type
+const DEFAULT_RETENTION_DAYS: u32 = 30;
 
-pub async fn cleanup_logs() {
+pub async fn cleanup_logs(install: &InstallConfig) {
     let path = Path::new("var/log");
 
-    if let Err(e) = cleanup_logs_inner(path, SystemTime::now()).await {
+    if let Err(e) = cleanup_logs_inner(path, SystemTime::now(), max_age(install)).await {
         error!("error cleaning up log directory", safe: { directory: path }, error: e);
     }
 }
 
-async fn cleanup_logs_inner(path: &Path, now: SystemTime) -> Result<(), Error> {
+// Archived logs are retained for at most the longest retention period of any log type, so anything a day older than
+// that is definitely eligible for cleanup
+fn max_age(install: &InstallConfig) -> Duration {
+    let retention_days = install
+        .logging()
+        .types()
+        .values()
+        .filter_map(|c| c.retention_days())
+        .fold(DEFAULT_RETENTION_DAYS, u32::max);
+
+    Duration::from_secs((u64::from(retention_days) + 1) * 24 * 60 * 60)
+}
+
+async fn cleanup_logs_inner(path: &Path, now: SystemTime, max_age: Duration) -> Result<(), Error> {
     fs::create_dir_all(path)
         .await
         .map_err(Error::internal_safe)?;
@@ -76,18 +90,19 @@ async fn cleanup_logs_inner(path: &Path, now: SystemTime) -> Result<(), Error> {
             Err(_) => continue,
         };
 
-        if age < MAX_AGE {
+        if age < max_age {
             continue;
         }
 
         match fs::remove_file(entry.path()).await {
             Ok(()) => {
                 info!(
-                    "deleted file more than 30 days old in the log directory",
+                    "deleted expired file in the log directory",
                     safe: {
                         directory: path,
                         size: metadata.len(),
                         age: format_args!("{:?}", age),
+                        maxAge: format_args!("{:?}", max_age),
                     },
                     unsafe: {
                         file: entry.file_name(),
@@ -96,11 +111,12 @@ async fn cleanup_logs_inner(path: &Path, now: SystemTime) -> Result<(), Error> {
             }
             Err(e) => {
                 error!(
-                    "error deleting file more than 30 days old from log directory",
+                    "error deleting expired file from log directory",
                     safe: {
                         directory: path,
                         size: metadata.len(),
                         age: format_args!("{:?}", age),
+                        maxAge: format_args!("{:?}", max_age),
                     },
                     unsafe: {
                         file: entry.file_name(),
@@ -127,8 +143,9 @@ mod test {
         fs::write(&file1, &[]).await.unwrap();
         fs::write(&file2, &[]).await.unwrap();
 
-        let now = SystemTime::now() + MAX_AGE + Duration::from_secs(10);
-        cleanup_logs_inner(dir.path(), now).await.unwrap();
+        let max_age = Duration::from_secs(31 * 24 * 60 * 60);
+        let now = SystemTime::now() + max_age + Duration::from_secs(10);
+        cleanup_logs_inner(dir.path(), now, max_age).await.unwrap();
 
         assert!(!file1.exists());
         assert!(!file2.exists());
@@ -143,8 +160,9 @@ mod test {
         fs::write(&file1, &[]).await.unwrap();
         fs::write(&file2, &[]).await.unwrap();
 
-        let now = SystemTime::now() + MAX_AGE - Duration::from_secs(10);
-        cleanup_logs_inner(dir.path(), now).await.unwrap();
+        let max_age = Duration::from_secs(31 * 24 * 60 * 60);
+        let now = SystemTime::now() + max_age - Duration::from_secs(10);
+        cleanup_logs_inner(dir.path(), now, max_age).await.unwrap();
 
         assert!(file1.exists());
         assert!(file2.exists());
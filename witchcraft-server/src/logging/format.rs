@@ -25,6 +25,14 @@ pub trait LogFormat: Sized {
     const TIME_LIMIT_DAYS: u32;
 
     type Reporter: ReportLog<Self>;
+
+    /// If `true`, a portion of the async queue is reserved for logs where [`LogFormat::high_priority`] returns `true`.
+    const PRIORITIZED: bool = false;
+
+    /// Returns `true` if the log should be allowed to use space in the async queue reserved for important logs.
+    fn high_priority(&self) -> bool {
+        false
+    }
 }
 
 pub trait ReportLog<T> {
@@ -58,6 +66,15 @@ impl LogFormat for ServiceLogV1 {
     const TIME_LIMIT_DAYS: u32 = 30;
 
     type Reporter = ServiceLogReporter;
+
+    const PRIORITIZED: bool = true;
+
+    fn high_priority(&self) -> bool {
+        matches!(
+            self.level(),
+            LogLevel::Fatal | LogLevel::Error | LogLevel::Warn
+        )
+    }
 }
 
 impl LogFormat for TraceLogV1 {
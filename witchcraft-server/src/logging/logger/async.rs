@@ -17,16 +17,22 @@ use crate::shutdown_hooks::ShutdownHooks;
 use core::fmt;
 use futures_sink::Sink;
 use futures_util::ready;
-use parking_lot::Mutex;
+use parking_lot::{Condvar, Mutex};
 use pin_project::pin_project;
 use std::collections::VecDeque;
 use std::error;
 use std::future::Future;
+use std::mem;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+use tokio::runtime::{Handle, RuntimeFlavor};
 use tokio::task::{self, JoinHandle};
-use witchcraft_metrics::{MetricId, MetricRegistry};
+use tokio::time;
+use witchcraft_log::warn;
+use witchcraft_metrics::{Meter, MetricId, MetricRegistry};
+use witchcraft_server_config::install::LogTypeConfig;
 
 #[derive(Debug)]
 pub struct Closed;
@@ -39,23 +45,50 @@ impl fmt::Display for Closed {
 
 impl error::Error for Closed {}
 
-const QUEUE_LIMIT: usize = 10_000;
+// The fraction of the queue's capacity which is reserved for high priority logs in prioritized formats.
+const RESERVED_FRACTION: usize = 10;
+const DROP_REPORT_INTERVAL: Duration = Duration::from_secs(60);
 
 struct State<T> {
     queue: VecDeque<Payload<T>>,
+    limit: usize,
     write_waker: Option<Waker>,
     read_waker: Option<Waker>,
     flushed: bool,
     closed: bool,
+    dropped: u64,
 }
 
 impl<T> State<T> {
     fn ready(&self) -> bool {
-        self.queue.len() < QUEUE_LIMIT
+        self.queue.len() < self.limit
+    }
+
+    fn ready_for(&self, item: &Payload<T>) -> bool
+    where
+        T: LogFormat,
+    {
+        if item.value.high_priority() {
+            self.ready()
+        } else {
+            self.queue.len() < self.limit - self.reserved()
+        }
+    }
+
+    // Always reserve at least one slot in prioritized formats unless that would leave no room for anything else.
+    fn reserved(&self) -> usize
+    where
+        T: LogFormat,
+    {
+        if T::PRIORITIZED && self.limit > 1 {
+            (self.limit / RESERVED_FRACTION).max(1)
+        } else {
+            0
+        }
     }
 
     fn start_send(&mut self, item: Payload<T>) {
-        debug_assert!(self.queue.len() < QUEUE_LIMIT);
+        debug_assert!(self.queue.len() < self.limit);
 
         self.queue.push_back(item);
         self.flushed = false;
@@ -82,6 +115,9 @@ impl<T> State<T> {
 
 pub struct AsyncAppender<T> {
     state: Arc<Mutex<State<T>>>,
+    not_full: Arc<Condvar>,
+    send_timeout: Option<Duration>,
+    dropped: Arc<Meter>,
 }
 
 impl<T> Drop for AsyncAppender<T> {
@@ -91,18 +127,26 @@ impl<T> Drop for AsyncAppender<T> {
 }
 
 impl<T> AsyncAppender<T> {
-    pub fn new<S>(inner: S, metrics: &MetricRegistry, hooks: &mut ShutdownHooks) -> Self
+    pub fn new<S>(
+        inner: S,
+        config: &LogTypeConfig,
+        metrics: &MetricRegistry,
+        hooks: &mut ShutdownHooks,
+    ) -> Self
     where
         S: Sink<Payload<T>> + 'static + Send,
         T: LogFormat + 'static + Send,
     {
         let state = Arc::new(Mutex::new(State {
             queue: VecDeque::new(),
+            limit: config.queue_size(),
             write_waker: None,
             read_waker: None,
             flushed: true,
             closed: false,
+            dropped: 0,
         }));
+        let not_full = Arc::new(Condvar::new());
 
         metrics.gauge(MetricId::new("logging.queue").with_tag("type", T::TYPE), {
             let state = state.clone();
@@ -111,21 +155,67 @@ impl<T> AsyncAppender<T> {
 
         let handle = task::spawn({
             let state = state.clone();
-            WorkerFuture { state, inner }
+            let not_full = not_full.clone();
+            WorkerFuture {
+                state,
+                not_full,
+                inner,
+            }
         });
 
+        task::spawn(report_dropped::<T>(Arc::downgrade(&state)));
+
         hooks.push(ShutdownFuture {
             state: state.clone(),
+            not_full: not_full.clone(),
             handle,
         });
 
-        AsyncAppender { state }
+        AsyncAppender {
+            state,
+            not_full,
+            send_timeout: config.send_timeout(),
+            dropped: metrics.meter(MetricId::new("logging.dropped").with_tag("type", T::TYPE)),
+        }
     }
 
-    pub fn try_send(&self, item: Payload<T>) -> Result<(), Payload<T>> {
+    /// Attempts to enqueue a log.
+    ///
+    /// If the queue is full, the log is dropped immediately unless the appender was configured with a send timeout, in
+    /// which case the calling thread blocks for up to that long waiting for space. Runtime worker threads are only
+    /// blocked via `block_in_place` on multi-threaded runtimes, and never on current-thread runtimes since the worker
+    /// draining the queue could not make progress.
+    pub fn try_send(&self, item: Payload<T>) -> Result<(), Payload<T>>
+    where
+        T: LogFormat,
+    {
         let mut state = self.state.lock();
 
-        if state.closed || !state.ready() {
+        if !state.closed && !state.ready_for(&item) {
+            if let Some(send_timeout) = self.send_timeout {
+                let deadline = Instant::now() + send_timeout;
+                let mut wait = || {
+                    while !state.closed && !state.ready_for(&item) {
+                        if self.not_full.wait_until(&mut state, deadline).timed_out() {
+                            break;
+                        }
+                    }
+                };
+
+                match Handle::try_current() {
+                    Err(_) => wait(),
+                    Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                        task::block_in_place(wait)
+                    }
+                    Ok(_) => {}
+                }
+            }
+        }
+
+        if state.closed || !state.ready_for(&item) {
+            state.dropped += 1;
+            drop(state);
+            self.dropped.mark(1);
             return Err(item);
         }
         state.start_send(item);
@@ -133,6 +223,32 @@ impl<T> AsyncAppender<T> {
     }
 }
 
+async fn report_dropped<T>(state: Weak<Mutex<State<T>>>)
+where
+    T: LogFormat,
+{
+    loop {
+        time::sleep(DROP_REPORT_INTERVAL).await;
+
+        let Some(state) = state.upgrade() else {
+            break;
+        };
+        let dropped = mem::take(&mut state.lock().dropped);
+        drop(state);
+
+        if dropped > 0 {
+            warn!(
+                "dropped logs because the async log queue was full",
+                safe: {
+                    type: T::TYPE,
+                    count: dropped,
+                    interval: format!("{:?}", DROP_REPORT_INTERVAL),
+                },
+            );
+        }
+    }
+}
+
 impl<T> Sink<Payload<T>> for AsyncAppender<T> {
     type Error = Closed;
 
@@ -197,6 +313,7 @@ impl<T> Sink<Payload<T>> for AsyncAppender<T> {
 #[pin_project]
 struct ShutdownFuture<T> {
     state: Arc<Mutex<State<T>>>,
+    not_full: Arc<Condvar>,
     #[pin]
     handle: JoinHandle<()>,
 }
@@ -208,6 +325,7 @@ impl<T> Future for ShutdownFuture<T> {
         let this = self.project();
 
         this.state.lock().start_close();
+        this.not_full.notify_all();
         let _ = ready!(this.handle.poll(cx));
 
         Poll::Ready(())
@@ -219,6 +337,7 @@ struct WorkerFuture<T, S> {
     #[pin]
     inner: S,
     state: Arc<Mutex<State<T>>>,
+    not_full: Arc<Condvar>,
 }
 
 impl<T, S> Future for WorkerFuture<T, S>
@@ -244,6 +363,7 @@ where
             if let Some(waker) = state.write_waker.take() {
                 waker.wake();
             }
+            this.not_full.notify_all();
 
             drop(state);
             let _ = this.inner.as_mut().start_send(value);
@@ -286,3 +406,170 @@ where
         Poll::Ready(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::logging::api::{LogLevel, MetricLogV1, ServiceLogV1};
+    use conjure_object::Utc;
+    use tokio::runtime::Runtime;
+
+    struct Stalled;
+
+    impl<T> Sink<T> for Stalled {
+        type Error = Closed;
+
+        fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+            Poll::Pending
+        }
+
+        fn start_send(self: Pin<&mut Self>, _: T) -> Result<(), Closed> {
+            Ok(())
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+            Poll::Pending
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+            Poll::Pending
+        }
+    }
+
+    fn log(level: LogLevel) -> Payload<ServiceLogV1> {
+        Payload {
+            value: ServiceLogV1::builder()
+                .type_("service.1")
+                .level(level)
+                .time(Utc::now())
+                .message("foo")
+                .build(),
+            cb: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn reserves_space_for_high_priority_logs() {
+        let metrics = MetricRegistry::new();
+        let config = LogTypeConfig::builder().queue_size(10).build().unwrap();
+        let appender = AsyncAppender::new(Stalled, &config, &metrics, &mut ShutdownHooks::new());
+
+        for _ in 0..9 {
+            assert!(appender.try_send(log(LogLevel::Info)).is_ok());
+        }
+        assert!(appender.try_send(log(LogLevel::Info)).is_err());
+        assert!(appender.try_send(log(LogLevel::Error)).is_ok());
+        assert!(appender.try_send(log(LogLevel::Error)).is_err());
+
+        let dropped = metrics.meter(MetricId::new("logging.dropped").with_tag("type", "service.1"));
+        assert_eq!(dropped.count(), 2);
+        assert_eq!(appender.state.lock().dropped, 2);
+    }
+
+    #[test]
+    fn reserves_space_in_small_queues() {
+        let runtime = Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        let config = LogTypeConfig::builder().queue_size(2).build().unwrap();
+        let appender = AsyncAppender::new(
+            Stalled,
+            &config,
+            &MetricRegistry::new(),
+            &mut ShutdownHooks::new(),
+        );
+
+        assert!(appender.try_send(log(LogLevel::Info)).is_ok());
+        assert!(appender.try_send(log(LogLevel::Info)).is_err());
+        assert!(appender.try_send(log(LogLevel::Error)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn no_reserved_space_for_unprioritized_logs() {
+        let config = LogTypeConfig::builder().queue_size(10).build().unwrap();
+        let appender = AsyncAppender::new(
+            Stalled,
+            &config,
+            &MetricRegistry::new(),
+            &mut ShutdownHooks::new(),
+        );
+
+        let log = || Payload {
+            value: MetricLogV1::builder()
+                .type_("metric.1")
+                .time(Utc::now())
+                .metric_name("foo")
+                .metric_type("gauge")
+                .build(),
+            cb: None,
+        };
+
+        for _ in 0..10 {
+            assert!(appender.try_send(log()).is_ok());
+        }
+        assert!(appender.try_send(log()).is_err());
+    }
+
+    #[test]
+    fn send_timeout() {
+        let runtime = Runtime::new().unwrap();
+        let config = LogTypeConfig::builder()
+            .queue_size(1)
+            .send_timeout(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let appender = {
+            let _guard = runtime.enter();
+            AsyncAppender::new(
+                Stalled,
+                &config,
+                &MetricRegistry::new(),
+                &mut ShutdownHooks::new(),
+            )
+        };
+
+        assert!(appender.try_send(log(LogLevel::Error)).is_ok());
+        let start = Instant::now();
+        assert!(appender.try_send(log(LogLevel::Error)).is_err());
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn send_timeout_multi_thread_runtime() {
+        let config = LogTypeConfig::builder()
+            .queue_size(1)
+            .send_timeout(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let appender = AsyncAppender::new(
+            Stalled,
+            &config,
+            &MetricRegistry::new(),
+            &mut ShutdownHooks::new(),
+        );
+
+        assert!(appender.try_send(log(LogLevel::Error)).is_ok());
+        let start = Instant::now();
+        assert!(appender.try_send(log(LogLevel::Error)).is_err());
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn send_timeout_current_thread_runtime() {
+        let config = LogTypeConfig::builder()
+            .queue_size(1)
+            .send_timeout(Duration::from_secs(10))
+            .build()
+            .unwrap();
+        let appender = AsyncAppender::new(
+            Stalled,
+            &config,
+            &MetricRegistry::new(),
+            &mut ShutdownHooks::new(),
+        );
+
+        assert!(appender.try_send(log(LogLevel::Error)).is_ok());
+        let start = Instant::now();
+        assert!(appender.try_send(log(LogLevel::Error)).is_err());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -15,7 +15,7 @@ use crate::logging::format::LogFormat;
 use crate::logging::logger::json::JsonAppender;
 use crate::logging::logger::metrics::MetricsAppender;
 use crate::logging::logger::r#async::AsyncAppender;
-use crate::logging::logger::rolling_file::RollingFileAppender;
+use crate::logging::logger::rolling_file::{RetentionPolicy, RollingFileAppender};
 use crate::logging::logger::stdout::StdoutAppender;
 use crate::shutdown_hooks::ShutdownHooks;
 use bytes::Bytes;
@@ -51,19 +51,32 @@ where
     T: Serialize + LogFormat + 'static + Send,
     T::Reporter: 'static + Send,
 {
-    let appender: Pin<Box<dyn Sink<Payload<Bytes>, Error = io::Error> + Sync + Send>> = if config
-        .use_console_log()
-    {
-        Box::pin(StdoutAppender::new())
-    } else {
-        let appender =
-            RollingFileAppender::new(T::FILE_STEM, T::SIZE_LIMIT_GB, T::TIME_LIMIT_DAYS).await?;
-        Box::pin(appender)
-    };
+    let type_config = config
+        .logging()
+        .types()
+        .get(T::TYPE)
+        .cloned()
+        .unwrap_or_default();
+
+    let appender: Pin<Box<dyn Sink<Payload<Bytes>, Error = io::Error> + Sync + Send>> =
+        if config.use_console_log() {
+            Box::pin(StdoutAppender::new())
+        } else {
+            let policy = RetentionPolicy {
+                max_file_size: type_config.max_file_size_bytes(),
+                max_archive_size: type_config
+                    .total_size_cap_bytes()
+                    .unwrap_or(u64::from(T::SIZE_LIMIT_GB) * 1024 * 1024 * 1024),
+                max_archive_days: type_config.retention_days().unwrap_or(T::TIME_LIMIT_DAYS),
+                compress: type_config.compress(),
+            };
+            let appender = RollingFileAppender::new(T::FILE_STEM, policy).await?;
+            Box::pin(appender)
+        };
 
     let appender = JsonAppender::new(appender);
     let appender = MetricsAppender::new(appender, metrics);
-    let appender = AsyncAppender::new(appender, metrics, hooks);
+    let appender = AsyncAppender::new(appender, &type_config, metrics, hooks);
 
     Ok(appender)
 }
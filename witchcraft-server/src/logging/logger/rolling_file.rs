@@ -31,7 +31,18 @@ use tokio::fs::{self, File, OpenOptions};
 use tokio::io::{self, AsyncWrite, AsyncWriteExt};
 use tokio::task;
 
-const MAX_LOG_SIZE: u64 = 1024 * 1024 * 1024;
+/// Limits on the size and age of a log type's files.
+#[derive(Copy, Clone, Debug)]
+pub struct RetentionPolicy {
+    /// The size at which the active log file is rotated.
+    pub max_file_size: u64,
+    /// The maximum total size of archived log files.
+    pub max_archive_size: u64,
+    /// The number of days archived log files are retained.
+    pub max_archive_days: u32,
+    /// Determines if archived log files are compressed.
+    pub compress: bool,
+}
 
 struct CurrentFile {
     sink: BufBytesSink<FileBytesSink>,
@@ -68,19 +79,12 @@ pub struct RollingFileAppender {
     state: State,
     next_archive_index: u32,
     name: &'static str,
-    max_archive_size: u64,
-    max_archive_days: u32,
+    policy: RetentionPolicy,
     archive_locator: Arc<ArchiveLocator>,
 }
 
 impl RollingFileAppender {
-    pub async fn new(
-        name: &'static str,
-        size_limit_gb: u32,
-        max_archive_days: u32,
-    ) -> Result<Self, Error> {
-        let max_archive_size = u64::from(size_limit_gb) * 1024 * 1024 * 1024;
-
+    pub async fn new(name: &'static str, policy: RetentionPolicy) -> Result<Self, Error> {
         let dir = log_dir();
         fs::create_dir_all(&dir)
             .await
@@ -109,22 +113,18 @@ impl RollingFileAppender {
             .max()
             .map_or(0, |n| n + 1);
 
-        clear_old_archives(
-            dir,
-            date,
-            max_archive_size,
-            max_archive_days,
-            &archive_locator,
-        )
-        .await
-        .map_err(Error::internal_safe)?;
-
-        clear_tmp_files(dir, &archive_locator)
+        clear_old_archives(dir, date, &policy, &archive_locator)
             .await
             .map_err(Error::internal_safe)?;
-        restart_compression(dir, name, &archive_locator)
+
+        clear_tmp_files(dir, &archive_locator)
             .await
             .map_err(Error::internal_safe)?;
+        if policy.compress {
+            restart_compression(dir, name, &archive_locator)
+                .await
+                .map_err(Error::internal_safe)?;
+        }
 
         Ok(RollingFileAppender {
             state: State::Live(CurrentFile {
@@ -134,8 +134,7 @@ impl RollingFileAppender {
             }),
             next_archive_index,
             name,
-            max_archive_size,
-            max_archive_days,
+            policy,
             archive_locator: Arc::new(archive_locator),
         })
     }
@@ -150,7 +149,7 @@ impl Sink<Payload<Bytes>> for RollingFileAppender {
             match &mut this.state {
                 State::Live(file) => {
                     let date = Utc::now().date_naive();
-                    if file.len < MAX_LOG_SIZE && date <= file.date {
+                    if file.len < this.policy.max_file_size && date <= file.date {
                         return file.poll_ready(cx);
                     }
 
@@ -168,8 +167,7 @@ impl Sink<Payload<Bytes>> for RollingFileAppender {
                         this.name,
                         file.date,
                         number,
-                        this.max_archive_size,
-                        this.max_archive_days,
+                        this.policy,
                         this.archive_locator.clone(),
                     )));
                 }
@@ -254,12 +252,15 @@ fn archive_gz_path(dir: &Path, name: &str, date: NaiveDate, number: u32) -> Path
 async fn clear_old_archives(
     dir: &Path,
     date: NaiveDate,
-    max_archive_size: u64,
-    max_archive_days: u32,
+    policy: &RetentionPolicy,
     archive_locator: &ArchiveLocator,
 ) -> io::Result<()> {
-    let logs = archive_locator.archived_logs(dir).await?;
-    clear_old_archives_inner(date, max_archive_size, max_archive_days, logs).await
+    let mut logs = archive_locator.archived_logs(dir).await?;
+    // with compression enabled, uncompressed archives are still being processed and will be picked up next time
+    if !policy.compress {
+        logs.extend(archive_locator.uncompressed_logs(dir).await?);
+    }
+    clear_old_archives_inner(date, policy.max_archive_size, policy.max_archive_days, logs).await
 }
 
 // split out for testing
@@ -340,8 +341,7 @@ async fn rotate(
     name: &'static str,
     date: NaiveDate,
     number: u32,
-    max_archive_size: u64,
-    max_archive_days: u32,
+    policy: RetentionPolicy,
     archive_locator: Arc<ArchiveLocator>,
 ) -> io::Result<File> {
     let log_path = log_path(dir, name);
@@ -351,16 +351,11 @@ async fn rotate(
 
     let dir = dir.to_path_buf();
     task::spawn(async move {
-        let _ = compress(&dir, name, date, number).await;
+        if policy.compress {
+            let _ = compress(&dir, name, date, number).await;
+        }
         // clear archives based on the current date rather than the date of the log being archived.
-        let _ = clear_old_archives(
-            &dir,
-            Utc::now().date_naive(),
-            max_archive_size,
-            max_archive_days,
-            &archive_locator,
-        )
-        .await;
+        let _ = clear_old_archives(&dir, Utc::now().date_naive(), &policy, &archive_locator).await;
     });
 
     open_log(&log_path).await
@@ -802,4 +797,41 @@ mod test {
         assert!(!service_archive_2_0_path.exists());
         assert!(service_archive_2_1_tmp_path.exists());
     }
+
+    #[tokio::test]
+    async fn clear_old_archives_uncompressed() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_locator = ArchiveLocator::new("service");
+
+        let day1 = NaiveDate::from_ymd_opt(2017, 4, 20).unwrap();
+        let service_archive_1_0_path = archive_path(dir.path(), "service", day1, 0);
+        File::create(&service_archive_1_0_path).await.unwrap();
+
+        let day2 = NaiveDate::from_ymd_opt(2017, 4, 21).unwrap();
+        let service_archive_2_0_path = archive_path(dir.path(), "service", day2, 0);
+        File::create(&service_archive_2_0_path).await.unwrap();
+
+        let mut policy = RetentionPolicy {
+            max_file_size: 1024,
+            max_archive_size: 1024,
+            max_archive_days: 1,
+            compress: true,
+        };
+
+        let date = NaiveDate::from_ymd_opt(2017, 4, 22).unwrap();
+        clear_old_archives(dir.path(), date, &policy, &archive_locator)
+            .await
+            .unwrap();
+
+        assert!(service_archive_1_0_path.exists());
+        assert!(service_archive_2_0_path.exists());
+
+        policy.compress = false;
+        clear_old_archives(dir.path(), date, &policy, &archive_locator)
+            .await
+            .unwrap();
+
+        assert!(!service_archive_1_0_path.exists());
+        assert!(service_archive_2_0_path.exists());
+    }
 }
@@ -0,0 +1,138 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::logging::api::MetricLogV1;
+use conjure_object::Any;
+use std::collections::HashMap;
+use witchcraft_metrics::MetricId;
+use witchcraft_server_config::runtime::{MetricFilterAction, MetricLoggingConfig};
+
+/// Determines which metrics are written to the metric log.
+#[derive(Default)]
+pub struct MetricFilter {
+    last: HashMap<MetricId, Any>,
+}
+
+impl MetricFilter {
+    pub fn new() -> Self {
+        MetricFilter::default()
+    }
+
+    /// Returns `true` if the metric is permitted by the configured filters.
+    ///
+    /// This is checked before the metric's values are computed.
+    pub fn allowed(&self, config: &MetricLoggingConfig, id: &MetricId) -> bool {
+        let filter = config.filters().iter().find(|f| {
+            f.name_prefix().is_none_or(|p| id.name().starts_with(p))
+                && f.tags()
+                    .iter()
+                    .all(|(k, v)| id.tags().iter().any(|t| t == (k, v)))
+        });
+
+        !matches!(filter.map(|f| f.action()), Some(MetricFilterAction::Deny))
+    }
+
+    /// Returns `true` if the metric's log should be written.
+    ///
+    /// If unchanged metrics are skipped, gauges are compared by their value and all other metrics by their count.
+    pub fn changed(
+        &mut self,
+        config: &MetricLoggingConfig,
+        id: &MetricId,
+        log: &MetricLogV1,
+    ) -> bool {
+        if !config.skip_unchanged() {
+            self.last.clear();
+            return true;
+        }
+
+        let Some(value) = log
+            .values()
+            .get("count")
+            .or_else(|| log.values().get("value"))
+        else {
+            return true;
+        };
+
+        if self.last.get(id) == Some(value) {
+            return false;
+        }
+
+        self.last.insert(id.clone(), value.clone());
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use conjure_object::Utc;
+    use witchcraft_server_config::runtime::MetricFilterConfig;
+
+    fn log(count: i64) -> MetricLogV1 {
+        MetricLogV1::builder()
+            .type_("metric.1")
+            .time(Utc::now())
+            .metric_name("server.requests")
+            .metric_type("meter")
+            .insert_values("count", count)
+            .build()
+    }
+
+    #[test]
+    fn allowed() {
+        let config = MetricLoggingConfig::builder()
+            .push_filters(
+                MetricFilterConfig::builder()
+                    .action(MetricFilterAction::Allow)
+                    .name_prefix("server.response".to_string())
+                    .insert_tags("endpoint", "ping")
+                    .build(),
+            )
+            .push_filters(
+                MetricFilterConfig::builder()
+                    .action(MetricFilterAction::Deny)
+                    .name_prefix("server.response".to_string())
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        let filter = MetricFilter::new();
+
+        assert!(filter.allowed(
+            &config,
+            &MetricId::new("server.response").with_tag("endpoint", "ping")
+        ));
+        assert!(!filter.allowed(
+            &config,
+            &MetricId::new("server.response").with_tag("endpoint", "pong")
+        ));
+        assert!(!filter.allowed(&config, &MetricId::new("server.response")));
+        assert!(filter.allowed(&config, &MetricId::new("process.uptime")));
+    }
+
+    #[test]
+    fn skip_unchanged() {
+        let config = MetricLoggingConfig::builder()
+            .skip_unchanged(true)
+            .build()
+            .unwrap();
+        let mut filter = MetricFilter::new();
+        let id = MetricId::new("server.requests");
+
+        assert!(filter.changed(&config, &id, &log(1)));
+        assert!(!filter.changed(&config, &id, &log(1)));
+        assert!(filter.changed(&config, &id, &log(2)));
+        assert!(filter.changed(&MetricLoggingConfig::default(), &id, &log(2)));
+    }
+}
@@ -11,8 +11,7 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use crate::logging::api::MetricLogV1;
-use crate::logging::metric;
+use crate::logging::metric::metric_name;
 use futures_util::stream::FuturesUnordered;
 use futures_util::{ready, Stream};
 use pin_project::pin_project;
@@ -39,18 +38,26 @@ const TOO_LONG_THRESHOLD: Duration = Duration::from_millis(500);
 /// throttled" approach. We use a semaphore to only allow 2 gauges to evaluate at a time, but if we detect that the
 /// gauge is slow (i.e. it's taking at least .5 seconds to run), we release its permit early. This should allow us to
 /// end up with the slow gauges running in parallel while the fast gauges are throttled by the concurrency limit.
-pub struct GaugeReporter {
+///
+/// The `record` function is invoked on the blocking task to evaluate the gauge and convert its value to the output
+/// type.
+pub struct GaugeReporter<T> {
     ids: HashSet<MetricId>,
-    results: FuturesUnordered<GaugeFuture>,
+    results: FuturesUnordered<GaugeFuture<T>>,
     semaphore: Arc<Semaphore>,
+    record: fn(&MetricId, &dyn Gauge) -> T,
 }
 
-impl GaugeReporter {
-    pub fn new() -> Self {
+impl<T> GaugeReporter<T>
+where
+    T: 'static + Send,
+{
+    pub fn new(record: fn(&MetricId, &dyn Gauge) -> T) -> Self {
         GaugeReporter {
             ids: HashSet::new(),
             results: FuturesUnordered::new(),
             semaphore: Arc::new(Semaphore::new(TARGET_CONCURRENT_TASKS)),
+            record,
         }
     }
 
@@ -63,6 +70,7 @@ impl GaugeReporter {
             id: Some(id.clone()),
             gauge: Some(gauge.clone()),
             semaphore: PollSemaphore::new(self.semaphore.clone()),
+            record: self.record,
         });
 
         true
@@ -73,8 +81,11 @@ impl GaugeReporter {
     }
 }
 
-impl Stream for GaugeReporter {
-    type Item = Result<MetricLogV1, JoinError>;
+impl<T> Stream for GaugeReporter<T>
+where
+    T: 'static + Send,
+{
+    type Item = Result<T, JoinError>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let item = ready!(Pin::new(&mut self.results).poll_next(cx));
@@ -87,23 +98,27 @@ impl Stream for GaugeReporter {
 
 #[pin_project(project = GaugeFutureProj)]
 #[allow(clippy::large_enum_variant)]
-enum GaugeFuture {
+enum GaugeFuture<T> {
     Acquiring {
         id: Option<MetricId>,
         gauge: Option<Arc<dyn Gauge>>,
         semaphore: PollSemaphore,
+        record: fn(&MetricId, &dyn Gauge) -> T,
     },
     Running {
         id: Option<MetricId>,
-        handle: JoinHandle<MetricLogV1>,
+        handle: JoinHandle<T>,
         permit: Option<OwnedSemaphorePermit>,
         #[pin]
         timeout: Sleep,
     },
 }
 
-impl Future for GaugeFuture {
-    type Output = (MetricId, Result<MetricLogV1, JoinError>);
+impl<T> Future for GaugeFuture<T>
+where
+    T: 'static + Send,
+{
+    type Output = (MetricId, Result<T, JoinError>);
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         loop {
@@ -112,21 +127,19 @@ impl Future for GaugeFuture {
                     id,
                     gauge,
                     semaphore,
+                    record,
                 } => {
                     let permit = ready!(semaphore.poll_acquire(cx));
                     let id = id.take().unwrap();
                     let gauge = gauge.take().unwrap();
+                    let record = *record;
                     let handle = task::spawn_blocking({
                         let id = id.clone();
                         move || {
                             let _span = zipkin::new_trace()
-                                .with_name(&format!("record-gauge: {}", metric::metric_name(&id)));
+                                .with_name(&format!("record-gauge: {}", metric_name(&id)));
 
-                            let value = gauge.value();
-                            let builder = metric::builder(&id)
-                                .metric_type("gauge")
-                                .insert_values("value", value);
-                            metric::finish_log(&id, builder)
+                            record(&id, &*gauge)
                         }
                     });
 
@@ -14,6 +14,7 @@
 use crate::logging::api::{metric_log_v1, MetricLogV1};
 use crate::logging::logger::r#async::Closed;
 use crate::logging::logger::{self, Appender, Payload};
+use crate::logging::metric::filter::MetricFilter;
 use crate::logging::metric::gauge_reporter::GaugeReporter;
 use crate::shutdown_hooks::ShutdownHooks;
 use conjure_error::Error;
@@ -21,30 +22,36 @@ use conjure_object::Utc;
 use futures_sink::Sink;
 use futures_util::{ready, SinkExt, Stream};
 use pin_project::pin_project;
+use refreshable::Refreshable;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::time::Duration;
 use tokio::task;
 use tokio::time::{self, Instant};
 use witchcraft_log::warn;
-use witchcraft_metrics::{Metric, MetricId, MetricRegistry};
+use witchcraft_metrics::{Gauge, Metric, MetricId, MetricRegistry};
 use witchcraft_server_config::install::InstallConfig;
+use witchcraft_server_config::runtime::{LoggingConfig, MetricLoggingConfig};
 
-mod gauge_reporter;
+mod filter;
+pub(crate) mod gauge_reporter;
 
-const LOG_INTERVAL: Duration = Duration::from_secs(30);
 const NANOS_PER_MICRO: i64 = 1_000;
 const NANOS_PER_MICRO_F64: f64 = NANOS_PER_MICRO as f64;
 
 pub async fn init(
     metrics: &Arc<MetricRegistry>,
     install: &InstallConfig,
+    runtime: &Refreshable<LoggingConfig, Error>,
     hooks: &mut ShutdownHooks,
 ) -> Result<(), Error> {
     let appender = logger::appender(install, metrics, hooks).await?;
-    task::spawn(log_metrics(appender, metrics.clone()));
+    task::spawn(log_metrics(
+        appender,
+        metrics.clone(),
+        runtime.map(|c| c.metrics().clone()),
+    ));
 
     Ok(())
 }
@@ -60,15 +67,32 @@ pub async fn init(
 /// tasks. We collect and output the results of the gauges during the "idle" time when waiting for the next collection
 /// interval. This makes the implementation a bit more complex but avoids having to have multiple owners of the
 /// appender.
-async fn log_metrics(mut appender: Appender<MetricLogV1>, metrics: Arc<MetricRegistry>) {
-    let mut gauge_reporter = GaugeReporter::new();
+async fn log_metrics(
+    mut appender: Appender<MetricLogV1>,
+    metrics: Arc<MetricRegistry>,
+    config: Refreshable<MetricLoggingConfig, Error>,
+) {
+    let mut gauge_reporter = GaugeReporter::new(gauge_log);
+    let mut filter = MetricFilter::new();
 
-    let mut next = Instant::now() + LOG_INTERVAL;
+    let mut next = Instant::now() + config.get().interval();
 
     loop {
-        idle(&mut gauge_reporter, &mut appender, next).await;
+        idle(
+            &mut gauge_reporter,
+            &mut appender,
+            &mut filter,
+            &config,
+            next,
+        )
+        .await;
 
+        let current = config.get().clone();
         for (id, metric) in &metrics.metrics() {
+            if !filter.allowed(&current, id) {
+                continue;
+            }
+
             let builder = match metric {
                 Metric::Counter(m) => builder(id)
                     .metric_type("counter")
@@ -111,6 +135,10 @@ async fn log_metrics(mut appender: Appender<MetricLogV1>, metrics: Arc<MetricReg
             };
 
             let metric = finish_log(id, builder);
+            if !filter.changed(&current, id, &metric) {
+                continue;
+            }
+
             if let Err(Closed) = Pin::new(&mut appender)
                 .feed(Payload {
                     value: metric,
@@ -122,10 +150,23 @@ async fn log_metrics(mut appender: Appender<MetricLogV1>, metrics: Arc<MetricReg
             }
         }
 
-        next += LOG_INTERVAL;
+        next += current.interval();
     }
 }
 
+// Gauges without a current value are omitted rather than logged with a null value.
+fn gauge_log(id: &MetricId, gauge: &dyn Gauge) -> (MetricId, Option<MetricLogV1>) {
+    let value = gauge.value();
+    if let Ok(serde_json::Value::Null) = serde_json::to_value(&value) {
+        return (id.clone(), None);
+    }
+
+    let builder = builder(id)
+        .metric_type("gauge")
+        .insert_values("value", value);
+    (id.clone(), Some(finish_log(id, builder)))
+}
+
 fn metric_name(id: &MetricId) -> String {
     let mut name = id.name().to_string();
 
@@ -168,13 +209,17 @@ fn finish_log(
 }
 
 async fn idle(
-    gauge_reporter: &mut GaugeReporter,
+    gauge_reporter: &mut GaugeReporter<(MetricId, Option<MetricLogV1>)>,
     appender: &mut Appender<MetricLogV1>,
+    filter: &mut MetricFilter,
+    config: &Refreshable<MetricLoggingConfig, Error>,
     timeout: Instant,
 ) {
     IdleFuture {
         gauge_reporter,
         appender,
+        filter,
+        config,
         sleep: time::sleep_until(timeout),
     }
     .await
@@ -182,8 +227,10 @@ async fn idle(
 
 #[pin_project]
 struct IdleFuture<'a> {
-    gauge_reporter: &'a mut GaugeReporter,
+    gauge_reporter: &'a mut GaugeReporter<(MetricId, Option<MetricLogV1>)>,
     appender: &'a mut Appender<MetricLogV1>,
+    filter: &'a mut MetricFilter,
+    config: &'a Refreshable<MetricLoggingConfig, Error>,
     #[pin]
     sleep: time::Sleep,
 }
@@ -208,7 +255,11 @@ impl Future for IdleFuture<'_> {
                 None => break,
             };
 
-            if let Ok(log) = result {
+            if let Ok((id, Some(log))) = result {
+                if !this.filter.changed(&this.config.get(), &id, &log) {
+                    continue;
+                }
+
                 if let Err(Closed) = Pin::new(&mut **this.appender).start_send(Payload {
                     value: log,
                     cb: None,
@@ -22,12 +22,18 @@ use futures::executor::block_on;
 use futures_channel::oneshot;
 use lazycell::AtomicLazyCell;
 pub(crate) use logger::{Appender, Payload};
+pub(crate) use metric::gauge_reporter::GaugeReporter;
 use once_cell::sync::OnceCell;
+pub(crate) use redaction::{redact_audit_log, redact_request_log};
 use refreshable::Refreshable;
+pub(crate) use service::{
+    level_override, request_level, set_request_level, set_temporary_level, OverrideRequest,
+};
 use std::io;
 use std::io::Write as _;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+pub(crate) use trace::{sample_request, DeferredSpan, RequestSampling};
 use witchcraft_metrics::MetricRegistry;
 use witchcraft_server_config::install::InstallConfig;
 use witchcraft_server_config::runtime::LoggingConfig;
@@ -41,7 +47,10 @@ mod format;
 mod logger;
 pub mod mdc;
 mod metric;
+mod rate_limit;
+mod redaction;
 mod service;
+mod subscriber;
 mod trace;
 
 pub(crate) static AUDIT_LOGGER: AtomicLazyCell<Arc<Mutex<Appender<AuditLogV3>>>> =
@@ -58,7 +67,7 @@ pub(crate) struct Loggers {
 }
 
 pub(crate) fn early_init() {
-    service::early_init()
+    service::early_init();
 }
 
 pub(crate) async fn init(
@@ -67,7 +76,8 @@ pub(crate) async fn init(
     runtime: &Refreshable<LoggingConfig, Error>,
     hooks: &mut ShutdownHooks,
 ) -> Result<Loggers, Error> {
-    metric::init(metrics, install, hooks).await?;
+    redaction::init(metrics, runtime);
+    metric::init(metrics, install, runtime, hooks).await?;
     service::init(metrics, install, runtime, hooks).await?;
     trace::init(metrics, install, runtime, hooks).await?;
     let request_logger = logger::appender(install, metrics, hooks).await?;
@@ -86,7 +96,7 @@ pub(crate) async fn init(
         .ok()
         .expect("Event logger already initialized");
 
-    cleanup::cleanup_logs().await;
+    cleanup::cleanup_logs(install).await;
 
     Ok(Loggers {
         request_logger,
@@ -94,6 +104,19 @@ pub(crate) async fn init(
     })
 }
 
+/// Returns a [`tracing`] subscriber which forwards events to the service log and spans to the trace log.
+///
+/// The subscriber is not installed automatically. Services which do not install their own subscriber can install it
+/// as the global default, typically at the start of their initialization callback:
+///
+/// ```
+/// tracing::subscriber::set_global_default(witchcraft_server::logging::tracing_subscriber())
+///     .expect("tracing subscriber already installed");
+/// ```
+pub fn tracing_subscriber() -> impl tracing::Subscriber + Send + Sync {
+    subscriber::WitchcraftSubscriber::new(subscriber::ServiceSink)
+}
+
 /// Write the provided v3 audit log entry to the audit log using the global audit logger.
 /// Returns an error if the global audit logger is not initialized.
 ///
@@ -109,7 +132,7 @@ pub async fn audit_log(entry: AuditLogEntry) -> Result<(), Error> {
         .lock()
         .await
         .try_send(Payload {
-            value: entry.0,
+            value: redact_audit_log(entry.0),
             cb: Some(tx),
         })
         .map_err(|_| Error::internal_safe("Audit logger is closed or not ready"))?;
@@ -0,0 +1,214 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::logging::api::ServiceLogV1;
+use crate::logging::format::LogFormat;
+use conjure_error::Error;
+use parking_lot::Mutex;
+use refreshable::Refreshable;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use witchcraft_log::{Level, Record};
+use witchcraft_metrics::{Meter, MetricId, MetricRegistry};
+use witchcraft_server_config::runtime::LogRateLimitConfig;
+
+// Sites are tracked in a fixed-size open-addressed table, which bounds the limiter's memory if something is logging
+// with an unbounded set of origins. Logs from sites which can't find a slot are never suppressed.
+const SLOTS: usize = 4096;
+const MAX_PROBES: usize = 8;
+const EMPTY: u64 = 0;
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A fixed-window rate limiter for service logs, keyed by message template and origin.
+///
+/// Logs are only counted with atomics, so the limiter doesn't add contention to the logging hot path.
+pub struct RateLimiter {
+    config: Refreshable<Option<LogRateLimitConfig>, Error>,
+    slots: Box<[Slot]>,
+    suppressed: Arc<Meter>,
+}
+
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    count: AtomicU64,
+    suppressed: AtomicU64,
+    // Only recorded when the site first has a log suppressed in a window.
+    site: Mutex<Option<Site>>,
+}
+
+struct Site {
+    message: &'static str,
+    origin: String,
+    level: Level,
+}
+
+/// A record of the logs suppressed from a single site over an interval.
+pub struct Suppressed {
+    pub message: &'static str,
+    pub origin: String,
+    pub level: Level,
+    pub count: u64,
+}
+
+impl RateLimiter {
+    pub fn new(
+        config: Refreshable<Option<LogRateLimitConfig>, Error>,
+        metrics: &MetricRegistry,
+    ) -> Self {
+        RateLimiter {
+            config,
+            slots: (0..SLOTS).map(|_| Slot::default()).collect(),
+            suppressed: metrics
+                .meter(MetricId::new("logging.suppressed").with_tag("type", ServiceLogV1::TYPE)),
+        }
+    }
+
+    /// Returns the current length of the rate limiting window.
+    pub fn interval(&self) -> Duration {
+        self.config
+            .get()
+            .as_ref()
+            .map_or(DEFAULT_INTERVAL, |c| c.interval())
+    }
+
+    /// Records a log, returning `true` if it should be emitted.
+    pub fn check(&self, record: &Record<'_>) -> bool {
+        let config = self.config.get();
+        let Some(config) = &*config else {
+            return true;
+        };
+
+        let Some(slot) = self.slot(key(record)) else {
+            return true;
+        };
+
+        if slot.count.fetch_add(1, Ordering::Relaxed) < config.max_logs() {
+            return true;
+        }
+
+        if slot.suppressed.fetch_add(1, Ordering::Relaxed) == 0 {
+            *slot.site.lock() = Some(Site {
+                message: record.message(),
+                origin: record.target().to_string(),
+                level: record.level(),
+            });
+        }
+        self.suppressed.mark(1);
+
+        false
+    }
+
+    fn slot(&self, key: u64) -> Option<&Slot> {
+        (0..MAX_PROBES)
+            .map(|i| &self.slots[(key as usize).wrapping_add(i) % SLOTS])
+            .find(|slot| {
+                match slot
+                    .key
+                    .compare_exchange(EMPTY, key, Ordering::AcqRel, Ordering::Acquire)
+                {
+                    Ok(_) => true,
+                    Err(existing) => existing == key,
+                }
+            })
+    }
+
+    /// Starts a new window, returning the sites which had logs suppressed during the previous one.
+    pub fn reset(&self) -> Vec<Suppressed> {
+        self.slots
+            .iter()
+            .filter(|slot| slot.key.load(Ordering::Acquire) != EMPTY)
+            .filter_map(|slot| {
+                let count = slot.suppressed.swap(0, Ordering::Relaxed);
+                let site = slot.site.lock().take();
+                slot.count.store(0, Ordering::Relaxed);
+                slot.key.store(EMPTY, Ordering::Release);
+
+                let site = site.filter(|_| count > 0)?;
+                Some(Suppressed {
+                    message: site.message,
+                    origin: site.origin,
+                    level: site.level,
+                    count,
+                })
+            })
+            .collect()
+    }
+}
+
+fn key(record: &Record<'_>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    record.message().hash(&mut hasher);
+    record.target().hash(&mut hasher);
+    // reserve 0 for empty slots
+    hasher.finish().max(1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(message: &'static str, target: &'static str) -> Record<'static> {
+        Record::builder()
+            .level(Level::Error)
+            .target(target)
+            .message(message)
+            .build()
+    }
+
+    #[test]
+    fn suppresses_repeats() {
+        let (config, _handle) = Refreshable::new(Some(
+            LogRateLimitConfig::builder().max_logs(2).build().unwrap(),
+        ));
+        let metrics = MetricRegistry::new();
+        let limiter = RateLimiter::new(config, &metrics);
+
+        assert!(limiter.check(&record("foo", "a")));
+        assert!(limiter.check(&record("foo", "a")));
+        assert!(!limiter.check(&record("foo", "a")));
+        assert!(!limiter.check(&record("foo", "a")));
+        assert!(limiter.check(&record("foo", "b")));
+        assert!(limiter.check(&record("bar", "a")));
+
+        let suppressed = limiter.reset();
+        assert_eq!(suppressed.len(), 1);
+        assert_eq!(suppressed[0].message, "foo");
+        assert_eq!(suppressed[0].origin, "a");
+        assert_eq!(suppressed[0].level, Level::Error);
+        assert_eq!(suppressed[0].count, 2);
+        assert_eq!(
+            metrics
+                .meter(MetricId::new("logging.suppressed").with_tag("type", "service.1"))
+                .count(),
+            2,
+        );
+
+        assert!(limiter.check(&record("foo", "a")));
+        assert!(limiter.reset().is_empty());
+    }
+
+    #[test]
+    fn disabled() {
+        let (config, _handle) = Refreshable::new(None);
+        let limiter = RateLimiter::new(config, &MetricRegistry::new());
+
+        for _ in 0..1_000 {
+            assert!(limiter.check(&record("foo", "a")));
+        }
+        assert!(limiter.reset().is_empty());
+    }
+}
@@ -0,0 +1,615 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::logging::api::{
+    audit_log_v3, request_log_v2, service_log_v1, AuditLogV3, RequestLogV2, SensitivityTaggedValue,
+    ServiceLogV1,
+};
+use crate::logging::format::LogFormat;
+use arc_swap::ArcSwap;
+use conjure_error::Error;
+use conjure_object::Any;
+use once_cell::sync::OnceCell;
+use refreshable::{Refreshable, Subscription};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use witchcraft_metrics::{MetricId, MetricRegistry};
+use witchcraft_server_config::runtime::{
+    LoggingConfig, RedactionAction, RedactionConfig, RedactionRuleConfig,
+};
+
+const MASK: &str = "[REDACTED]";
+const RULES_FIRED_PARAM: &str = "redactionRulesFired";
+const MESSAGE_KEY: &str = "message";
+const STACKTRACE_KEY: &str = "stacktrace";
+
+static REDACTOR: OnceCell<Redactor> = OnceCell::new();
+
+pub fn init(metrics: &Arc<MetricRegistry>, runtime: &Refreshable<LoggingConfig, Error>) {
+    let rules = Arc::new(ArcSwap::new(Arc::new(Rules::empty())));
+    let subscription = runtime.subscribe({
+        let rules = rules.clone();
+        move |config| rules.store(Arc::new(Rules::new(config.redaction())))
+    });
+
+    let redactor = Redactor {
+        rules,
+        metrics: metrics.clone(),
+        _subscription: subscription,
+    };
+    REDACTOR
+        .set(redactor)
+        .ok()
+        .expect("redactor already initialized");
+}
+
+/// Applies the configured redaction rules to a service log's unsafe parameters, message and stacktrace.
+///
+/// An unsafe message, like those of events captured from the `tracing` crate, and the stacktrace are matched against the
+/// rules under the `message` and `stacktrace` keys respectively.
+pub fn redact_service_log(log: ServiceLogV1) -> ServiceLogV1 {
+    let Some(redactor) = REDACTOR.get() else {
+        return log;
+    };
+    let rules = redactor.rules.load();
+    if rules.rules.is_empty() {
+        return log;
+    }
+
+    let (log, fired) = rules.redact_service_log(log);
+    redactor.report::<ServiceLogV1>(&fired);
+    log
+}
+
+/// Applies the configured redaction rules to a request log's unsafe parameters.
+pub fn redact_request_log(log: RequestLogV2) -> RequestLogV2 {
+    let Some(redactor) = REDACTOR.get() else {
+        return log;
+    };
+    let rules = redactor.rules.load();
+    if rules.rules.is_empty() {
+        return log;
+    }
+
+    let mut unsafe_params = log.unsafe_params().clone();
+    let fired = rules.apply(&mut unsafe_params);
+    if fired.is_empty() {
+        return log;
+    }
+    redactor.report::<RequestLogV2>(&fired);
+
+    let builder = request_log_v2::Builder::from(log);
+    if rules.test_mode {
+        builder.insert_params(RULES_FIRED_PARAM, fired).build()
+    } else {
+        builder.unsafe_params(unsafe_params).build()
+    }
+}
+
+/// Applies the configured redaction rules to an audit log's request and result fields, along with the deprecated
+/// request and result parameters.
+///
+/// Audit logs have no place to record the rules which fired in test mode, so they are only reported via metrics.
+pub fn redact_audit_log(log: AuditLogV3) -> AuditLogV3 {
+    let Some(redactor) = REDACTOR.get() else {
+        return log;
+    };
+    let rules = redactor.rules.load();
+    if rules.rules.is_empty() {
+        return log;
+    }
+
+    let (log, fired) = rules.redact_audit_log(log);
+    redactor.report::<AuditLogV3>(&fired);
+    log
+}
+
+struct Redactor {
+    rules: Arc<ArcSwap<Rules>>,
+    metrics: Arc<MetricRegistry>,
+    _subscription: Subscription<LoggingConfig, Error>,
+}
+
+impl Redactor {
+    fn report<T>(&self, fired: &BTreeSet<String>)
+    where
+        T: LogFormat,
+    {
+        for rule in fired {
+            self.metrics
+                .meter(
+                    MetricId::new("logging.redacted")
+                        .with_tag("type", T::TYPE)
+                        .with_tag("rule", rule.clone()),
+                )
+                .mark(1);
+        }
+    }
+}
+
+struct Rules {
+    test_mode: bool,
+    rules: Vec<Rule>,
+}
+
+impl Rules {
+    fn empty() -> Self {
+        Rules {
+            test_mode: false,
+            rules: vec![],
+        }
+    }
+
+    fn new(config: &RedactionConfig) -> Self {
+        Rules {
+            test_mode: config.test_mode(),
+            rules: config.rules().iter().map(Rule::new).collect(),
+        }
+    }
+
+    fn redact_service_log(&self, log: ServiceLogV1) -> (ServiceLogV1, BTreeSet<String>) {
+        let mut unsafe_params = log.unsafe_params().clone();
+        let mut fired = self.apply(&mut unsafe_params);
+
+        let mut message = None;
+        if log.safe() == Some(false) {
+            let mut unsafe_message = log.message().to_string();
+            fired.extend(self.apply_to_field(MESSAGE_KEY, &mut unsafe_message));
+            message = Some(unsafe_message);
+        }
+
+        let mut stacktrace = log.stacktrace().map(str::to_string);
+        if let Some(stacktrace) = &mut stacktrace {
+            fired.extend(self.apply_to_field(STACKTRACE_KEY, stacktrace));
+        }
+
+        if fired.is_empty() {
+            return (log, fired);
+        }
+
+        let mut builder = service_log_v1::Builder::from(log);
+        if self.test_mode {
+            builder = builder.insert_params(RULES_FIRED_PARAM, &fired);
+        } else {
+            builder = builder.unsafe_params(unsafe_params).stacktrace(stacktrace);
+            if let Some(message) = message {
+                builder = builder.message(message);
+            }
+        }
+
+        (builder.build(), fired)
+    }
+
+    #[allow(deprecated)]
+    fn redact_audit_log(&self, log: AuditLogV3) -> (AuditLogV3, BTreeSet<String>) {
+        let mut request_fields = log.request_fields().clone();
+        let mut result_fields = log.result_fields().clone();
+        let mut request_params = log.request_params().clone();
+        let mut result_params = log.result_params().clone();
+        let mut fired = self.apply(&mut request_fields);
+        fired.extend(self.apply(&mut result_fields));
+        fired.extend(self.apply_tagged(&mut request_params));
+        fired.extend(self.apply_tagged(&mut result_params));
+
+        if self.test_mode || fired.is_empty() {
+            return (log, fired);
+        }
+
+        let log = audit_log_v3::Builder::from(log)
+            .request_fields(request_fields)
+            .result_fields(result_fields)
+            .request_params(request_params)
+            .result_params(result_params)
+            .build();
+        (log, fired)
+    }
+
+    /// Applies the rules to the payloads of sensitivity-tagged parameters, returning the names of the rules which fired.
+    fn apply_tagged(
+        &self,
+        params: &mut BTreeMap<String, SensitivityTaggedValue>,
+    ) -> BTreeSet<String> {
+        let mut payloads = params
+            .iter()
+            .map(|(key, value)| (key.clone(), value.payload().clone()))
+            .collect();
+
+        let fired = self.apply(&mut payloads);
+        if self.test_mode || fired.is_empty() {
+            return fired;
+        }
+
+        params.retain(|key, _| payloads.contains_key(key));
+        for (key, value) in params.iter_mut() {
+            if let Some(payload) = payloads.remove(key) {
+                *value = SensitivityTaggedValue::builder()
+                    .payload(payload)
+                    .level(value.level().to_vec())
+                    .build();
+            }
+        }
+
+        fired
+    }
+
+    /// Applies the rules to a single unsafe field of a log, returning the names of the rules which fired.
+    ///
+    /// The field can't be removed from the log, so it is masked if a rule would drop it.
+    fn apply_to_field(&self, key: &str, field: &mut String) -> BTreeSet<String> {
+        let Ok(value) = Any::new(&*field) else {
+            return BTreeSet::new();
+        };
+        let mut params = BTreeMap::from([(key.to_string(), value)]);
+
+        let fired = self.apply(&mut params);
+        if self.test_mode || fired.is_empty() {
+            return fired;
+        }
+
+        *field = params
+            .remove(key)
+            .and_then(|value| String::deserialize(value).ok())
+            .unwrap_or_else(|| MASK.to_string());
+        fired
+    }
+
+    /// Applies the rules to the parameters, returning the names of the rules which fired.
+    ///
+    /// In test mode, the parameters are left unmodified.
+    fn apply(&self, params: &mut BTreeMap<String, Any>) -> BTreeSet<String> {
+        let mut fired = BTreeSet::new();
+        if params.is_empty() {
+            return fired;
+        }
+
+        let mut values = params
+            .iter()
+            .map(|(key, value)| (key.clone(), serde_json::to_value(value).ok()))
+            .collect::<BTreeMap<_, _>>();
+
+        for rule in &self.rules {
+            values.retain(|key, value| {
+                let Some(value) = value else {
+                    return true;
+                };
+
+                match rule.apply(key, value) {
+                    Outcome::Unmatched => true,
+                    Outcome::Modified => {
+                        fired.insert(rule.name.clone());
+                        true
+                    }
+                    Outcome::Dropped => {
+                        fired.insert(rule.name.clone());
+                        false
+                    }
+                }
+            });
+        }
+
+        if self.test_mode || fired.is_empty() {
+            return fired;
+        }
+
+        params.retain(|key, _| values.contains_key(key));
+        for (key, value) in values {
+            if let (Some(value), Some(param)) = (value, params.get_mut(&key)) {
+                if let Ok(value) = Any::new(value) {
+                    *param = value;
+                }
+            }
+        }
+
+        fired
+    }
+}
+
+struct Rule {
+    name: String,
+    key_pattern: Option<Regex>,
+    value_pattern: Option<Regex>,
+    action: RedactionAction,
+}
+
+enum Outcome {
+    Unmatched,
+    Modified,
+    Dropped,
+}
+
+impl Rule {
+    fn new(config: &RedactionRuleConfig) -> Self {
+        // patterns are validated when the config is deserialized
+        Rule {
+            name: config.name().to_string(),
+            key_pattern: config.key_pattern().and_then(|p| Regex::new(p).ok()),
+            value_pattern: config.value_pattern().and_then(|p| Regex::new(p).ok()),
+            action: config.action(),
+        }
+    }
+
+    fn apply(&self, key: &str, value: &mut Value) -> Outcome {
+        if self.key_pattern.as_ref().is_some_and(|p| !p.is_match(key)) {
+            return Outcome::Unmatched;
+        }
+
+        let Some(value_pattern) = &self.value_pattern else {
+            match self.action {
+                RedactionAction::Drop => return Outcome::Dropped,
+                RedactionAction::Hash => {
+                    let hash = match &*value {
+                        Value::String(s) => hash(s),
+                        other => hash(&other.to_string()),
+                    };
+                    *value = Value::String(hash);
+                }
+                _ => *value = Value::String(MASK.to_string()),
+            }
+            return Outcome::Modified;
+        };
+
+        if !redact_strings(value, &mut |s| {
+            if !value_pattern.is_match(s) {
+                return false;
+            }
+
+            if let RedactionAction::Hash = self.action {
+                *s = value_pattern
+                    .replace_all(s, |c: &Captures<'_>| hash(&c[0]))
+                    .into_owned();
+            } else {
+                *s = value_pattern.replace_all(s, MASK).into_owned();
+            }
+            true
+        }) {
+            return Outcome::Unmatched;
+        }
+
+        if let RedactionAction::Drop = self.action {
+            Outcome::Dropped
+        } else {
+            Outcome::Modified
+        }
+    }
+}
+
+/// Applies a function to every string in a JSON value, returning `true` if it returned `true` for any of them.
+fn redact_strings(value: &mut Value, f: &mut dyn FnMut(&mut String) -> bool) -> bool {
+    match value {
+        Value::String(s) => f(s),
+        Value::Array(values) => values
+            .iter_mut()
+            .fold(false, |matched, v| redact_strings(v, f) | matched),
+        Value::Object(values) => values
+            .values_mut()
+            .fold(false, |matched, v| redact_strings(v, f) | matched),
+        _ => false,
+    }
+}
+
+fn hash(s: &str) -> String {
+    format!("sha256:{:x}", Sha256::digest(s.as_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::logging::api::{AuditProducer, AuditResult, LogLevel};
+    use conjure_object::{Utc, Uuid};
+
+    fn params(entries: &[(&str, &str)]) -> BTreeMap<String, Any> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), Any::new(v).unwrap()))
+            .collect()
+    }
+
+    fn rules(test_mode: bool) -> Rules {
+        let config = RedactionConfig::builder()
+            .test_mode(test_mode)
+            .push_rules(
+                RedactionRuleConfig::builder()
+                    .name("token")
+                    .action(RedactionAction::Drop)
+                    .key_pattern("^token$".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .push_rules(
+                RedactionRuleConfig::builder()
+                    .name("email")
+                    .action(RedactionAction::Mask)
+                    .value_pattern(r"[\w.]+@[\w.]+".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .push_rules(
+                RedactionRuleConfig::builder()
+                    .name("password")
+                    .action(RedactionAction::Hash)
+                    .key_pattern("^path$".to_string())
+                    .value_pattern("password=[^&]*".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .build();
+        Rules::new(&config)
+    }
+
+    #[test]
+    fn redact() {
+        let mut params = params(&[
+            ("token", "secret"),
+            ("user", "contact bob@example.com or alice@example.com"),
+            ("path", "/login?password=hunter2&bob@example.com"),
+            ("other", "password=hunter2"),
+        ]);
+
+        let fired = rules(false).apply(&mut params);
+        assert_eq!(
+            fired,
+            BTreeSet::from([
+                "email".to_string(),
+                "password".to_string(),
+                "token".to_string()
+            ]),
+        );
+
+        let expected = self::params(&[
+            ("user", "contact [REDACTED] or [REDACTED]"),
+            (
+                "path",
+                &format!("/login?{}&[REDACTED]", hash("password=hunter2")),
+            ),
+            ("other", "password=hunter2"),
+        ]);
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn nested_values() {
+        let mut params = BTreeMap::from([(
+            "errorCause".to_string(),
+            Any::new(["failed for bob@example.com", "oops"]).unwrap(),
+        )]);
+
+        rules(false).apply(&mut params);
+        assert_eq!(
+            params["errorCause"],
+            Any::new(["failed for [REDACTED]", "oops"]).unwrap(),
+        );
+    }
+
+    fn service_log(message: &str) -> ServiceLogV1 {
+        ServiceLogV1::builder()
+            .type_("service.1")
+            .level(LogLevel::Info)
+            .time(Utc::now())
+            .message(message)
+            .build()
+    }
+
+    #[test]
+    fn unsafe_message() {
+        let rules = rules(false);
+
+        let log = service_log("contacting bob@example.com");
+        let (log, fired) = rules.redact_service_log(log);
+        assert!(fired.is_empty());
+        assert_eq!(log.message(), "contacting bob@example.com");
+
+        let log = service_log_v1::Builder::from(service_log("contacting bob@example.com"))
+            .safe(false)
+            .build();
+        let (log, fired) = rules.redact_service_log(log);
+        assert_eq!(fired, BTreeSet::from(["email".to_string()]));
+        assert_eq!(log.message(), "contacting [REDACTED]");
+    }
+
+    fn bearer_rules() -> Rules {
+        let config = RedactionConfig::builder()
+            .push_rules(
+                RedactionRuleConfig::builder()
+                    .name("bearer")
+                    .action(RedactionAction::Mask)
+                    .value_pattern(r"Bearer [\w.-]+".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .build();
+        Rules::new(&config)
+    }
+
+    #[test]
+    fn bearer_token_in_error() {
+        let log = service_log_v1::Builder::from(service_log("request failed"))
+            .insert_unsafe_params(
+                "errorCause",
+                ["header was `Authorization: Bearer abc.def`", "oops"],
+            )
+            .stacktrace("sending `Authorization: Bearer abc.def`\n   at foo::bar".to_string())
+            .build();
+
+        let (log, fired) = bearer_rules().redact_service_log(log);
+        assert_eq!(fired, BTreeSet::from(["bearer".to_string()]));
+        assert_eq!(log.message(), "request failed");
+        assert_eq!(
+            log.unsafe_params()["errorCause"],
+            Any::new(["header was `Authorization: [REDACTED]`", "oops"]).unwrap(),
+        );
+        assert_eq!(
+            log.stacktrace(),
+            Some("sending `Authorization: [REDACTED]`\n   at foo::bar"),
+        );
+    }
+
+    #[test]
+    fn bearer_token_in_tracing_event() {
+        let log = service_log_v1::Builder::from(service_log("using Bearer abc.def"))
+            .safe(false)
+            .build();
+
+        let (log, fired) = bearer_rules().redact_service_log(log);
+        assert_eq!(fired, BTreeSet::from(["bearer".to_string()]));
+        assert_eq!(log.message(), "using [REDACTED]");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn audit_params() {
+        let log = AuditLogV3::builder()
+            .type_("audit.3")
+            .deployment("deployment")
+            .host("host")
+            .product("product")
+            .product_version("1.0.0")
+            .producer_type(AuditProducer::Server)
+            .event_id(Uuid::new_v4())
+            .time(Utc::now())
+            .name("LOGIN")
+            .result(AuditResult::Success)
+            .insert_request_params(
+                "header",
+                SensitivityTaggedValue::builder()
+                    .payload("Bearer abc.def")
+                    .level(["secret".to_string()])
+                    .build(),
+            )
+            .insert_result_fields("header", "Bearer abc.def")
+            .build();
+
+        let (log, fired) = bearer_rules().redact_audit_log(log);
+        assert_eq!(fired, BTreeSet::from(["bearer".to_string()]));
+        let param = &log.request_params()["header"];
+        assert_eq!(*param.payload(), Any::new(MASK).unwrap());
+        assert_eq!(param.level(), ["secret".to_string()]);
+        assert_eq!(log.result_fields()["header"], Any::new(MASK).unwrap());
+    }
+
+    #[test]
+    fn test_mode() {
+        let original = params(&[("token", "secret"), ("user", "bob@example.com")]);
+        let mut params = original.clone();
+
+        let fired = rules(true).apply(&mut params);
+        assert_eq!(
+            fired,
+            BTreeSet::from(["email".to_string(), "token".to_string()]),
+        );
+        assert_eq!(params, original);
+    }
+}
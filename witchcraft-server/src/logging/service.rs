@@ -16,28 +16,47 @@ use crate::logging::api::{
     LogLevel, OrganizationId, ServiceLogV1, SessionId, TokenId, TraceId, UserId,
 };
 use crate::logging::logger::{self, Appender, Payload};
+use crate::logging::rate_limit::RateLimiter;
+use crate::logging::redaction;
+use crate::logging::subscriber;
 use crate::shutdown_hooks::ShutdownHooks;
 use arc_swap::ArcSwap;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use conjure_error::{Error, ErrorKind};
 use conjure_object::Utc;
 use conjure_serde::json;
+use hmac::{Hmac, Mac};
+use http::HeaderMap;
 use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
 use refreshable::{Refreshable, Subscription};
 use sequence_trie::SequenceTrie;
 use serde::Deserialize;
+use sha2::Sha256;
+use std::cell::Cell;
 use std::fmt::Write as _;
 use std::io::Write as _;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use std::{error, io, panic, thread};
+use tokio::{task, time};
 use witchcraft_log::bridge::{self, BridgedLogger};
-use witchcraft_log::{error, mdc};
+use witchcraft_log::{error, info, mdc};
 use witchcraft_log::{Level, LevelFilter, Log, Metadata, Record};
 use witchcraft_metrics::MetricRegistry;
 use witchcraft_server_config::install::InstallConfig;
-use witchcraft_server_config::runtime::LoggingConfig;
+use witchcraft_server_config::runtime::{LevelOverrideConfig, LoggingConfig};
+
+const DEBUG_HEADER: &str = "X-Witchcraft-Debug";
 
 static STATE: OnceCell<LoggerState> = OnceCell::new();
 
+thread_local! {
+    // The level override for the request being processed on this thread, computed once when the request starts.
+    static REQUEST_LEVEL: Cell<Option<LevelFilter>> = const { Cell::new(None) };
+}
+
 pub fn early_init() {
     witchcraft_log::set_max_level(LevelFilter::Info);
     bridge::set_max_level(LevelFilter::Info);
@@ -54,30 +73,165 @@ pub async fn init(
 ) -> Result<(), Error> {
     let appender = logger::appender(install, metrics, hooks).await?;
     let levels = Arc::new(ArcSwap::new(Arc::new(Levels::empty())));
+    let sources = Arc::new(Mutex::new(LevelSources {
+        config: runtime.get().clone(),
+        temporary: vec![],
+    }));
     let subscription = runtime.subscribe({
         let levels = levels.clone();
+        let sources = sources.clone();
         move |config| {
-            let new_levels = Levels::new(config);
-            let max_level = new_levels.max_level();
-            witchcraft_log::set_max_level(max_level);
-            bridge::set_max_level(max_level);
-            levels.store(Arc::new(new_levels));
+            let mut sources = sources.lock();
+            sources.config = config.clone();
+            update_levels(&levels, &sources);
         }
     });
 
+    let rate_limiter = Arc::new(RateLimiter::new(
+        runtime.map(|c| c.rate_limit().cloned()),
+        metrics,
+    ));
+    task::spawn(report_suppressed(rate_limiter.clone()));
+
     let logger = LoggerState {
         appender,
         levels,
+        sources,
+        rate_limiter,
         _subscription: subscription,
     };
     STATE.set(logger).ok().expect("logger already initialized");
+    subscriber::refresh_levels();
+
+    Ok(())
+}
+
+/// Periodically emits a summary of the logs suppressed by the rate limiter over the last interval.
+async fn report_suppressed(rate_limiter: Arc<RateLimiter>) {
+    loop {
+        time::sleep(rate_limiter.interval()).await;
+
+        for suppressed in rate_limiter.reset() {
+            ServiceLogger.write(
+                &Record::builder()
+                    .level(suppressed.level)
+                    .target(&suppressed.origin)
+                    .message("log messages suppressed by rate limiting")
+                    .safe_params(&[
+                        ("suppressedMessage", &suppressed.message),
+                        ("suppressedCount", &suppressed.count),
+                    ])
+                    .build(),
+            );
+        }
+    }
+}
+
+fn update_levels(levels: &ArcSwap<Levels>, sources: &LevelSources) {
+    let mut new_levels = Levels::new(&sources.config);
+    new_levels.apply_temporary(&sources.temporary, Instant::now());
+    let max_level = new_levels.max_level();
+    witchcraft_log::set_max_level(max_level);
+    bridge::set_max_level(max_level);
+    levels.store(Arc::new(new_levels));
+    subscriber::refresh_levels();
+}
+
+/// Temporarily overrides the level of a logger and its children, reverting to the configured levels after `ttl`.
+///
+/// If `target` is `None`, the level of every logger is overridden. Setting a new level for a target replaces any
+/// existing temporary level for it.
+pub fn set_temporary_level(
+    target: Option<String>,
+    level: LevelFilter,
+    ttl: Duration,
+) -> Result<(), Error> {
+    let state = STATE
+        .get()
+        .ok_or_else(|| Error::internal_safe("logging not initialized"))?;
+
+    let mut sources = state.sources.lock();
+    sources.temporary.retain(|t| t.target != target);
+    sources.temporary.push(TemporaryLevel {
+        target,
+        level,
+        expiration: Instant::now() + ttl,
+    });
+    update_levels(&state.levels, &sources);
+    drop(sources);
+
+    task::spawn(async move {
+        time::sleep(ttl).await;
+
+        let mut sources = state.sources.lock();
+        let now = Instant::now();
+        let len = sources.temporary.len();
+        sources.temporary.retain(|t| t.expiration > now);
+        if sources.temporary.len() != len {
+            update_levels(&state.levels, &sources);
+            info!("reverted expired temporary log levels");
+        }
+    });
 
     Ok(())
 }
 
+/// Returns the most verbose level of the configured level overrides matching a request, if any.
+pub fn level_override(request: &OverrideRequest<'_>) -> Option<LevelFilter> {
+    STATE
+        .get()?
+        .levels
+        .load()
+        .level_override(request, SystemTime::now())
+}
+
+/// Returns the level override of the request being processed on the current thread.
+pub fn request_level() -> Option<LevelFilter> {
+    REQUEST_LEVEL.with(|l| l.get())
+}
+
+/// Sets the level override of the request being processed on the current thread, returning the previous value.
+pub fn set_request_level(level: Option<LevelFilter>) -> Option<LevelFilter> {
+    REQUEST_LEVEL.with(|l| l.replace(level))
+}
+
+/// Returns true if any request level overrides are configured.
+pub fn has_level_overrides() -> bool {
+    STATE
+        .get()
+        .is_some_and(|state| !state.levels.load().overrides.is_empty())
+}
+
+/// Logs a record with a message only known at runtime.
+///
+/// The record's static message is only used to rate limit the log, and the logged message is marked unsafe.
+pub fn log_message(record: &Record<'_>, message: &str) {
+    if !ServiceLogger.enabled(record.metadata()) {
+        return;
+    }
+
+    if let Some(state) = STATE.get() {
+        if !state.rate_limiter.check(record) {
+            return;
+        }
+    }
+
+    ServiceLogger.write_message(record, message, false);
+}
+
+/// The properties of a request used to match it against level overrides.
+pub struct OverrideRequest<'a> {
+    pub trace_id: &'a str,
+    pub user_id: Option<&'a str>,
+    pub endpoint: Option<&'a str>,
+    pub headers: &'a HeaderMap,
+}
+
 struct LoggerState {
     appender: Appender<ServiceLogV1>,
     levels: Arc<ArcSwap<Levels>>,
+    sources: Arc<Mutex<LevelSources>>,
+    rate_limiter: Arc<RateLimiter>,
     _subscription: Subscription<LoggingConfig, Error>,
 }
 
@@ -96,6 +250,26 @@ impl Log for ServiceLogger {
             return;
         }
 
+        if let Some(state) = STATE.get() {
+            if !state.rate_limiter.check(record) {
+                return;
+            }
+        }
+
+        self.write(record);
+    }
+
+    fn flush(&self) {
+        // We flush via a different mode.
+    }
+}
+
+impl ServiceLogger {
+    fn write(&self, record: &Record<'_>) {
+        self.write_message(record, record.message(), true);
+    }
+
+    fn write_message(&self, record: &Record<'_>, message: &str, safe: bool) {
         let level = match record.level() {
             Level::Fatal => LogLevel::Fatal,
             Level::Error => LogLevel::Error,
@@ -109,8 +283,8 @@ impl Log for ServiceLogger {
             .type_("service.1")
             .level(level)
             .time(Utc::now())
-            .message(record.message())
-            .safe(true)
+            .message(message)
+            .safe(safe)
             .origin(record.target().to_string())
             .thread(thread::current().name().map(ToString::to_string));
 
@@ -195,7 +369,7 @@ impl Log for ServiceLogger {
         for (key, value) in record.unsafe_params() {
             message = message.insert_unsafe_params(*key, value);
         }
-        let message = message.build();
+        let message = redaction::redact_service_log(message.build());
 
         match STATE.get() {
             Some(state) => {
@@ -211,20 +385,30 @@ impl Log for ServiceLogger {
             }
         }
     }
+}
 
-    fn flush(&self) {
-        // We flush via a different mode.
-    }
+/// The inputs to the service logger's levels.
+struct LevelSources {
+    config: LoggingConfig,
+    temporary: Vec<TemporaryLevel>,
+}
+
+struct TemporaryLevel {
+    target: Option<String>,
+    level: LevelFilter,
+    expiration: Instant,
 }
 
 struct Levels {
     trie: SequenceTrie<String, LevelFilter>,
+    overrides: Vec<LevelOverrideConfig>,
 }
 
 impl Levels {
     fn empty() -> Self {
         Levels {
             trie: SequenceTrie::new(),
+            overrides: vec![],
         }
     }
 
@@ -235,20 +419,120 @@ impl Levels {
             trie.insert(logger.split("::"), *level);
         }
 
-        Levels { trie }
+        Levels {
+            trie,
+            overrides: config.level_overrides().to_vec(),
+        }
+    }
+
+    /// Applies the unexpired temporary levels on top of the configured levels.
+    ///
+    /// A global temporary level replaces the level of every logger, and targeted levels are applied after it.
+    fn apply_temporary(&mut self, temporary: &[TemporaryLevel], now: Instant) {
+        let temporary = temporary.iter().filter(|t| t.expiration > now);
+        for t in temporary.clone().filter(|t| t.target.is_none()) {
+            self.trie = SequenceTrie::new();
+            self.trie.insert_owned([], t.level);
+        }
+        for t in temporary {
+            if let Some(target) = &t.target {
+                self.trie.insert(target.split("::"), t.level);
+            }
+        }
     }
 
     fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        metadata.level()
+        if metadata.level()
             <= *self
                 .trie
                 .get_ancestor(metadata.target().split("::"))
                 .unwrap()
+        {
+            return true;
+        }
+
+        if self.overrides.is_empty() {
+            return false;
+        }
+
+        request_level().is_some_and(|level| metadata.level() <= level)
     }
 
     fn max_level(&self) -> LevelFilter {
-        self.trie.values().cloned().max().unwrap()
+        self.trie
+            .values()
+            .cloned()
+            .chain(self.overrides.iter().map(|o| o.level()))
+            .max()
+            .unwrap()
+    }
+
+    fn level_override(
+        &self,
+        request: &OverrideRequest<'_>,
+        now: SystemTime,
+    ) -> Option<LevelFilter> {
+        self.overrides
+            .iter()
+            .filter(|o| override_matches(o, request, now))
+            .map(|o| o.level())
+            .max()
+    }
+}
+
+fn override_matches(
+    config: &LevelOverrideConfig,
+    request: &OverrideRequest<'_>,
+    now: SystemTime,
+) -> bool {
+    if config.trace_id().is_some_and(|id| id != request.trace_id) {
+        return false;
+    }
+
+    if config
+        .user_id()
+        .is_some_and(|id| request.user_id != Some(id))
+    {
+        return false;
+    }
+
+    if config
+        .endpoint()
+        .is_some_and(|endpoint| request.endpoint != Some(endpoint))
+    {
+        return false;
+    }
+
+    if let Some(secret) = config.debug_header_secret() {
+        if !valid_debug_header(request.headers, secret, now) {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn valid_debug_header(headers: &HeaderMap, secret: &str, now: SystemTime) -> bool {
+    let Some(value) = headers.get(DEBUG_HEADER).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let Some((expiration, signature)) = value.split_once('.') else {
+        return false;
+    };
+    let Ok(expiration_secs) = expiration.parse::<u64>() else {
+        return false;
+    };
+    if SystemTime::UNIX_EPOCH + Duration::from_secs(expiration_secs) < now {
+        return false;
     }
+    let Ok(signature) = URL_SAFE_NO_PAD.decode(signature) else {
+        return false;
+    };
+
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(expiration.as_bytes());
+    mac.verify_slice(&signature).is_ok()
 }
 
 fn log_panics() {
@@ -318,4 +602,184 @@ mod test {
 
         assert_eq!(loggers.max_level(), LevelFilter::Debug);
     }
+
+    #[test]
+    fn temporary_levels() {
+        let config = LoggingConfig::builder()
+            .level(LevelFilter::Info)
+            .insert_loggers("foo", LevelFilter::Warn)
+            .build()
+            .unwrap();
+
+        let now = Instant::now();
+        let temporary = [
+            TemporaryLevel {
+                target: Some("foo::bar".to_string()),
+                level: LevelFilter::Trace,
+                expiration: now + Duration::from_secs(60),
+            },
+            TemporaryLevel {
+                target: Some("baz".to_string()),
+                level: LevelFilter::Debug,
+                expiration: now,
+            },
+        ];
+
+        let mut loggers = Levels::new(&config);
+        loggers.apply_temporary(&temporary, now);
+
+        assert!(loggers.enabled(
+            &Metadata::builder()
+                .level(Level::Trace)
+                .target("foo::bar")
+                .build()
+        ));
+        assert!(!loggers.enabled(&Metadata::builder().level(Level::Info).target("foo").build()));
+        assert!(!loggers.enabled(
+            &Metadata::builder()
+                .level(Level::Debug)
+                .target("baz")
+                .build()
+        ));
+        assert_eq!(loggers.max_level(), LevelFilter::Trace);
+
+        let global = [TemporaryLevel {
+            target: None,
+            level: LevelFilter::Debug,
+            expiration: now + Duration::from_secs(60),
+        }];
+
+        let mut loggers = Levels::new(&config);
+        loggers.apply_temporary(&global, now);
+
+        assert!(loggers.enabled(
+            &Metadata::builder()
+                .level(Level::Debug)
+                .target("foo")
+                .build()
+        ));
+        assert_eq!(loggers.max_level(), LevelFilter::Debug);
+    }
+
+    fn request<'a>(headers: &'a HeaderMap, endpoint: Option<&'a str>) -> OverrideRequest<'a> {
+        OverrideRequest {
+            trace_id: "0011223344556677",
+            user_id: Some("user"),
+            endpoint,
+            headers,
+        }
+    }
+
+    fn sign(secret: &str, expiration: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(expiration.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+        format!("{expiration}.{signature}")
+    }
+
+    #[test]
+    fn level_overrides() {
+        let config = LoggingConfig::builder()
+            .push_level_overrides(
+                LevelOverrideConfig::builder()
+                    .level(LevelFilter::Debug)
+                    .user_id("user".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .push_level_overrides(
+                LevelOverrideConfig::builder()
+                    .level(LevelFilter::Trace)
+                    .trace_id("0011223344556677".to_string())
+                    .endpoint("getFoo".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let levels = Levels::new(&config);
+        assert_eq!(levels.max_level(), LevelFilter::Trace);
+
+        let headers = HeaderMap::new();
+        let now = SystemTime::now();
+        assert_eq!(
+            levels.level_override(&request(&headers, None), now),
+            Some(LevelFilter::Debug),
+        );
+        assert_eq!(
+            levels.level_override(&request(&headers, Some("getFoo")), now),
+            Some(LevelFilter::Trace),
+        );
+
+        let mut other_user = request(&headers, Some("getFoo"));
+        other_user.user_id = None;
+        other_user.trace_id = "7766554433221100";
+        assert_eq!(levels.level_override(&other_user, now), None);
+    }
+
+    #[test]
+    fn request_level_enabled() {
+        let config = LoggingConfig::builder()
+            .push_level_overrides(
+                LevelOverrideConfig::builder()
+                    .level(LevelFilter::Debug)
+                    .user_id("user".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        let levels = Levels::new(&config);
+        let debug = Metadata::builder()
+            .level(Level::Debug)
+            .target("foo")
+            .build();
+
+        assert!(!levels.enabled(&debug));
+
+        let previous = set_request_level(Some(LevelFilter::Debug));
+        assert!(levels.enabled(&debug));
+        assert!(!levels.enabled(
+            &Metadata::builder()
+                .level(Level::Trace)
+                .target("foo")
+                .build()
+        ));
+        set_request_level(previous);
+
+        assert!(!levels.enabled(&debug));
+    }
+
+    #[test]
+    fn debug_header_override() {
+        let config = LoggingConfig::builder()
+            .push_level_overrides(
+                LevelOverrideConfig::builder()
+                    .level(LevelFilter::Debug)
+                    .debug_header_secret("secret".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        let levels = Levels::new(&config);
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(levels.level_override(&request(&headers, None), now), None);
+
+        headers.insert(DEBUG_HEADER, sign("secret", "2000").parse().unwrap());
+        assert_eq!(
+            levels.level_override(&request(&headers, None), now),
+            Some(LevelFilter::Debug),
+        );
+
+        headers.insert(DEBUG_HEADER, sign("secret", "500").parse().unwrap());
+        assert_eq!(levels.level_override(&request(&headers, None), now), None);
+
+        headers.insert(DEBUG_HEADER, sign("other", "2000").parse().unwrap());
+        assert_eq!(levels.level_override(&request(&headers, None), now), None);
+    }
 }
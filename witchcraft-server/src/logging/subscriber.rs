@@ -0,0 +1,540 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! A `tracing` subscriber which forwards events to the service log and spans to zipkin.
+use crate::logging::{service, DeferredSpan};
+use parking_lot::Mutex;
+use serde::{Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record as SpanRecord};
+use tracing::subscriber::Interest;
+use tracing::{Event, Metadata, Subscriber};
+use zipkin::{CurrentGuard, Detached, OpenSpan, TraceContext};
+
+const MESSAGE_FIELD: &str = "message";
+
+thread_local! {
+    static STACK: RefCell<Vec<Entered>> = const { RefCell::new(vec![]) };
+}
+
+/// Recomputes the enabled state of `tracing` callsites after the service log levels change.
+pub fn refresh_levels() {
+    tracing::callsite::rebuild_interest_cache();
+}
+
+/// A `tracing` subscriber which forwards events to the service logger and reports spans to zipkin.
+///
+/// Events are filtered by the service logger's levels. The event's message is used as the log message, and the other
+/// span and event fields are included as unsafe params. A span is reported to zipkin as a child of its parent's trace if
+/// one exists, so spans created while handling a request show up in that request's trace. Span fields are not added as
+/// zipkin tags since they may contain unsafe data.
+///
+/// Spans are created and closed constantly, so their state is split into independently locked shards to avoid
+/// contention between threads.
+pub struct WitchcraftSubscriber<S> {
+    sink: S,
+    next_id: AtomicU64,
+    spans: Box<[Mutex<HashMap<u64, SpanState>>]>,
+}
+
+/// The destination of the events recorded by a [`WitchcraftSubscriber`].
+pub trait EventSink: 'static + Sync + Send {
+    fn enabled(&self, metadata: &witchcraft_log::Metadata<'_>) -> bool;
+
+    fn max_level(&self) -> witchcraft_log::LevelFilter;
+
+    /// Returns true if the enabled state of a callsite can vary between requests.
+    fn has_level_overrides(&self) -> bool;
+
+    fn log(&self, record: &witchcraft_log::Record<'_>, message: &str);
+}
+
+/// An [`EventSink`] which writes to the service log.
+pub struct ServiceSink;
+
+impl EventSink for ServiceSink {
+    fn enabled(&self, metadata: &witchcraft_log::Metadata<'_>) -> bool {
+        witchcraft_log::logger().enabled(metadata)
+    }
+
+    fn max_level(&self) -> witchcraft_log::LevelFilter {
+        witchcraft_log::max_level()
+    }
+
+    fn has_level_overrides(&self) -> bool {
+        service::has_level_overrides()
+    }
+
+    fn log(&self, record: &witchcraft_log::Record<'_>, message: &str) {
+        service::log_message(record, message);
+    }
+}
+
+struct SpanState {
+    refs: usize,
+    fields: Vec<(&'static str, FieldValue)>,
+    span: Option<OpenSpan<Detached>>,
+    _deferred: Option<DeferredSpan>,
+}
+
+struct Entered {
+    id: u64,
+    exited: bool,
+    _guard: Option<CurrentGuard>,
+}
+
+impl<S> WitchcraftSubscriber<S> {
+    pub fn new(sink: S) -> Self {
+        let shards = (num_cpus::get() * 4).next_power_of_two();
+
+        WitchcraftSubscriber {
+            sink,
+            next_id: AtomicU64::new(1),
+            spans: (0..shards).map(|_| Mutex::new(HashMap::new())).collect(),
+        }
+    }
+
+    // IDs are allocated sequentially, so they spread evenly across the shards without hashing.
+    fn shard(&self, id: u64) -> &Mutex<HashMap<u64, SpanState>> {
+        &self.spans[id as usize % self.spans.len()]
+    }
+
+    fn current_id() -> Option<u64> {
+        STACK.with(|s| s.borrow().iter().rev().find(|e| !e.exited).map(|e| e.id))
+    }
+
+    fn parent_id(&self, explicit: Option<&Id>, contextual: bool) -> Option<u64> {
+        match explicit {
+            Some(id) => Some(id.into_u64()),
+            None if contextual => Self::current_id(),
+            None => None,
+        }
+    }
+
+    fn fields(&self, id: Option<u64>) -> Vec<(&'static str, FieldValue)> {
+        id.and_then(|id| self.shard(id).lock().get(&id).map(|s| s.fields.clone()))
+            .unwrap_or_default()
+    }
+
+    fn context(&self, id: u64) -> Option<TraceContext> {
+        self.shard(id)
+            .lock()
+            .get(&id)
+            .and_then(|s| s.span.as_ref())
+            .map(|s| s.context())
+    }
+}
+
+impl<S> Subscriber for WitchcraftSubscriber<S>
+where
+    S: EventSink,
+{
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        // request level overrides can enable any callsite, so we need to check every time. Otherwise the interest cache
+        // is rebuilt when the levels are reconfigured.
+        if self.sink.has_level_overrides() {
+            Interest::sometimes()
+        } else if self.enabled(metadata) {
+            Interest::always()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.sink.enabled(
+            &witchcraft_log::Metadata::builder()
+                .level(cvt_level(*metadata.level()))
+                .target(metadata.target())
+                .build(),
+        )
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        let level = match self.sink.max_level() {
+            witchcraft_log::LevelFilter::Off | witchcraft_log::LevelFilter::Fatal => {
+                tracing::level_filters::LevelFilter::OFF
+            }
+            witchcraft_log::LevelFilter::Error => tracing::level_filters::LevelFilter::ERROR,
+            witchcraft_log::LevelFilter::Warn => tracing::level_filters::LevelFilter::WARN,
+            witchcraft_log::LevelFilter::Info => tracing::level_filters::LevelFilter::INFO,
+            witchcraft_log::LevelFilter::Debug => tracing::level_filters::LevelFilter::DEBUG,
+            witchcraft_log::LevelFilter::Trace => tracing::level_filters::LevelFilter::TRACE,
+        };
+        Some(level)
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let parent = self.parent_id(attrs.parent(), attrs.is_contextual());
+
+        let mut fields = self.fields(parent);
+        attrs.record(&mut FieldVisitor {
+            fields: &mut fields,
+        });
+
+        let context = match attrs.parent() {
+            Some(parent) => self.context(parent.into_u64()),
+            None if attrs.is_contextual() => zipkin::current(),
+            None => None,
+        };
+        let span = context.map(|context| {
+            zipkin::new_child(context)
+                .with_name(attrs.metadata().name())
+                .detach()
+        });
+        let deferred = span
+            .as_ref()
+            .and_then(|span| DeferredSpan::new(span.context(), attrs.metadata().name()));
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.shard(id).lock().insert(
+            id,
+            SpanState {
+                refs: 1,
+                fields,
+                span,
+                _deferred: deferred,
+            },
+        );
+
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &SpanRecord<'_>) {
+        let id = span.into_u64();
+        if let Some(state) = self.shard(id).lock().get_mut(&id) {
+            values.record(&mut FieldVisitor {
+                fields: &mut state.fields,
+            });
+        }
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let parent = self.parent_id(event.parent(), event.is_contextual());
+
+        let mut event_fields = vec![];
+        event.record(&mut FieldVisitor {
+            fields: &mut event_fields,
+        });
+        let message = event_fields
+            .iter()
+            .position(|(key, _)| *key == MESSAGE_FIELD)
+            .map(|i| event_fields.remove(i).1.to_string())
+            .unwrap_or_default();
+
+        let mut fields = self.fields(parent);
+        let mut visitor = FieldVisitor {
+            fields: &mut fields,
+        };
+        for (key, value) in event_fields {
+            visitor.insert(key, value);
+        }
+        let params = fields
+            .iter()
+            .map(|(key, value)| (*key, value as _))
+            .collect::<Vec<_>>();
+
+        // The event's message isn't static, so the callsite's name stands in for it when rate limiting.
+        self.sink.log(
+            &witchcraft_log::Record::builder()
+                .level(cvt_level(*metadata.level()))
+                .target(metadata.target())
+                .message(metadata.name())
+                .file(metadata.file())
+                .line(metadata.line())
+                .unsafe_params(&params)
+                .build(),
+            &message,
+        );
+    }
+
+    fn enter(&self, span: &Id) {
+        let id = span.into_u64();
+        let guard = self.context(id).map(zipkin::set_current);
+        STACK.with(|s| {
+            s.borrow_mut().push(Entered {
+                id,
+                exited: false,
+                _guard: guard,
+            })
+        });
+    }
+
+    fn exit(&self, span: &Id) {
+        let id = span.into_u64();
+        // Each zipkin guard restores the context that was current when it was created, so they have to be released in
+        // the reverse order they were created. A span exited out of order stays on the stack until every span entered
+        // after it has exited as well.
+        let exited = STACK.with(|s| {
+            let mut stack = s.borrow_mut();
+            if let Some(entered) = stack.iter_mut().rev().find(|e| e.id == id && !e.exited) {
+                entered.exited = true;
+            }
+
+            let mut exited = vec![];
+            while stack.last().is_some_and(|e| e.exited) {
+                exited.extend(stack.pop());
+            }
+            exited
+        });
+        drop(exited);
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        let id = span.into_u64();
+        if let Some(state) = self.shard(id).lock().get_mut(&id) {
+            state.refs += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let id = span.into_u64();
+        let mut spans = self.shard(id).lock();
+        let Some(state) = spans.get_mut(&id) else {
+            return false;
+        };
+
+        state.refs -= 1;
+        if state.refs > 0 {
+            return false;
+        }
+
+        let state = spans.remove(&id);
+        // finishing the zipkin span reports it, so make sure we aren't holding the lock
+        drop(spans);
+        drop(state);
+
+        true
+    }
+}
+
+fn cvt_level(level: tracing::Level) -> witchcraft_log::Level {
+    match level {
+        tracing::Level::ERROR => witchcraft_log::Level::Error,
+        tracing::Level::WARN => witchcraft_log::Level::Warn,
+        tracing::Level::INFO => witchcraft_log::Level::Info,
+        tracing::Level::DEBUG => witchcraft_log::Level::Debug,
+        tracing::Level::TRACE => witchcraft_log::Level::Trace,
+    }
+}
+
+struct FieldVisitor<'a> {
+    fields: &'a mut Vec<(&'static str, FieldValue)>,
+}
+
+impl FieldVisitor<'_> {
+    fn insert(&mut self, key: &'static str, value: FieldValue) {
+        match self.fields.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.fields.push((key, value)),
+        }
+    }
+}
+
+impl Visit for FieldVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field.name(), FieldValue::F64(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field.name(), FieldValue::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field.name(), FieldValue::U64(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field.name(), FieldValue::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field.name(), FieldValue::String(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field.name(), FieldValue::String(format!("{:?}", value)));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum FieldValue {
+    F64(f64),
+    I64(i64),
+    U64(u64),
+    Bool(bool),
+    String(String),
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::F64(v) => fmt::Display::fmt(v, f),
+            FieldValue::I64(v) => fmt::Display::fmt(v, f),
+            FieldValue::U64(v) => fmt::Display::fmt(v, f),
+            FieldValue::Bool(v) => fmt::Display::fmt(v, f),
+            FieldValue::String(v) => fmt::Display::fmt(v, f),
+        }
+    }
+}
+
+impl Serialize for FieldValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            FieldValue::F64(v) => serializer.serialize_f64(*v),
+            FieldValue::I64(v) => serializer.serialize_i64(*v),
+            FieldValue::U64(v) => serializer.serialize_u64(*v),
+            FieldValue::Bool(v) => serializer.serialize_bool(*v),
+            FieldValue::String(v) => serializer.serialize_str(v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+    use std::sync::Arc;
+    use tracing::{debug, info, info_span};
+
+    struct Logged {
+        level: witchcraft_log::Level,
+        message: String,
+        params: HashMap<String, Value>,
+    }
+
+    struct TestSink {
+        level: witchcraft_log::LevelFilter,
+        logs: Arc<Mutex<Vec<Logged>>>,
+    }
+
+    impl EventSink for TestSink {
+        fn enabled(&self, metadata: &witchcraft_log::Metadata<'_>) -> bool {
+            metadata.level() <= self.level
+        }
+
+        fn max_level(&self) -> witchcraft_log::LevelFilter {
+            self.level
+        }
+
+        fn has_level_overrides(&self) -> bool {
+            false
+        }
+
+        fn log(&self, record: &witchcraft_log::Record<'_>, message: &str) {
+            assert!(record.safe_params().is_empty());
+            self.logs.lock().push(Logged {
+                level: record.level(),
+                message: message.to_string(),
+                params: record
+                    .unsafe_params()
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), serde_json::to_value(v).unwrap()))
+                    .collect(),
+            });
+        }
+    }
+
+    fn subscriber(
+        level: witchcraft_log::LevelFilter,
+    ) -> (WitchcraftSubscriber<TestSink>, Arc<Mutex<Vec<Logged>>>) {
+        let logs = Arc::new(Mutex::new(vec![]));
+        let subscriber = WitchcraftSubscriber::new(TestSink {
+            level,
+            logs: logs.clone(),
+        });
+        (subscriber, logs)
+    }
+
+    #[test]
+    fn event_fields() {
+        let (subscriber, logs) = subscriber(witchcraft_log::LevelFilter::Info);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = info_span!("outer", user = "alice", count = 1);
+            let _guard = span.enter();
+            info!(count = 2, enabled = true, "hello {}", "world");
+        });
+
+        let logs = logs.lock();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].level, witchcraft_log::Level::Info);
+        assert_eq!(logs[0].message, "hello world");
+        assert_eq!(
+            logs[0].params,
+            HashMap::from([
+                ("user".to_string(), Value::from("alice")),
+                ("count".to_string(), Value::from(2)),
+                ("enabled".to_string(), Value::from(true)),
+            ]),
+        );
+    }
+
+    #[test]
+    fn level_filtering() {
+        let (subscriber, logs) = subscriber(witchcraft_log::LevelFilter::Info);
+
+        tracing::subscriber::with_default(subscriber, || {
+            debug!("filtered");
+            info!("logged");
+        });
+
+        let logs = logs.lock();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].message, "logged");
+    }
+
+    #[test]
+    fn nested_spans() {
+        let (subscriber, _) = subscriber(witchcraft_log::LevelFilter::Info);
+
+        let trace = zipkin::new_trace().detach();
+        let _trace_guard = zipkin::set_current(trace.context());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let outer = info_span!("outer");
+            let outer_guard = outer.enter();
+            let outer_context = zipkin::current().unwrap();
+            assert_eq!(outer_context.trace_id(), trace.context().trace_id());
+            assert_ne!(outer_context.span_id(), trace.context().span_id());
+
+            let inner = info_span!("inner");
+            let inner_guard = inner.enter();
+            let inner_context = zipkin::current().unwrap();
+            assert_eq!(inner_context.trace_id(), trace.context().trace_id());
+            assert_eq!(inner_context.parent_id(), Some(outer_context.span_id()));
+
+            // exiting out of order leaves the innermost span current until it exits as well
+            drop(outer_guard);
+            assert_eq!(zipkin::current(), Some(inner_context));
+            drop(inner_guard);
+            assert_eq!(zipkin::current(), Some(trace.context()));
+
+            let _outer_guard = outer.enter();
+            assert_eq!(zipkin::current(), Some(outer_context));
+        });
+
+        assert_eq!(zipkin::current(), Some(trace.context()));
+    }
+}
@@ -13,29 +13,120 @@
 // limitations under the License.
 use crate::logging::api::{Annotation, Endpoint, Span, TraceLogV1};
 use crate::logging::logger::{self, Appender, Payload};
+use crate::logging::trace::otlp::OtlpExporter;
 use crate::shutdown_hooks::ShutdownHooks;
 use conjure_error::Error;
 use conjure_object::{SafeLong, Utc};
+use once_cell::sync::OnceCell;
 use refreshable::Refreshable;
-use std::time::{Duration, SystemTime};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use witchcraft_metrics::MetricRegistry;
 use witchcraft_server_config::install::InstallConfig;
 use witchcraft_server_config::runtime::LoggingConfig;
-use zipkin::{Kind, Report, Sample, TraceId};
+use zipkin::{span, Kind, Report, TraceContext};
 
 mod ifaddrs;
+mod otlp;
+mod sampling;
+
+pub use sampling::{sample_request, RequestSampling};
+
+static DEFERRED_REPORTER: OnceCell<DeferredReporter> = OnceCell::new();
+
+struct DeferredReporter {
+    reporter: Arc<WitchcraftReporter>,
+    local_endpoint: zipkin::Endpoint,
+}
+
+/// A span of a trace whose sampling decision was deferred.
+///
+/// The zipkin spans of a deferred trace are unsampled so the deferral isn't propagated to other services, so the server
+/// records its own spans of those traces separately. They are buffered until the request completes, and then reported
+/// if it qualifies for tail sampling.
+pub struct DeferredSpan {
+    span: span::Builder,
+    start: Instant,
+}
+
+impl DeferredSpan {
+    /// Starts the server span of a request whose sampling decision was deferred, which completes the trace when dropped.
+    pub fn server(context: TraceContext) -> Self {
+        sampling::defer_trace(context.trace_id());
+        let mut span = DeferredSpan::new_unchecked(context);
+        span.span.kind(Kind::Server);
+        span
+    }
+
+    /// Starts a span mirroring the zipkin span with the given context, if it belongs to a deferred trace.
+    pub fn new(context: TraceContext, name: &str) -> Option<Self> {
+        if context.sampled() != Some(false) || !sampling::is_deferred(context.trace_id()) {
+            return None;
+        }
+
+        let mut span = DeferredSpan::new_unchecked(context);
+        span.name(name);
+        Some(span)
+    }
+
+    fn new_unchecked(context: TraceContext) -> Self {
+        let mut span = zipkin::Span::builder();
+        span.trace_id(context.trace_id())
+            .id(context.span_id())
+            .timestamp(SystemTime::now());
+        if let Some(parent_id) = context.parent_id() {
+            span.parent_id(parent_id);
+        }
+
+        DeferredSpan {
+            span,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn name(&mut self, name: &str) {
+        self.span.name(name);
+    }
+
+    pub fn tag(&mut self, key: &str, value: &str) {
+        self.span.tag(key, value);
+    }
+}
+
+impl Drop for DeferredSpan {
+    fn drop(&mut self) {
+        let Some(deferred) = DEFERRED_REPORTER.get() else {
+            return;
+        };
+
+        self.span
+            .duration(self.start.elapsed())
+            .local_endpoint(deferred.local_endpoint.clone());
+        sampling::process_deferred(self.span.build(), &mut |span| {
+            deferred.reporter.report(span)
+        });
+    }
+}
 
 pub async fn init(
-    metrics: &MetricRegistry,
+    metrics: &Arc<MetricRegistry>,
     install: &InstallConfig,
     runtime: &Refreshable<LoggingConfig, Error>,
     hooks: &mut ShutdownHooks,
 ) -> Result<(), Error> {
     let appender = logger::appender(install, metrics, hooks).await?;
-    let sampler = WitchcraftSampler {
-        trace_rate: runtime.map(|c| c.trace_rate()),
+    let sampler = sampling::init(metrics, runtime);
+    let otlp = match install.logging().trace_exporter() {
+        Some(config) => Some(OtlpExporter::new(
+            config,
+            install.product_name(),
+            install.product_version(),
+            metrics,
+            hooks,
+        )?),
+        None => None,
     };
-    let reporter = WitchcraftReporter { appender };
+    let reporter = Arc::new(WitchcraftReporter { appender, otlp });
 
     let mut local_endpoint = zipkin::Endpoint::builder();
     local_endpoint
@@ -46,18 +137,31 @@ pub async fn init(
         local_endpoint.ip(ip);
     }
 
-    zipkin::set_tracer(sampler, reporter, local_endpoint.build())
+    let local_endpoint = local_endpoint.build();
+
+    DEFERRED_REPORTER
+        .set(DeferredReporter {
+            reporter: reporter.clone(),
+            local_endpoint: local_endpoint.clone(),
+        })
+        .ok()
         .expect("tracer already initialized");
+    zipkin::set_tracer(sampler, reporter, local_endpoint).expect("tracer already initialized");
 
     Ok(())
 }
 
 struct WitchcraftReporter {
     appender: Appender<TraceLogV1>,
+    otlp: Option<OtlpExporter>,
 }
 
 impl Report for WitchcraftReporter {
     fn report(&self, raw_span: zipkin::Span) {
+        if let Some(otlp) = &self.otlp {
+            otlp.export(&raw_span);
+        }
+
         let raw_timestamp = raw_span.timestamp().expect("BUG: span missing timestamp");
         let timestamp = time2micros(raw_timestamp);
 
@@ -146,13 +250,3 @@ fn time2micros(t: SystemTime) -> SafeLong {
 fn dur2micros(d: Duration) -> SafeLong {
     SafeLong::try_from(d.as_micros()).ok().unwrap_or_default()
 }
-
-struct WitchcraftSampler {
-    trace_rate: Refreshable<f32, Error>,
-}
-
-impl Sample for WitchcraftSampler {
-    fn sample(&self, _: TraceId) -> bool {
-        rand::random::<f32>() < *self.trace_rate.get()
-    }
-}
@@ -0,0 +1,319 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! An exporter sending spans to an OpenTelemetry collector using the JSON encoding of OTLP/HTTP.
+use crate::otlp::{self, attribute, unix_nanos};
+use crate::shutdown_hooks::ShutdownHooks;
+use conjure_error::Error;
+use conjure_runtime::Client;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::Notify;
+use tokio::{task, time};
+use witchcraft_log::warn;
+use witchcraft_metrics::{Meter, MetricId, MetricRegistry};
+use witchcraft_server_config::install::TraceExporterConfig;
+use zipkin::Kind;
+
+const PATH: &str = "/v1/traces";
+
+// https://opentelemetry.io/docs/specs/otlp/#otlphttp
+const SPAN_KIND_INTERNAL: i32 = 1;
+const SPAN_KIND_SERVER: i32 = 2;
+const SPAN_KIND_CLIENT: i32 = 3;
+const SPAN_KIND_PRODUCER: i32 = 4;
+const SPAN_KIND_CONSUMER: i32 = 5;
+
+struct Shared {
+    queue: Mutex<VecDeque<Value>>,
+    notify: Notify,
+    closed: AtomicBool,
+}
+
+/// A handle used to queue spans for export.
+pub struct OtlpExporter {
+    shared: Arc<Shared>,
+    batch_size: usize,
+    queue_size: usize,
+    dropped: Arc<Meter>,
+}
+
+impl OtlpExporter {
+    pub fn new(
+        config: &TraceExporterConfig,
+        product_name: &str,
+        product_version: &str,
+        metrics: &Arc<MetricRegistry>,
+        hooks: &mut ShutdownHooks,
+    ) -> Result<Self, Error> {
+        let client = otlp::client(config.collector(), product_name, product_version, metrics)?;
+        let resource = otlp::resource(product_name, product_version);
+
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(VecDeque::new()),
+            notify: Notify::new(),
+            closed: AtomicBool::new(false),
+        });
+
+        let handle = task::spawn(export_loop(
+            shared.clone(),
+            client,
+            resource,
+            config.batch_size(),
+            config.flush_interval(),
+            metrics.meter("tracing.export.failures"),
+        ));
+
+        hooks.push({
+            let shared = shared.clone();
+            async move {
+                shared.closed.store(true, Ordering::Relaxed);
+                shared.notify.notify_one();
+                let _ = handle.await;
+            }
+        });
+
+        Ok(OtlpExporter {
+            shared,
+            batch_size: config.batch_size(),
+            queue_size: config.queue_size(),
+            dropped: metrics.meter(MetricId::new("tracing.export.dropped")),
+        })
+    }
+
+    /// Queues a finished span to be sent to the collector.
+    pub fn export(&self, span: &zipkin::Span) {
+        let mut queue = self.shared.queue.lock();
+        if queue.len() >= self.queue_size {
+            drop(queue);
+            self.dropped.mark(1);
+            return;
+        }
+
+        queue.push_back(span_json(span));
+        let len = queue.len();
+        drop(queue);
+
+        if len == self.batch_size {
+            self.shared.notify.notify_one();
+        }
+    }
+}
+
+async fn export_loop(
+    shared: Arc<Shared>,
+    client: Client,
+    resource: Value,
+    batch_size: usize,
+    flush_interval: Duration,
+    failures: Arc<Meter>,
+) {
+    loop {
+        let _ = time::timeout(flush_interval, shared.notify.notified()).await;
+        let closed = shared.closed.load(Ordering::Relaxed);
+
+        loop {
+            let batch = {
+                let mut queue = shared.queue.lock();
+                let len = usize::min(queue.len(), batch_size);
+                queue.drain(..len).collect::<Vec<_>>()
+            };
+            if batch.is_empty() {
+                break;
+            }
+
+            let len = batch.len();
+            if let Err(e) = send(&client, &resource, batch).await {
+                failures.mark(1);
+                warn!(
+                    "error exporting spans to the OTLP collector",
+                    safe: {
+                        spans: len,
+                    },
+                    error: e,
+                );
+            }
+        }
+
+        if closed {
+            break;
+        }
+    }
+}
+
+async fn send(client: &Client, resource: &Value, spans: Vec<Value>) -> Result<(), Error> {
+    let body = json!({
+        "resourceSpans": [{
+            "resource": resource,
+            "scopeSpans": [{
+                "scope": otlp::scope(),
+                "spans": spans,
+            }],
+        }],
+    });
+
+    otlp::send(client, "OtlpTraceService", PATH, &body).await
+}
+
+fn span_json(span: &zipkin::Span) -> Value {
+    let start = span.timestamp().unwrap_or(SystemTime::UNIX_EPOCH);
+    let end = start + span.duration().unwrap_or_default();
+
+    let kind = match span.kind() {
+        Some(Kind::Server) => SPAN_KIND_SERVER,
+        Some(Kind::Client) => SPAN_KIND_CLIENT,
+        Some(Kind::Producer) => SPAN_KIND_PRODUCER,
+        Some(Kind::Consumer) => SPAN_KIND_CONSUMER,
+        _ => SPAN_KIND_INTERNAL,
+    };
+
+    let mut tags = span.tags().iter().collect::<Vec<_>>();
+    tags.sort();
+
+    json!({
+        // OTLP requires 16 byte trace IDs, but zipkin allows 8 byte IDs
+        "traceId": format!("{:0>32}", span.trace_id().to_string()),
+        "spanId": span.id().to_string(),
+        "parentSpanId": span.parent_id().map_or_else(String::new, |id| id.to_string()),
+        "name": span.name().unwrap_or("unknown"),
+        "kind": kind,
+        "startTimeUnixNano": unix_nanos(start),
+        "endTimeUnixNano": unix_nanos(end),
+        "attributes": tags
+            .into_iter()
+            .map(|(k, v)| attribute(k, v))
+            .collect::<Vec<_>>(),
+        "events": span
+            .annotations()
+            .iter()
+            .map(|a| json!({"timeUnixNano": unix_nanos(a.timestamp()), "name": a.value()}))
+            .collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytes::Bytes;
+    use http::{Request, Response};
+    use http_body_util::{BodyExt, Full};
+    use hyper::body::Incoming;
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use hyper_util::rt::TokioIo;
+    use std::convert::Infallible;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use zipkin::{Annotation, SpanId, TraceId};
+
+    async fn mock_collector() -> (u16, mpsc::UnboundedReceiver<(String, Value)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        task::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let tx = tx.clone();
+                task::spawn(async move {
+                    let service = service_fn(move |req: Request<Incoming>| {
+                        let tx = tx.clone();
+                        async move {
+                            let path = req.uri().path().to_string();
+                            let body = req.into_body().collect().await.unwrap().to_bytes();
+                            tx.send((path, serde_json::from_slice(&body).unwrap()))
+                                .unwrap();
+                            Ok::<_, Infallible>(Response::new(Full::new(Bytes::from("{}"))))
+                        }
+                    });
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+
+        (port, rx)
+    }
+
+    #[tokio::test]
+    async fn export() {
+        let (port, mut rx) = mock_collector().await;
+
+        let config = TraceExporterConfig::builder()
+            .collector(
+                serde_json::from_value(json!({"uris": [format!("http://127.0.0.1:{port}")]}))
+                    .unwrap(),
+            )
+            .flush_interval(Duration::from_secs(60 * 60))
+            .build()
+            .unwrap();
+        let mut hooks = ShutdownHooks::new();
+        let exporter = OtlpExporter::new(
+            &config,
+            "my-service",
+            "1.0.0",
+            &Arc::new(MetricRegistry::new()),
+            &mut hooks,
+        )
+        .unwrap();
+
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        exporter.export(
+            &zipkin::Span::builder()
+                .trace_id(TraceId::from([1, 2, 3, 4, 5, 6, 7, 8]))
+                .id(SpanId::from([8, 7, 6, 5, 4, 3, 2, 1]))
+                .parent_id(SpanId::from([1, 1, 1, 1, 1, 1, 1, 1]))
+                .name("my-span")
+                .kind(Kind::Server)
+                .timestamp(start)
+                .duration(Duration::from_millis(5))
+                .tag("foo", "bar")
+                .annotation(Annotation::new(start, "fizz"))
+                .build(),
+        );
+
+        // the hooks flush the queue
+        hooks.await;
+
+        let (path, body) = rx.recv().await.unwrap();
+        assert_eq!(path, "/v1/traces");
+
+        let resource_spans = &body["resourceSpans"][0];
+        assert_eq!(
+            resource_spans["resource"]["attributes"],
+            json!([
+                {"key": "service.name", "value": {"stringValue": "my-service"}},
+                {"key": "service.version", "value": {"stringValue": "1.0.0"}},
+            ]),
+        );
+        assert_eq!(
+            resource_spans["scopeSpans"][0]["spans"],
+            json!([{
+                "traceId": "00000000000000000102030405060708",
+                "spanId": "0807060504030201",
+                "parentSpanId": "0101010101010101",
+                "name": "my-span",
+                "kind": SPAN_KIND_SERVER,
+                "startTimeUnixNano": "1000000000",
+                "endTimeUnixNano": "1005000000",
+                "attributes": [{"key": "foo", "value": {"stringValue": "bar"}}],
+                "events": [{"timeUnixNano": "1000000000", "name": "fizz"}],
+            }]),
+        );
+    }
+}
@@ -0,0 +1,478 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use conjure_error::Error;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use refreshable::Refreshable;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use witchcraft_metrics::{MetricId, MetricRegistry};
+use witchcraft_server_config::runtime::{LoggingConfig, TraceSamplingConfig};
+use zipkin::{Kind, Sample, SamplingFlags, Span, TraceId};
+
+const UPSTREAM: &str = "upstream";
+const RATE: &str = "rate";
+const RULE: &str = "rule";
+const ERROR: &str = "error";
+const LATENCY: &str = "latency";
+const MAX_BUFFERED_SPANS: usize = 1_000;
+
+static SAMPLER: OnceCell<Arc<Sampler>> = OnceCell::new();
+
+pub fn init(
+    metrics: &Arc<MetricRegistry>,
+    runtime: &Refreshable<LoggingConfig, Error>,
+) -> WitchcraftSampler {
+    let sampler = Arc::new(Sampler::new(
+        runtime.map(|c| c.trace_rate()),
+        runtime.map(|c| c.trace_sampling().clone()),
+        metrics.clone(),
+    ));
+    SAMPLER
+        .set(sampler.clone())
+        .ok()
+        .expect("sampler already initialized");
+
+    WitchcraftSampler(sampler)
+}
+
+/// The sampling decision for a request.
+pub struct RequestSampling {
+    /// The sampling flags to use for the request's span.
+    pub flags: SamplingFlags,
+    /// If `true`, the request is unsampled but the request's trace must be passed to [`defer_trace`] so the spans the
+    /// server records for it are buffered until the request completes.
+    pub deferred: bool,
+}
+
+/// Makes the sampling decision for a request with the given propagated sampling flags.
+///
+/// Decisions made by upstream services are respected. Otherwise, the request is sampled at the rate of the first
+/// matching sampling rule, or the global trace rate if none match. If that doesn't select the request and tail-based
+/// sampling is enabled, the decision is deferred until the request completes. The deferral is local to this service,
+/// so the request's trace is propagated as unsampled.
+pub fn sample_request(
+    flags: SamplingFlags,
+    service: Option<&str>,
+    endpoint: Option<&str>,
+) -> RequestSampling {
+    match SAMPLER.get() {
+        Some(sampler) => sampler.sample_request(flags, service, endpoint),
+        None => RequestSampling {
+            flags,
+            deferred: false,
+        },
+    }
+}
+
+/// Starts buffering the spans of a trace whose sampling decision was deferred.
+pub fn defer_trace(trace_id: TraceId) {
+    if let Some(sampler) = SAMPLER.get() {
+        sampler.defer(trace_id);
+    }
+}
+
+/// Determines if spans of the trace should be recorded separately and passed to [`process_deferred`].
+pub fn is_deferred(trace_id: TraceId) -> bool {
+    SAMPLER
+        .get()
+        .is_some_and(|sampler| sampler.is_deferred(trace_id))
+}
+
+/// Processes a finished span of a deferred trace.
+///
+/// Spans are buffered until the trace's server span completes, at which point they are either all passed to `report`
+/// or all discarded. Spans finishing after that follow the same decision.
+pub fn process_deferred(span: Span, report: &mut dyn FnMut(Span)) {
+    if let Some(sampler) = SAMPLER.get() {
+        sampler.process_deferred(span, report);
+    }
+}
+
+/// The sampler used for traces not started by requests.
+pub struct WitchcraftSampler(Arc<Sampler>);
+
+impl Sample for WitchcraftSampler {
+    fn sample(&self, _: TraceId) -> bool {
+        self.0.sample_rate(*self.0.trace_rate.get(), RATE)
+    }
+}
+
+struct Sampler {
+    trace_rate: Refreshable<f32, Error>,
+    config: Refreshable<TraceSamplingConfig, Error>,
+    metrics: Arc<MetricRegistry>,
+    pending: Mutex<Pending>,
+}
+
+#[derive(Default)]
+struct Pending {
+    // TraceId doesn't implement Hash, so traces are keyed by their hex representation
+    traces: HashMap<String, Trace>,
+    // may contain the IDs of traces which have already been evicted
+    order: VecDeque<String>,
+}
+
+enum Trace {
+    Buffered(Vec<Span>),
+    // spans can finish after the request completes, so we remember the decision to apply it to those as well
+    Reported,
+    Discarded,
+}
+
+impl Sampler {
+    fn new(
+        trace_rate: Refreshable<f32, Error>,
+        config: Refreshable<TraceSamplingConfig, Error>,
+        metrics: Arc<MetricRegistry>,
+    ) -> Self {
+        Sampler {
+            trace_rate,
+            config,
+            metrics,
+            pending: Mutex::new(Pending::default()),
+        }
+    }
+
+    fn mark(&self, reason: &'static str) {
+        self.metrics
+            .meter(MetricId::new("tracing.sampled").with_tag("reason", reason))
+            .mark(1);
+    }
+
+    fn sample_rate(&self, rate: f32, reason: &'static str) -> bool {
+        let sampled = rand::random::<f32>() < rate;
+        if sampled {
+            self.mark(reason);
+        }
+        sampled
+    }
+
+    fn sample_request(
+        &self,
+        flags: SamplingFlags,
+        service: Option<&str>,
+        endpoint: Option<&str>,
+    ) -> RequestSampling {
+        match flags.sampled() {
+            Some(true) => {
+                self.mark(UPSTREAM);
+                return RequestSampling {
+                    flags,
+                    deferred: false,
+                };
+            }
+            Some(false) => {
+                return RequestSampling {
+                    flags,
+                    deferred: false,
+                }
+            }
+            None => {}
+        }
+
+        let config = self.config.get();
+        let rule = config.rules().iter().find(|rule| {
+            rule.service().is_none_or(|s| Some(s) == service)
+                && rule.endpoint().is_none_or(|e| Some(e) == endpoint)
+        });
+        let sampled = match rule {
+            Some(rule) => self.sample_rate(rule.rate(), RULE),
+            None => self.sample_rate(*self.trace_rate.get(), RATE),
+        };
+
+        RequestSampling {
+            flags: SamplingFlags::builder().sampled(sampled).build(),
+            deferred: !sampled && config.tail().enabled(),
+        }
+    }
+
+    fn defer(&self, trace_id: TraceId) {
+        let max_pending_traces = self.config.get().tail().max_pending_traces();
+
+        let trace_id = trace_id.to_string();
+        let mut pending = self.pending.lock();
+        pending
+            .traces
+            .insert(trace_id.clone(), Trace::Buffered(vec![]));
+        pending.order.push_back(trace_id);
+
+        while pending.traces.len() > max_pending_traces {
+            let Some(trace_id) = pending.order.pop_front() else {
+                break;
+            };
+            pending.traces.remove(&trace_id);
+        }
+
+        if pending.order.len() > max_pending_traces * 2 {
+            let Pending { traces, order } = &mut *pending;
+            order.retain(|trace_id| traces.contains_key(trace_id));
+        }
+    }
+
+    fn is_deferred(&self, trace_id: TraceId) -> bool {
+        let pending = self.pending.lock();
+        if pending.traces.is_empty() {
+            return false;
+        }
+
+        matches!(
+            pending.traces.get(&trace_id.to_string()),
+            Some(Trace::Buffered(_) | Trace::Reported)
+        )
+    }
+
+    fn process_deferred(&self, span: Span, report: &mut dyn FnMut(Span)) {
+        let trace_id = span.trace_id().to_string();
+        let mut pending = self.pending.lock();
+        let spans = match pending.traces.get_mut(&trace_id) {
+            Some(Trace::Buffered(spans)) => spans,
+            Some(Trace::Reported) => {
+                drop(pending);
+                report(span);
+                return;
+            }
+            Some(Trace::Discarded) | None => return,
+        };
+
+        if !matches!(span.kind(), Some(Kind::Server)) {
+            if spans.len() < MAX_BUFFERED_SPANS {
+                spans.push(span);
+            }
+            return;
+        }
+
+        let Some(reason) = self.tail_reason(&span) else {
+            pending.traces.insert(trace_id, Trace::Discarded);
+            return;
+        };
+
+        let Some(Trace::Buffered(spans)) = pending.traces.insert(trace_id, Trace::Reported) else {
+            unreachable!();
+        };
+        drop(pending);
+        self.mark(reason);
+
+        for span in spans {
+            report(span);
+        }
+        report(span);
+    }
+
+    fn tail_reason(&self, span: &Span) -> Option<&'static str> {
+        let config = self.config.get();
+        let config = config.tail();
+
+        let server_error = span
+            .tags()
+            .get("http.status_code")
+            .and_then(|s| s.parse::<u16>().ok())
+            .is_some_and(|s| s >= 500);
+        if config.errors() && server_error {
+            return Some(ERROR);
+        }
+
+        if let (Some(threshold), Some(duration)) = (config.latency_threshold(), span.duration()) {
+            if duration >= threshold {
+                return Some(LATENCY);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use witchcraft_server_config::runtime::{TailSamplingConfig, TraceSamplingRuleConfig};
+    use zipkin::SpanId;
+
+    fn sampler(trace_rate: f32, config: TraceSamplingConfig) -> (Sampler, Arc<MetricRegistry>) {
+        let (trace_rate, _) = Refreshable::new(trace_rate);
+        let (config, _) = Refreshable::new(config);
+        let metrics = Arc::new(MetricRegistry::new());
+        (Sampler::new(trace_rate, config, metrics.clone()), metrics)
+    }
+
+    fn sampled(metrics: &MetricRegistry, reason: &'static str) -> i64 {
+        metrics
+            .meter(MetricId::new("tracing.sampled").with_tag("reason", reason))
+            .count()
+    }
+
+    fn span(trace: u8, kind: Option<Kind>, status: u16, duration: Duration) -> Span {
+        let mut span = Span::builder();
+        span.trace_id(TraceId::from([trace; 8]))
+            .id(SpanId::from([1; 8]))
+            .timestamp(SystemTime::now())
+            .duration(duration)
+            .tag("http.status_code", &status.to_string());
+        if let Some(kind) = kind {
+            span.kind(kind);
+        }
+        span.build()
+    }
+
+    #[test]
+    fn rules() {
+        let (sampler, metrics) = sampler(
+            0.,
+            TraceSamplingConfig::builder()
+                .push_rules(
+                    TraceSamplingRuleConfig::builder()
+                        .rate(1.)
+                        .service("MyService".to_string())
+                        .endpoint("sampled".to_string())
+                        .build()
+                        .unwrap(),
+                )
+                .build(),
+        );
+        let flags = SamplingFlags::default();
+
+        let sampling = sampler.sample_request(flags, Some("MyService"), Some("sampled"));
+        assert_eq!(sampling.flags.sampled(), Some(true));
+        assert!(!sampling.deferred);
+
+        let sampling = sampler.sample_request(flags, Some("OtherService"), Some("sampled"));
+        assert_eq!(sampling.flags.sampled(), Some(false));
+
+        let sampling =
+            sampler.sample_request(SamplingFlags::builder().sampled(true).build(), None, None);
+        assert_eq!(sampling.flags.sampled(), Some(true));
+
+        assert_eq!(sampled(&metrics, RULE), 1);
+        assert_eq!(sampled(&metrics, UPSTREAM), 1);
+        assert_eq!(sampled(&metrics, RATE), 0);
+    }
+
+    #[test]
+    fn tail_sampling() {
+        let (sampler, metrics) = sampler(
+            0.,
+            TraceSamplingConfig::builder()
+                .tail(
+                    TailSamplingConfig::builder()
+                        .enabled(true)
+                        .latency_threshold(Duration::from_secs(1))
+                        .build()
+                        .unwrap(),
+                )
+                .build(),
+        );
+
+        // the deferral isn't propagated to other services
+        let sampling = sampler.sample_request(SamplingFlags::default(), None, None);
+        assert_eq!(sampling.flags.sampled(), Some(false));
+        assert!(sampling.deferred);
+
+        let mut reported = vec![];
+        let mut report = |span: Span| reported.push(span.trace_id());
+
+        // fast success is discarded, along with spans finishing after it
+        sampler.defer(TraceId::from([1; 8]));
+        assert!(sampler.is_deferred(TraceId::from([1; 8])));
+        sampler.process_deferred(span(1, None, 200, Duration::ZERO), &mut report);
+        sampler.process_deferred(
+            span(1, Some(Kind::Server), 200, Duration::ZERO),
+            &mut report,
+        );
+        sampler.process_deferred(span(1, None, 200, Duration::ZERO), &mut report);
+        assert!(!sampler.is_deferred(TraceId::from([1; 8])));
+
+        // error is reported, along with spans finishing after it
+        sampler.defer(TraceId::from([2; 8]));
+        sampler.process_deferred(span(2, None, 500, Duration::ZERO), &mut report);
+        sampler.process_deferred(
+            span(2, Some(Kind::Server), 503, Duration::ZERO),
+            &mut report,
+        );
+        sampler.process_deferred(span(2, None, 200, Duration::ZERO), &mut report);
+
+        // slow request is reported
+        sampler.defer(TraceId::from([3; 8]));
+        sampler.process_deferred(
+            span(3, Some(Kind::Server), 200, Duration::from_secs(2)),
+            &mut report,
+        );
+
+        // spans of other traces aren't deferred
+        assert!(!sampler.is_deferred(TraceId::from([4; 8])));
+        sampler.process_deferred(span(4, None, 200, Duration::ZERO), &mut report);
+
+        assert_eq!(
+            reported,
+            [
+                TraceId::from([2; 8]),
+                TraceId::from([2; 8]),
+                TraceId::from([2; 8]),
+                TraceId::from([3; 8]),
+            ],
+        );
+        assert_eq!(sampled(&metrics, ERROR), 1);
+        assert_eq!(sampled(&metrics, LATENCY), 1);
+    }
+
+    #[test]
+    fn max_pending_traces() {
+        let (sampler, _) = sampler(
+            0.,
+            TraceSamplingConfig::builder()
+                .tail(
+                    TailSamplingConfig::builder()
+                        .enabled(true)
+                        .max_pending_traces(2)
+                        .build()
+                        .unwrap(),
+                )
+                .build(),
+        );
+
+        for i in 0..10 {
+            sampler.defer(TraceId::from([i; 8]));
+        }
+
+        let pending = sampler.pending.lock();
+        assert_eq!(pending.traces.len(), 2);
+        assert!(pending
+            .traces
+            .contains_key(&TraceId::from([9; 8]).to_string()));
+        assert!(pending.order.len() <= 4);
+    }
+
+    #[test]
+    fn max_buffered_spans() {
+        let (sampler, _) = sampler(
+            0.,
+            TraceSamplingConfig::builder()
+                .tail(TailSamplingConfig::builder().enabled(true).build().unwrap())
+                .build(),
+        );
+
+        let mut reported = 0;
+        let mut report = |_| reported += 1;
+        sampler.defer(TraceId::from([1; 8]));
+        for _ in 0..MAX_BUFFERED_SPANS * 2 {
+            sampler.process_deferred(span(1, None, 200, Duration::ZERO), &mut report);
+        }
+        sampler.process_deferred(
+            span(1, Some(Kind::Server), 500, Duration::ZERO),
+            &mut report,
+        );
+
+        assert_eq!(reported, MAX_BUFFERED_SPANS + 1);
+    }
+}
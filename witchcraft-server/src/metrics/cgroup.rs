@@ -0,0 +1,332 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Metrics describing the resource limits and usage of the process's cgroup.
+//!
+//! Both cgroup v1 and v2 are supported, including hybrid systems where some controllers are mounted in a v1 hierarchy
+//! and others in the unified v2 hierarchy.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use witchcraft_metrics::MetricRegistry;
+
+const NANOS_PER_MICRO: u64 = 1_000;
+// cgroup v1 reports an unlimited memory limit as a very large page-aligned value
+const V1_UNLIMITED_MEMORY: u64 = 1 << 62;
+
+/// Registers the cgroup metrics.
+///
+/// The quota and limit gauges have no value while the cgroup is unlimited, and are omitted from reports.
+pub fn register_metrics(metrics: &MetricRegistry) {
+    let Some(cgroups) = Cgroups::load() else {
+        return;
+    };
+
+    if let Some(cpu) = cgroups.cpu {
+        let cpu = Arc::new(cpu);
+        metrics.gauge("cgroup.cpu.quota", {
+            let cpu = cpu.clone();
+            move || cpu.quota()
+        });
+        metrics.gauge("cgroup.cpu.throttled-time", {
+            let cpu = cpu.clone();
+            move || cpu.stat().map_or(0, |s| s.throttled_micros)
+        });
+        metrics.gauge("cgroup.cpu.throttled-periods", {
+            let cpu = cpu.clone();
+            move || cpu.stat().map_or(0, |s| s.throttled_periods)
+        });
+    }
+
+    if let Some(memory) = cgroups.memory {
+        let memory = Arc::new(memory);
+        metrics.gauge("cgroup.memory.limit", {
+            let memory = memory.clone();
+            move || memory.memory_limit()
+        });
+        metrics.gauge("cgroup.memory.usage", {
+            let memory = memory.clone();
+            move || memory.memory_usage().unwrap_or(0)
+        });
+        metrics.gauge("cgroup.memory.oom-kills", {
+            let memory = memory.clone();
+            move || memory.oom_kills().unwrap_or(0)
+        });
+    }
+}
+
+struct Cgroups {
+    cpu: Option<Controller>,
+    memory: Option<Controller>,
+}
+
+impl Cgroups {
+    fn load() -> Option<Self> {
+        let mountinfo = fs::read_to_string("/proc/self/mountinfo").ok()?;
+        let cgroup = fs::read_to_string("/proc/self/cgroup").ok()?;
+
+        Some(Cgroups {
+            cpu: Controller::find(&mountinfo, &cgroup, "cpu"),
+            memory: Controller::find(&mountinfo, &cgroup, "memory"),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Controller {
+    V1(PathBuf),
+    V2(PathBuf),
+}
+
+struct CpuStat {
+    throttled_micros: u64,
+    throttled_periods: u64,
+}
+
+impl Controller {
+    /// Locates the directory of the process's cgroup for a controller, preferring a v1 hierarchy if the controller is
+    /// mounted in one.
+    fn find(mountinfo: &str, cgroup: &str, controller: &str) -> Option<Self> {
+        let mounts = mountinfo
+            .lines()
+            .filter_map(Mount::parse)
+            .collect::<Vec<_>>();
+
+        let v1 = mounts
+            .iter()
+            .find(|m| m.fstype == "cgroup" && m.super_options.split(',').any(|o| o == controller));
+        if let Some(mount) = v1 {
+            let path = cgroup.lines().find_map(|l| {
+                let mut parts = l.splitn(3, ':');
+                parts.next()?;
+                let controllers = parts.next()?;
+                let path = parts.next()?;
+                controllers
+                    .split(',')
+                    .any(|c| c == controller)
+                    .then_some(path)
+            })?;
+            return Some(Controller::V1(mount.cgroup_dir(path)));
+        }
+
+        let v2 = mounts.iter().find(|m| m.fstype == "cgroup2")?;
+        let path = cgroup.lines().find_map(|l| l.strip_prefix("0::"))?;
+        Some(Controller::V2(v2.cgroup_dir(path)))
+    }
+
+    fn read(&self, file: &str) -> Option<String> {
+        let dir = match self {
+            Controller::V1(dir) | Controller::V2(dir) => dir,
+        };
+        fs::read_to_string(dir.join(file)).ok()
+    }
+
+    /// Returns the CPU quota in cores, or `None` if unlimited.
+    fn quota(&self) -> Option<f64> {
+        match self {
+            Controller::V1(_) => {
+                let quota = self.read("cpu.cfs_quota_us")?.trim().parse::<i64>().ok()?;
+                let period = self.read("cpu.cfs_period_us")?.trim().parse::<i64>().ok()?;
+                parse_quota(quota, period)
+            }
+            Controller::V2(_) => {
+                let max = self.read("cpu.max")?;
+                let mut parts = max.split_whitespace();
+                let quota = parts.next()?.parse().ok()?;
+                let period = parts.next()?.parse().ok()?;
+                parse_quota(quota, period)
+            }
+        }
+    }
+
+    fn stat(&self) -> Option<CpuStat> {
+        let stat = self.read("cpu.stat")?;
+        let throttled_micros = match self {
+            Controller::V1(_) => stat_value(&stat, "throttled_time")? / NANOS_PER_MICRO,
+            Controller::V2(_) => stat_value(&stat, "throttled_usec")?,
+        };
+
+        Some(CpuStat {
+            throttled_micros,
+            throttled_periods: stat_value(&stat, "nr_throttled")?,
+        })
+    }
+
+    /// Returns the memory limit in bytes, or `None` if unlimited.
+    fn memory_limit(&self) -> Option<u64> {
+        match self {
+            Controller::V1(_) => {
+                let limit = self.read("memory.limit_in_bytes")?.trim().parse().ok()?;
+                (limit < V1_UNLIMITED_MEMORY).then_some(limit)
+            }
+            // "max" fails to parse, indicating no limit
+            Controller::V2(_) => self.read("memory.max")?.trim().parse().ok(),
+        }
+    }
+
+    fn memory_usage(&self) -> Option<u64> {
+        let file = match self {
+            Controller::V1(_) => "memory.usage_in_bytes",
+            Controller::V2(_) => "memory.current",
+        };
+        self.read(file)?.trim().parse().ok()
+    }
+
+    fn oom_kills(&self) -> Option<u64> {
+        let file = match self {
+            Controller::V1(_) => "memory.oom_control",
+            Controller::V2(_) => "memory.events",
+        };
+        stat_value(&self.read(file)?, "oom_kill")
+    }
+}
+
+fn parse_quota(quota: i64, period: i64) -> Option<f64> {
+    if quota <= 0 || period <= 0 {
+        return None;
+    }
+
+    Some(quota as f64 / period as f64)
+}
+
+/// Parses a value from a file of space-separated key-value pairs, one per line.
+fn stat_value(stat: &str, key: &str) -> Option<u64> {
+    stat.lines().find_map(|l| {
+        let (k, v) = l.split_once(' ')?;
+        if k == key {
+            v.trim().parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+struct Mount<'a> {
+    root: &'a str,
+    mount_point: &'a str,
+    fstype: &'a str,
+    super_options: &'a str,
+}
+
+impl<'a> Mount<'a> {
+    // See proc(5) for the format of mountinfo lines.
+    fn parse(line: &'a str) -> Option<Self> {
+        let (fields, tail) = line.split_once(" - ")?;
+        let mut fields = fields.split(' ');
+        let root = fields.nth(3)?;
+        let mount_point = fields.next()?;
+
+        let mut tail = tail.split(' ');
+        let fstype = tail.next()?;
+        let super_options = tail.nth(1)?;
+
+        Some(Mount {
+            root,
+            mount_point,
+            fstype,
+            super_options,
+        })
+    }
+
+    /// Returns the directory of a cgroup within this mount.
+    ///
+    /// The cgroup path is relative to the root of the hierarchy, which may not be the root of the mount when running
+    /// in a container without a cgroup namespace. If the cgroup isn't visible within the mount, the mount point itself
+    /// is used.
+    fn cgroup_dir(&self, path: &str) -> PathBuf {
+        let relative = if self.root == "/" {
+            Some(path)
+        } else {
+            path.strip_prefix(self.root)
+                .filter(|p| p.is_empty() || p.starts_with('/'))
+        };
+
+        let mount_point = Path::new(self.mount_point);
+        match relative {
+            Some(relative) => {
+                let dir = mount_point.join(relative.trim_start_matches('/'));
+                if dir.is_dir() {
+                    dir
+                } else {
+                    mount_point.to_path_buf()
+                }
+            }
+            None => mount_point.to_path_buf(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HYBRID_MOUNTINFO: &str = "\
+32 24 0:28 / /sys/fs/cgroup rw,relatime - tmpfs tmpfs rw,mode=755
+33 32 0:29 / /sys/fs/cgroup/cpu rw,relatime - cgroup cgroup rw,cpu
+36 32 0:32 / /sys/fs/cgroup/memory rw,relatime - cgroup cgroup rw,memory
+42 32 0:38 / /sys/fs/cgroup/unified rw,relatime - cgroup2 cgroup2 rw
+";
+
+    #[test]
+    fn find_v1() {
+        let cgroup = "4:memory:/\n1:cpu:/\n0::/\n";
+
+        assert_eq!(
+            Controller::find(HYBRID_MOUNTINFO, cgroup, "cpu"),
+            Some(Controller::V1(PathBuf::from("/sys/fs/cgroup/cpu"))),
+        );
+        assert_eq!(
+            Controller::find(HYBRID_MOUNTINFO, cgroup, "memory"),
+            Some(Controller::V1(PathBuf::from("/sys/fs/cgroup/memory"))),
+        );
+    }
+
+    #[test]
+    fn find_v2() {
+        let mountinfo = "30 23 0:26 / /sys/fs/cgroup rw,nosuid - cgroup2 cgroup2 rw,nsdelegate\n";
+        let cgroup = "0::/\n";
+
+        assert_eq!(
+            Controller::find(mountinfo, cgroup, "cpu"),
+            Some(Controller::V2(PathBuf::from("/sys/fs/cgroup"))),
+        );
+        assert_eq!(Controller::find(mountinfo, "", "cpu"), None);
+    }
+
+    #[test]
+    fn cgroup_dir_outside_mount_root() {
+        let mount =
+            Mount::parse("1 2 0:29 /docker/abc /sys/fs/cgroup/cpu rw - cgroup cgroup rw,cpu")
+                .unwrap();
+
+        assert_eq!(
+            mount.cgroup_dir("/docker/abc"),
+            PathBuf::from("/sys/fs/cgroup/cpu")
+        );
+        assert_eq!(
+            mount.cgroup_dir("/other"),
+            PathBuf::from("/sys/fs/cgroup/cpu")
+        );
+    }
+
+    #[test]
+    fn parse_stat() {
+        let stat = "nr_periods 10\nnr_throttled 3\nthrottled_usec 1500\n";
+
+        assert_eq!(stat_value(stat, "nr_throttled"), Some(3));
+        assert_eq!(stat_value(stat, "throttled_usec"), Some(1500));
+        assert_eq!(stat_value(stat, "missing"), None);
+        assert_eq!(parse_quota(150_000, 100_000), Some(1.5));
+        assert_eq!(parse_quota(-1, 100_000), None);
+    }
+}
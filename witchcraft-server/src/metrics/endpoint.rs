@@ -0,0 +1,136 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::logging::GaugeReporter;
+use crate::metrics::openmetrics::Encoder;
+use crate::metrics::{gauge_value, GAUGE_TIMEOUT};
+use conjure_error::{Error, PermissionDenied};
+use conjure_http::server::{
+    AsyncResponseBody, AsyncSerializeResponse, ConjureRuntime, FromStrOptionDecoder,
+};
+use conjure_http::{conjure_endpoints, endpoint};
+use futures_util::StreamExt;
+use http::header::CONTENT_TYPE;
+use http::{HeaderMap, HeaderValue, Response};
+use refreshable::Refreshable;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tokio::sync::Mutex;
+use tokio::time;
+use witchcraft_metrics::{Metric, MetricId, MetricRegistry};
+use witchcraft_server_config::runtime::RuntimeConfig;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const OPENMETRICS_CONTENT_TYPE: HeaderValue =
+    HeaderValue::from_static("application/openmetrics-text; version=1.0.0; charset=utf-8");
+
+#[conjure_endpoints]
+pub trait MetricsService {
+    #[endpoint(path = "/metrics", method = GET, produces = OpenMetricsResponseSerializer)]
+    async fn metrics(
+        &self,
+        #[header(name = "Authorization", decoder = FromStrOptionDecoder)] authorization: Option<
+            String,
+        >,
+    ) -> Result<String, Error>;
+}
+
+enum OpenMetricsResponseSerializer {}
+
+impl<W> AsyncSerializeResponse<String, W> for OpenMetricsResponseSerializer {
+    fn serialize(
+        _: &ConjureRuntime,
+        _: &HeaderMap,
+        value: String,
+    ) -> Result<Response<AsyncResponseBody<W>>, Error> {
+        let mut response = Response::new(AsyncResponseBody::Fixed(value.into()));
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE);
+
+        Ok(response)
+    }
+}
+
+pub struct MetricsResource {
+    shared_secret: Refreshable<Option<String>, Error>,
+    metrics: Arc<MetricRegistry>,
+    // Shared across requests so a slow gauge isn't evaluated concurrently by multiple scrapes.
+    gauge_reporter: Mutex<GaugeReporter<(MetricId, Option<f64>)>>,
+}
+
+impl MetricsResource {
+    pub fn new<R>(runtime: &Refreshable<R, Error>, metrics: &Arc<MetricRegistry>) -> Self
+    where
+        R: AsRef<RuntimeConfig> + PartialEq + 'static + Sync + Send,
+    {
+        MetricsResource {
+            shared_secret: runtime.map(|c| {
+                c.as_ref()
+                    .diagnostics()
+                    .metrics_shared_secret()
+                    .map(|s| s.to_string())
+            }),
+            metrics: metrics.clone(),
+            gauge_reporter: Mutex::new(GaugeReporter::new(gauge_value)),
+        }
+    }
+
+    fn authorize(&self, authorization: Option<&str>) -> Result<(), Error> {
+        let expected = self.shared_secret.get();
+        let Some(expected) = &*expected else {
+            return Ok(());
+        };
+
+        let token = authorization
+            .and_then(|a| a.strip_prefix("Bearer "))
+            .unwrap_or("");
+        if !bool::from(token.as_bytes().ct_eq(expected.as_bytes())) {
+            return Err(Error::service_safe(
+                "invalid metrics secret",
+                PermissionDenied::new(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl MetricsService for MetricsResource {
+    async fn metrics(&self, authorization: Option<String>) -> Result<String, Error> {
+        self.authorize(authorization.as_deref())?;
+
+        let mut gauge_reporter = self.gauge_reporter.lock().await;
+        let mut encoder = Encoder::new();
+
+        for (id, metric) in &self.metrics.metrics() {
+            match metric {
+                Metric::Gauge(gauge) => {
+                    gauge_reporter.insert(id, gauge);
+                }
+                metric => encoder.metric(id, metric),
+            }
+        }
+
+        let _ = time::timeout(GAUGE_TIMEOUT, async {
+            while let Some(result) = gauge_reporter.next().await {
+                if let Ok((id, Some(value))) = result {
+                    encoder.gauge(&id, value);
+                }
+            }
+        })
+        .await;
+
+        Ok(encoder.finish())
+    }
+}
@@ -12,26 +12,51 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use crate::metrics::rusage::Rusage;
+use serde_json::Value;
 use std::panic;
-use std::time::Instant;
-use witchcraft_metrics::MetricRegistry;
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+use witchcraft_metrics::{Gauge, MetricId, MetricRegistry};
 
+#[cfg(target_os = "linux")]
+mod cgroup;
+pub(crate) mod endpoint;
 #[cfg(feature = "jemalloc")]
 mod jemalloc;
+mod openmetrics;
 #[cfg(target_os = "linux")]
 mod proc;
+pub(crate) mod reporter;
+mod runtime;
 mod rusage;
 
-pub fn init(metrics: &MetricRegistry) {
+// Gauges which haven't completed by this point are omitted from collected metrics.
+const GAUGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn init(metrics: &MetricRegistry, handle: &Handle) {
     register_uptime_metric(metrics);
     register_panic_metric(metrics);
     register_rusage_metrics(metrics);
+    runtime::register_metrics(metrics, "server", handle);
     #[cfg(target_os = "linux")]
     proc::register_metrics(metrics);
+    #[cfg(target_os = "linux")]
+    cgroup::register_metrics(metrics);
     #[cfg(feature = "jemalloc")]
     jemalloc::register_metrics(metrics);
 }
 
+/// Evaluates a gauge, returning its value if it is numeric.
+fn gauge_value(id: &MetricId, gauge: &dyn Gauge) -> (MetricId, Option<f64>) {
+    let value = match serde_json::to_value(gauge.value()) {
+        Ok(Value::Number(n)) => n.as_f64(),
+        Ok(Value::Bool(b)) => Some(if b { 1. } else { 0. }),
+        _ => None,
+    };
+
+    (id.clone(), value)
+}
+
 fn register_uptime_metric(metrics: &MetricRegistry) {
     let start = Instant::now();
     metrics.gauge("process.uptime", move || start.elapsed().as_micros() as u64);
@@ -0,0 +1,269 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! An encoder for the [OpenMetrics](https://openmetrics.io) text format.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use witchcraft_metrics::{Metric, MetricId};
+
+const QUANTILES: [f64; 4] = [0.5, 0.95, 0.99, 0.999];
+const NANOS_PER_SECOND: f64 = 1_000_000_000.;
+
+struct Family {
+    type_: &'static str,
+    samples: String,
+}
+
+/// Accumulates metrics into OpenMetrics metric families.
+///
+/// Metric names and tag keys are converted to valid OpenMetrics identifiers by replacing invalid characters with `_`.
+/// Counters are reported as gauges since they can decrease, meters as counters, and histograms and timers as
+/// summaries, with timer values in seconds.
+#[derive(Default)]
+pub struct Encoder {
+    families: BTreeMap<String, Family>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Encoder::default()
+    }
+
+    /// Adds a metric to the output. Gauges should be evaluated separately and added via [`Encoder::gauge`].
+    pub fn metric(&mut self, id: &MetricId, metric: &Metric) {
+        let name = sanitize_name(id.name());
+
+        match metric {
+            Metric::Counter(m) => self.sample(&name, "gauge", &name, id, None, m.count() as f64),
+            Metric::Meter(m) => self.sample(
+                &name,
+                "counter",
+                &format!("{name}_total"),
+                id,
+                None,
+                m.count() as f64,
+            ),
+            Metric::Histogram(m) => {
+                let snapshot = m.snapshot();
+                for quantile in QUANTILES {
+                    self.sample(
+                        &name,
+                        "summary",
+                        &name,
+                        id,
+                        Some(quantile),
+                        snapshot.value(quantile),
+                    );
+                }
+                let count = format!("{name}_count");
+                self.sample(&name, "summary", &count, id, None, m.count() as f64);
+
+                let max = format!("{name}_max");
+                self.sample(&max, "gauge", &max, id, None, snapshot.max() as f64);
+            }
+            Metric::Timer(m) => {
+                let name = format!("{name}_seconds");
+                let snapshot = m.snapshot();
+                for quantile in QUANTILES {
+                    self.sample(
+                        &name,
+                        "summary",
+                        &name,
+                        id,
+                        Some(quantile),
+                        snapshot.value(quantile) / NANOS_PER_SECOND,
+                    );
+                }
+                let count = format!("{name}_count");
+                self.sample(&name, "summary", &count, id, None, m.count() as f64);
+
+                let max = format!("{name}_max");
+                self.sample(
+                    &max,
+                    "gauge",
+                    &max,
+                    id,
+                    None,
+                    snapshot.max() as f64 / NANOS_PER_SECOND,
+                );
+            }
+            Metric::Gauge(_) => {}
+        }
+    }
+
+    /// Adds the value of a gauge to the output.
+    pub fn gauge(&mut self, id: &MetricId, value: f64) {
+        let name = sanitize_name(id.name());
+        self.sample(&name, "gauge", &name, id, None, value);
+    }
+
+    fn sample(
+        &mut self,
+        family: &str,
+        type_: &'static str,
+        name: &str,
+        id: &MetricId,
+        quantile: Option<f64>,
+        value: f64,
+    ) {
+        let family = self
+            .families
+            .entry(family.to_string())
+            .or_insert_with(|| Family {
+                type_,
+                samples: String::new(),
+            });
+        // the same sanitized name may have been used for a metric of a different type
+        if family.type_ != type_ {
+            return;
+        }
+
+        let samples = &mut family.samples;
+        samples.push_str(name);
+
+        let mut labels = id
+            .tags()
+            .iter()
+            .map(|(k, v)| (sanitize_label(k), v.to_string()))
+            .collect::<Vec<_>>();
+        if let Some(quantile) = quantile {
+            labels.push(("quantile".to_string(), quantile.to_string()));
+        }
+        if !labels.is_empty() {
+            samples.push('{');
+            for (i, (key, value)) in labels.iter().enumerate() {
+                if i != 0 {
+                    samples.push(',');
+                }
+                let _ = write!(samples, "{key}=\"{}\"", escape(value));
+            }
+            samples.push('}');
+        }
+
+        samples.push(' ');
+        samples.push_str(&format_value(value));
+        samples.push('\n');
+    }
+
+    /// Returns the encoded metric families.
+    pub fn finish(self) -> String {
+        let mut out = String::new();
+        for (name, family) in self.families {
+            let _ = writeln!(out, "# TYPE {name} {}", family.type_);
+            out.push_str(&family.samples);
+        }
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+fn sanitize_name(name: &str) -> String {
+    let mut out = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | ':' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+fn sanitize_label(key: &str) -> String {
+    let mut out = sanitize_name(key).replace(':', "_");
+    // names starting with __ are reserved
+    while out.starts_with("__") {
+        out.remove(0);
+    }
+    out
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metrics::gauge_value;
+    use std::time::Duration;
+    use witchcraft_metrics::MetricRegistry;
+
+    #[test]
+    fn encode() {
+        let registry = MetricRegistry::new();
+        registry
+            .counter(MetricId::new("server.counter").with_tag("service-name", "foo\"bar"))
+            .inc();
+        registry.meter("server.requests").mark(3);
+        registry
+            .timer("server.latency")
+            .update(Duration::from_millis(500));
+        registry.gauge("1.weird-name", || 5);
+
+        let mut encoder = Encoder::new();
+        for (id, metric) in &registry.metrics() {
+            match metric {
+                Metric::Gauge(gauge) => {
+                    let (id, value) = gauge_value(id, &**gauge);
+                    encoder.gauge(&id, value.unwrap());
+                }
+                metric => encoder.metric(id, metric),
+            }
+        }
+
+        assert_eq!(
+            encoder.finish(),
+            "\
+# TYPE _1_weird_name gauge
+_1_weird_name 5
+# TYPE server_counter gauge
+server_counter{service_name=\"foo\\\"bar\"} 1
+# TYPE server_latency_seconds summary
+server_latency_seconds{quantile=\"0.5\"} 0.5
+server_latency_seconds{quantile=\"0.95\"} 0.5
+server_latency_seconds{quantile=\"0.99\"} 0.5
+server_latency_seconds{quantile=\"0.999\"} 0.5
+server_latency_seconds_count 1
+# TYPE server_latency_seconds_max gauge
+server_latency_seconds_max 0.5
+# TYPE server_requests counter
+server_requests_total 3
+# EOF
+"
+        );
+    }
+}
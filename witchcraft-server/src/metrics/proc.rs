@@ -11,14 +11,188 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
 use std::mem::MaybeUninit;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::{fs, io};
-use witchcraft_metrics::MetricRegistry;
+use witchcraft_metrics::{MetricId, MetricRegistry};
+
+// Socket counts are shared by the per-state gauges, which are evaluated together.
+const SOCKET_CACHE_TTL: Duration = Duration::from_secs(1);
+// Reading smaps_rollup walks every mapping of the process while holding its mmap lock, so it's read at most this often
+// no matter how many reporters evaluate the gauge.
+const PSS_CACHE_TTL: Duration = Duration::from_secs(10);
+
+// See include/net/tcp_states.h in the Linux source. TIME_WAIT sockets are omitted since they are no longer owned by the
+// process.
+const TCP_STATES: &[(&str, &str)] = &[
+    ("01", "established"),
+    ("02", "syn-sent"),
+    ("03", "syn-recv"),
+    ("04", "fin-wait1"),
+    ("05", "fin-wait2"),
+    ("07", "close"),
+    ("08", "close-wait"),
+    ("09", "last-ack"),
+    ("0A", "listen"),
+    ("0B", "closing"),
+];
 
 pub fn register_metrics(metrics: &MetricRegistry) {
     metrics.gauge("process.threads", || num_threads().unwrap_or(0));
 
     metrics.gauge("process.filedescriptor", || filedescriptor().unwrap_or(0.));
+
+    metrics.gauge("process.memory.rss", || {
+        status_value("/proc/self/status", "VmRSS").unwrap_or(0)
+    });
+    let pss = Pss::new();
+    metrics.gauge("process.memory.pss", move || pss.get());
+
+    metrics.gauge(
+        MetricId::new("process.context-switches").with_tag("type", "voluntary"),
+        || status_value("/proc/self/status", "voluntary_ctxt_switches").unwrap_or(0),
+    );
+    metrics.gauge(
+        MetricId::new("process.context-switches").with_tag("type", "involuntary"),
+        || status_value("/proc/self/status", "nonvoluntary_ctxt_switches").unwrap_or(0),
+    );
+
+    let sockets = Arc::new(SocketCounts::new());
+    for (_, state) in TCP_STATES {
+        metrics.gauge(
+            MetricId::new("process.sockets").with_tag("state", *state),
+            {
+                let sockets = sockets.clone();
+                move || sockets.get(state)
+            },
+        );
+    }
+}
+
+/// Reads a value from a `key: value` formatted file like `/proc/self/status`, converting sizes in kB to bytes.
+fn status_value(path: &str, key: &str) -> Option<u64> {
+    let status = fs::read_to_string(path).ok()?;
+    parse_status_value(&status, key)
+}
+
+fn parse_status_value(status: &str, key: &str) -> Option<u64> {
+    let value = status.lines().find_map(|l| {
+        let (k, v) = l.split_once(':')?;
+        (k == key).then_some(v.trim())
+    })?;
+
+    match value.strip_suffix(" kB") {
+        Some(kb) => kb.trim().parse::<u64>().ok().map(|kb| kb * 1024),
+        None => value.parse().ok(),
+    }
+}
+
+/// The proportional set size of the process in bytes.
+struct Pss {
+    cache: Mutex<Option<(Instant, u64)>>,
+}
+
+impl Pss {
+    fn new() -> Self {
+        Pss {
+            cache: Mutex::new(None),
+        }
+    }
+
+    fn get(&self) -> u64 {
+        let mut cache = self.cache.lock();
+        match &*cache {
+            Some((time, pss)) if time.elapsed() < PSS_CACHE_TTL => *pss,
+            _ => {
+                let pss = status_value("/proc/self/smaps_rollup", "Pss").unwrap_or(0);
+                *cache = Some((Instant::now(), pss));
+                pss
+            }
+        }
+    }
+}
+
+/// The number of TCP sockets owned by the process, by state.
+struct SocketCounts {
+    cache: Mutex<Option<(Instant, HashMap<&'static str, u64>)>>,
+}
+
+impl SocketCounts {
+    fn new() -> Self {
+        SocketCounts {
+            cache: Mutex::new(None),
+        }
+    }
+
+    fn get(&self, state: &str) -> u64 {
+        let mut cache = self.cache.lock();
+        let counts = match &*cache {
+            Some((time, counts)) if time.elapsed() < SOCKET_CACHE_TTL => counts,
+            _ => &cache.insert((Instant::now(), socket_counts())).1,
+        };
+
+        counts.get(state).copied().unwrap_or(0)
+    }
+}
+
+fn socket_counts() -> HashMap<&'static str, u64> {
+    let mut counts = HashMap::new();
+
+    // /proc/net/tcp lists all sockets in the network namespace, so we filter to those referenced by the process's fds.
+    let Ok(inodes) = socket_inodes() else {
+        return counts;
+    };
+
+    for path in ["/proc/self/net/tcp", "/proc/self/net/tcp6"] {
+        if let Ok(table) = fs::read_to_string(path) {
+            count_sockets(&table, &inodes, &mut counts);
+        }
+    }
+
+    counts
+}
+
+fn socket_inodes() -> io::Result<HashSet<u64>> {
+    let mut inodes = HashSet::new();
+    for r in fs::read_dir("/proc/self/fd")? {
+        // fds can be closed while we're iterating
+        let Ok(target) = fs::read_link(r?.path()) else {
+            continue;
+        };
+        let inode = target
+            .to_str()
+            .and_then(|t| t.strip_prefix("socket:["))
+            .and_then(|t| t.strip_suffix(']'))
+            .and_then(|t| t.parse().ok());
+        if let Some(inode) = inode {
+            inodes.insert(inode);
+        }
+    }
+
+    Ok(inodes)
+}
+
+fn count_sockets(table: &str, inodes: &HashSet<u64>, counts: &mut HashMap<&'static str, u64>) {
+    // The first line is a header. The state is the 4th column and the inode the 10th.
+    for line in table.lines().skip(1) {
+        let mut columns = line.split_whitespace();
+        let Some(state) = columns.nth(3) else {
+            continue;
+        };
+        let Some(inode) = columns.nth(5).and_then(|i| i.parse().ok()) else {
+            continue;
+        };
+        if !inodes.contains(&inode) {
+            continue;
+        }
+
+        if let Some((_, name)) = TCP_STATES.iter().find(|(code, _)| *code == state) {
+            *counts.entry(*name).or_insert(0) += 1;
+        }
+    }
 }
 
 fn num_threads() -> Option<i64> {
@@ -81,4 +255,29 @@ mod test {
 
         assert_eq!(parse_num_threads(stat), Some(1));
     }
+
+    #[test]
+    fn status() {
+        let status = "Name:\tcat\nVmRSS:\t    1312 kB\nvoluntary_ctxt_switches:\t4\n";
+
+        assert_eq!(parse_status_value(status, "VmRSS"), Some(1312 * 1024));
+        assert_eq!(
+            parse_status_value(status, "voluntary_ctxt_switches"),
+            Some(4)
+        );
+        assert_eq!(parse_status_value(status, "Pss"), None);
+    }
+
+    #[test]
+    fn sockets() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 100 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:1F90 0100007F:D2F0 01 00000000:00000000 00:00000000 00000000  1000        0 101 1 0000000000000000 20 4 30 10 -1
+   2: 0100007F:1F90 0100007F:D2F2 01 00000000:00000000 00:00000000 00000000  1000        0 200 1 0000000000000000 20 4 30 10 -1
+";
+        let mut counts = HashMap::new();
+        count_sockets(table, &HashSet::from([100, 101]), &mut counts);
+
+        assert_eq!(counts, HashMap::from([("listen", 1), ("established", 1)]));
+    }
 }
@@ -0,0 +1,194 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Reporters periodically pushing metrics to external systems.
+use crate::logging::GaugeReporter;
+use crate::metrics::reporter::otlp::OtlpReporter;
+use crate::metrics::reporter::statsd::StatsdReporter;
+use crate::metrics::{gauge_value, GAUGE_TIMEOUT};
+use conjure_error::Error;
+use futures_util::StreamExt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task;
+use tokio::time::{self, Instant, MissedTickBehavior};
+use witchcraft_log::warn;
+use witchcraft_metrics::{Metric, MetricId, MetricRegistry};
+use witchcraft_server_config::install::InstallConfig;
+
+mod otlp;
+mod statsd;
+
+const NANOS_PER_MICRO: i64 = 1_000;
+const NANOS_PER_MICRO_F64: f64 = NANOS_PER_MICRO as f64;
+const QUANTILES: [f64; 3] = [0.95, 0.99, 0.999];
+
+/// A destination for metrics.
+pub trait Reporter: Send + 'static {
+    /// The name of the reporter, used in logs and metrics.
+    const NAME: &'static str;
+
+    fn report(&mut self, snapshot: &Snapshot) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+/// The values of the registry's metrics at a point in time.
+pub struct Snapshot {
+    pub time: SystemTime,
+    pub samples: Vec<Sample>,
+}
+
+pub struct Sample {
+    pub id: MetricId,
+    pub value: Value,
+}
+
+pub enum Value {
+    /// A value which can arbitrarily increase or decrease, used for gauges and counters.
+    Gauge(f64),
+    /// A monotonically increasing count since the process started, used for meters.
+    Count(i64),
+    /// A distribution of values, used for histograms and timers. Timer values are in microseconds.
+    Summary {
+        count: i64,
+        max: f64,
+        quantiles: Vec<(f64, f64)>,
+    },
+}
+
+pub async fn init(metrics: &Arc<MetricRegistry>, install: &InstallConfig) -> Result<(), Error> {
+    let Some(config) = install.metrics().reporter() else {
+        return Ok(());
+    };
+
+    if let Some(statsd) = config.statsd() {
+        spawn(StatsdReporter::new(statsd), metrics, config.interval());
+    }
+
+    if let Some(otlp) = config.otlp() {
+        spawn(
+            OtlpReporter::new(
+                otlp,
+                install.product_name(),
+                install.product_version(),
+                metrics,
+            )?,
+            metrics,
+            config.interval(),
+        );
+    }
+
+    Ok(())
+}
+
+fn spawn<R>(reporter: R, metrics: &Arc<MetricRegistry>, interval: Duration)
+where
+    R: Reporter,
+{
+    task::spawn(report_metrics(reporter, metrics.clone(), interval));
+}
+
+async fn report_metrics<R>(mut reporter: R, metrics: Arc<MetricRegistry>, period: Duration)
+where
+    R: Reporter,
+{
+    let failures =
+        metrics.meter(MetricId::new("metrics.reporter.failures").with_tag("reporter", R::NAME));
+    let mut gauge_reporter = GaugeReporter::new(gauge_value);
+
+    let mut interval = time::interval_at(Instant::now() + period, period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        let snapshot = collect(
+            &metrics,
+            &mut gauge_reporter,
+            Duration::min(period, GAUGE_TIMEOUT),
+        )
+        .await;
+        if let Err(e) = reporter.report(&snapshot).await {
+            failures.mark(1);
+            warn!(
+                "error pushing metrics",
+                safe: {
+                    reporter: R::NAME,
+                },
+                error: e,
+            );
+        }
+    }
+}
+
+/// Collects the current values of all metrics.
+///
+/// Gauges are evaluated asynchronously. Those which don't complete within the timeout are omitted from this snapshot,
+/// and will not be evaluated again until they complete.
+async fn collect(
+    metrics: &MetricRegistry,
+    gauge_reporter: &mut GaugeReporter<(MetricId, Option<f64>)>,
+    gauge_timeout: Duration,
+) -> Snapshot {
+    let time = SystemTime::now();
+    let mut samples = vec![];
+
+    for (id, metric) in &metrics.metrics() {
+        let value = match metric {
+            Metric::Counter(m) => Value::Gauge(m.count() as f64),
+            Metric::Meter(m) => Value::Count(m.count()),
+            Metric::Gauge(m) => {
+                gauge_reporter.insert(id, m);
+                continue;
+            }
+            Metric::Histogram(m) => {
+                let snapshot = m.snapshot();
+                Value::Summary {
+                    count: m.count() as i64,
+                    max: snapshot.max() as f64,
+                    quantiles: QUANTILES.iter().map(|&q| (q, snapshot.value(q))).collect(),
+                }
+            }
+            Metric::Timer(m) => {
+                let snapshot = m.snapshot();
+                Value::Summary {
+                    count: m.count(),
+                    max: (snapshot.max() / NANOS_PER_MICRO) as f64,
+                    quantiles: QUANTILES
+                        .iter()
+                        .map(|&q| (q, snapshot.value(q) / NANOS_PER_MICRO_F64))
+                        .collect(),
+                }
+            }
+        };
+
+        samples.push(Sample {
+            id: id.clone(),
+            value,
+        });
+    }
+
+    let _ = time::timeout(gauge_timeout, async {
+        while let Some(result) = gauge_reporter.next().await {
+            if let Ok((id, Some(value))) = result {
+                samples.push(Sample {
+                    id,
+                    value: Value::Gauge(value),
+                });
+            }
+        }
+    })
+    .await;
+
+    Snapshot { time, samples }
+}
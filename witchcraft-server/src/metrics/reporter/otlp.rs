@@ -0,0 +1,294 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! A reporter sending metrics to an OpenTelemetry collector using the JSON encoding of OTLP/HTTP.
+use crate::metrics::reporter::{Reporter, Snapshot, Value};
+use crate::otlp::{self, attribute, unix_nanos};
+use conjure_error::Error;
+use conjure_runtime::Client;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+use witchcraft_metrics::{MetricId, MetricRegistry};
+use witchcraft_server_config::install::OtlpReporterConfig;
+
+const PATH: &str = "/v1/metrics";
+
+// https://opentelemetry.io/docs/specs/otel/metrics/data-model/#sums
+const AGGREGATION_TEMPORALITY_CUMULATIVE: i32 = 2;
+
+/// A reporter using OTLP.
+///
+/// Metric tags are mapped to data point attributes. Counters and gauges are reported as gauges, meters as cumulative
+/// monotonic sums, and histograms and timers as summaries, with the maximum value reported as the 1.0 quantile.
+pub struct OtlpReporter {
+    client: Client,
+    resource: serde_json::Value,
+    start: String,
+}
+
+impl OtlpReporter {
+    pub fn new(
+        config: &OtlpReporterConfig,
+        product_name: &str,
+        product_version: &str,
+        metrics: &Arc<MetricRegistry>,
+    ) -> Result<Self, Error> {
+        Ok(OtlpReporter {
+            client: otlp::client(config.collector(), product_name, product_version, metrics)?,
+            resource: otlp::resource(product_name, product_version),
+            start: unix_nanos(SystemTime::now()),
+        })
+    }
+
+    fn metrics(&self, snapshot: &Snapshot) -> Vec<serde_json::Value> {
+        let time = unix_nanos(snapshot.time);
+
+        // OTLP groups data points of the same metric together
+        let mut metrics = BTreeMap::new();
+        for sample in &snapshot.samples {
+            metrics
+                .entry(sample.id.name())
+                .or_insert_with(Vec::new)
+                .push(sample);
+        }
+
+        metrics
+            .into_iter()
+            .map(|(name, samples)| {
+                let data_points = |f: &dyn Fn(&Value) -> Option<serde_json::Value>| {
+                    samples
+                        .iter()
+                        .filter_map(|s| {
+                            let mut data_point = f(&s.value)?;
+                            data_point["attributes"] = attributes(&s.id);
+                            data_point["timeUnixNano"] = json!(time);
+                            Some(data_point)
+                        })
+                        .collect::<Vec<_>>()
+                };
+
+                // the same name may have been used for metrics of different types, so we use the type of the first
+                match &samples[0].value {
+                    Value::Gauge(_) => json!({
+                        "name": name,
+                        "gauge": {
+                            "dataPoints": data_points(&|v| match v {
+                                Value::Gauge(value) => Some(json!({"asDouble": value})),
+                                _ => None,
+                            }),
+                        },
+                    }),
+                    Value::Count(_) => json!({
+                        "name": name,
+                        "sum": {
+                            "aggregationTemporality": AGGREGATION_TEMPORALITY_CUMULATIVE,
+                            "isMonotonic": true,
+                            "dataPoints": data_points(&|v| match v {
+                                Value::Count(count) => Some(json!({
+                                    "startTimeUnixNano": self.start,
+                                    "asInt": count.to_string(),
+                                })),
+                                _ => None,
+                            }),
+                        },
+                    }),
+                    Value::Summary { .. } => json!({
+                        "name": name,
+                        "summary": {
+                            "dataPoints": data_points(&|v| match v {
+                                Value::Summary {
+                                    count,
+                                    max,
+                                    quantiles,
+                                } => Some(json!({
+                                    "startTimeUnixNano": self.start,
+                                    "count": count.to_string(),
+                                    "quantileValues": quantiles
+                                        .iter()
+                                        .chain([(1., *max)].iter())
+                                        .map(|(q, v)| json!({"quantile": q, "value": v}))
+                                        .collect::<Vec<_>>(),
+                                })),
+                                _ => None,
+                            }),
+                        },
+                    }),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Reporter for OtlpReporter {
+    const NAME: &'static str = "otlp";
+
+    async fn report(&mut self, snapshot: &Snapshot) -> Result<(), Error> {
+        let body = json!({
+            "resourceMetrics": [{
+                "resource": self.resource,
+                "scopeMetrics": [{
+                    "scope": otlp::scope(),
+                    "metrics": self.metrics(snapshot),
+                }],
+            }],
+        });
+
+        otlp::send(&self.client, "OtlpMetricsService", PATH, &body).await
+    }
+}
+
+fn attributes(id: &MetricId) -> serde_json::Value {
+    id.tags().iter().map(|(k, v)| attribute(k, v)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metrics::reporter::Sample;
+    use bytes::Bytes;
+    use http::{Request, Response};
+    use http_body_util::{BodyExt, Full};
+    use hyper::body::Incoming;
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use hyper_util::rt::TokioIo;
+    use std::convert::Infallible;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio::task;
+
+    #[tokio::test]
+    async fn report() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        task::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let service = service_fn(move |req: Request<Incoming>| {
+                let tx = tx.clone();
+                async move {
+                    let path = req.uri().path().to_string();
+                    let body = req.into_body().collect().await.unwrap().to_bytes();
+                    tx.send((
+                        path,
+                        serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+                    ))
+                    .unwrap();
+                    Ok::<_, Infallible>(Response::new(Full::new(Bytes::from("{}"))))
+                }
+            });
+            let _ = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+        });
+
+        let config = OtlpReporterConfig::builder()
+            .collector(
+                serde_json::from_value(json!({"uris": [format!("http://127.0.0.1:{port}")]}))
+                    .unwrap(),
+            )
+            .build();
+        let mut reporter = OtlpReporter::new(
+            &config,
+            "my-service",
+            "1.0.0",
+            &Arc::new(MetricRegistry::new()),
+        )
+        .unwrap();
+        reporter.start = "0".to_string();
+
+        let snapshot = Snapshot {
+            time: SystemTime::UNIX_EPOCH + Duration::from_secs(1),
+            samples: vec![
+                Sample {
+                    id: MetricId::new("server.requests").with_tag("endpoint", "foo"),
+                    value: Value::Count(5),
+                },
+                Sample {
+                    id: MetricId::new("server.requests").with_tag("endpoint", "bar"),
+                    value: Value::Count(3),
+                },
+                Sample {
+                    id: MetricId::new("server.latency"),
+                    value: Value::Summary {
+                        count: 2,
+                        max: 10.,
+                        quantiles: vec![(0.95, 9.)],
+                    },
+                },
+                Sample {
+                    id: MetricId::new("process.uptime"),
+                    value: Value::Gauge(1.5),
+                },
+            ],
+        };
+        reporter.report(&snapshot).await.unwrap();
+
+        let (path, body) = rx.recv().await.unwrap();
+        assert_eq!(path, "/v1/metrics");
+        assert_eq!(
+            body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"],
+            json!([
+                {
+                    "name": "process.uptime",
+                    "gauge": {
+                        "dataPoints": [{
+                            "attributes": [],
+                            "timeUnixNano": "1000000000",
+                            "asDouble": 1.5,
+                        }],
+                    },
+                },
+                {
+                    "name": "server.latency",
+                    "summary": {
+                        "dataPoints": [{
+                            "attributes": [],
+                            "timeUnixNano": "1000000000",
+                            "startTimeUnixNano": "0",
+                            "count": "2",
+                            "quantileValues": [
+                                {"quantile": 0.95, "value": 9.},
+                                {"quantile": 1., "value": 10.},
+                            ],
+                        }],
+                    },
+                },
+                {
+                    "name": "server.requests",
+                    "sum": {
+                        "aggregationTemporality": AGGREGATION_TEMPORALITY_CUMULATIVE,
+                        "isMonotonic": true,
+                        "dataPoints": [
+                            {
+                                "attributes": [{"key": "endpoint", "value": {"stringValue": "foo"}}],
+                                "timeUnixNano": "1000000000",
+                                "startTimeUnixNano": "0",
+                                "asInt": "5",
+                            },
+                            {
+                                "attributes": [{"key": "endpoint", "value": {"stringValue": "bar"}}],
+                                "timeUnixNano": "1000000000",
+                                "startTimeUnixNano": "0",
+                                "asInt": "3",
+                            },
+                        ],
+                    },
+                },
+            ]),
+        );
+    }
+}
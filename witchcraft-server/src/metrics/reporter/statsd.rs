@@ -0,0 +1,258 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! A reporter sending metrics to a DogStatsD agent over UDP.
+use crate::metrics::reporter::{Reporter, Snapshot, Value};
+use conjure_error::Error;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::net::{self, UdpSocket};
+use witchcraft_metrics::MetricId;
+use witchcraft_server_config::install::StatsdReporterConfig;
+
+/// A reporter using the [DogStatsD](https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/) protocol.
+///
+/// Metric tags are mapped to DogStatsD `key:value` tags. Meters and the counts of histograms and timers are reported
+/// as counters of the change since the previous report, and all other values as gauges.
+pub struct StatsdReporter {
+    address: String,
+    prefix: Option<String>,
+    max_packet_size: usize,
+    socket: Option<UdpSocket>,
+    counts: HashMap<MetricId, i64>,
+}
+
+impl StatsdReporter {
+    pub fn new(config: &StatsdReporterConfig) -> Self {
+        StatsdReporter {
+            address: config.address().to_string(),
+            prefix: config.prefix().map(|s| s.to_string()),
+            max_packet_size: config.max_packet_size(),
+            socket: None,
+            counts: HashMap::new(),
+        }
+    }
+
+    fn lines(&mut self, snapshot: &Snapshot) -> Vec<String> {
+        let mut lines = vec![];
+
+        for sample in &snapshot.samples {
+            let name = self.name(sample.id.name());
+            let tags = tags(&sample.id);
+
+            match &sample.value {
+                Value::Gauge(value) => gauge(&mut lines, &name, &tags, *value),
+                Value::Count(count) => {
+                    let delta = self.delta(&sample.id, *count);
+                    lines.push(format!("{name}:{delta}|c{tags}"));
+                }
+                Value::Summary {
+                    count,
+                    max,
+                    quantiles,
+                } => {
+                    let delta = self.delta(&sample.id, *count);
+                    lines.push(format!("{name}.count:{delta}|c{tags}"));
+                    gauge(&mut lines, &format!("{name}.max"), &tags, *max);
+                    for (quantile, value) in quantiles {
+                        let quantile = quantile.to_string().replace("0.", "p");
+                        gauge(&mut lines, &format!("{name}.{quantile}"), &tags, *value);
+                    }
+                }
+            }
+        }
+
+        lines
+    }
+
+    fn name(&self, name: &str) -> String {
+        let name = name
+            .chars()
+            .map(|c| match c {
+                ':' | '|' | '@' | '\n' => '_',
+                c => c,
+            })
+            .collect::<String>();
+
+        match &self.prefix {
+            Some(prefix) => format!("{prefix}.{name}"),
+            None => name,
+        }
+    }
+
+    fn delta(&mut self, id: &MetricId, count: i64) -> i64 {
+        let previous = self.counts.insert(id.clone(), count).unwrap_or(0);
+        count - previous
+    }
+}
+
+impl Reporter for StatsdReporter {
+    const NAME: &'static str = "statsd";
+
+    async fn report(&mut self, snapshot: &Snapshot) -> Result<(), Error> {
+        let lines = self.lines(snapshot);
+
+        // The socket is dropped on error so that the address is resolved again on the next report in case the agent
+        // moved.
+        let socket = match self.socket.take() {
+            Some(socket) => socket,
+            None => connect(&self.address).await?,
+        };
+
+        for packet in packets(&lines, self.max_packet_size) {
+            socket
+                .send(packet.as_bytes())
+                .await
+                .map_err(Error::internal_safe)?;
+        }
+
+        self.socket = Some(socket);
+        Ok(())
+    }
+}
+
+// The address is resolved lazily so that an agent that isn't yet reachable doesn't prevent the server from starting.
+async fn connect(address: &str) -> Result<UdpSocket, Error> {
+    let addr = net::lookup_host(address)
+        .await
+        .map_err(Error::internal_safe)?
+        .next()
+        .ok_or_else(|| Error::internal_safe("statsd address did not resolve"))?;
+    let local = match addr {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    };
+    let socket = UdpSocket::bind(local).await.map_err(Error::internal_safe)?;
+    socket.connect(addr).await.map_err(Error::internal_safe)?;
+
+    Ok(socket)
+}
+
+fn gauge(lines: &mut Vec<String>, name: &str, tags: &str, value: f64) {
+    // DogStatsD has no representation of non-finite values
+    if value.is_finite() {
+        lines.push(format!("{name}:{value}|g{tags}"));
+    }
+}
+
+fn tags(id: &MetricId) -> String {
+    let mut out = String::new();
+    for (i, (key, value)) in id.tags().iter().enumerate() {
+        out.push_str(if i == 0 { "|#" } else { "," });
+        let _ = write!(
+            out,
+            "{}:{}",
+            sanitize_tag(key).replace(':', "_"),
+            sanitize_tag(value)
+        );
+    }
+    out
+}
+
+fn sanitize_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| match c {
+            ',' | '|' | '#' | '\n' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Combines newline-separated lines into packets no larger than the max size, unless a single line exceeds it.
+fn packets(lines: &[String], max_packet_size: usize) -> Vec<String> {
+    let mut packets = vec![];
+    let mut packet = String::new();
+
+    for line in lines {
+        if !packet.is_empty() && packet.len() + 1 + line.len() > max_packet_size {
+            packets.push(packet);
+            packet = String::new();
+        }
+        if !packet.is_empty() {
+            packet.push('\n');
+        }
+        packet.push_str(line);
+    }
+
+    if !packet.is_empty() {
+        packets.push(packet);
+    }
+
+    packets
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metrics::reporter::Sample;
+    use std::time::SystemTime;
+
+    #[tokio::test]
+    async fn report() {
+        let agent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        let config = StatsdReporterConfig::builder()
+            .address(agent.local_addr().unwrap().to_string())
+            .prefix("my-service".to_string())
+            .max_packet_size(80)
+            .build()
+            .unwrap();
+        let mut reporter = StatsdReporter::new(&config);
+
+        let mut snapshot = Snapshot {
+            time: SystemTime::now(),
+            samples: vec![
+                Sample {
+                    id: MetricId::new("server.requests").with_tag("endpoint", "a,b"),
+                    value: Value::Count(5),
+                },
+                Sample {
+                    id: MetricId::new("server.latency"),
+                    value: Value::Summary {
+                        count: 2,
+                        max: 10.,
+                        quantiles: vec![(0.95, 9.), (0.999, 10.)],
+                    },
+                },
+                Sample {
+                    id: MetricId::new("process.uptime"),
+                    value: Value::Gauge(f64::NAN),
+                },
+            ],
+        };
+        reporter.report(&snapshot).await.unwrap();
+
+        let mut buf = [0; 1024];
+        let mut packets = vec![];
+        for _ in 0..2 {
+            let len = agent.recv(&mut buf).await.unwrap();
+            packets.push(String::from_utf8(buf[..len].to_vec()).unwrap());
+        }
+        assert_eq!(
+            packets,
+            [
+                "my-service.server.requests:5|c|#endpoint:a_b\nmy-service.server.latency.count:2|c",
+                "my-service.server.latency.max:10|g\nmy-service.server.latency.p95:9|g",
+            ],
+        );
+        let len = agent.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"my-service.server.latency.p999:10|g");
+
+        snapshot.samples.truncate(1);
+        snapshot.samples[0].value = Value::Count(7);
+        reporter.report(&snapshot).await.unwrap();
+        let len = agent.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"my-service.server.requests:2|c|#endpoint:a_b");
+    }
+}
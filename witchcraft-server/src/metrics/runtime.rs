@@ -0,0 +1,282 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Metrics describing the Tokio runtime.
+//!
+//! Some of Tokio's runtime metrics are unstable, and are only registered when the server is built with
+//! `RUSTFLAGS="--cfg tokio_unstable"`.
+#[cfg(target_has_atomic = "64")]
+use parking_lot::Mutex;
+#[cfg(target_has_atomic = "64")]
+use std::sync::Arc;
+#[cfg(target_has_atomic = "64")]
+use std::time::{Duration, Instant};
+use tokio::runtime::{Handle, RuntimeMetrics};
+#[cfg(target_has_atomic = "64")]
+use tokio::time;
+use witchcraft_metrics::{MetricId, MetricRegistry};
+
+// The interval over which the rate-based metrics are computed.
+#[cfg(target_has_atomic = "64")]
+const RATE_INTERVAL: Duration = Duration::from_secs(30);
+
+pub fn register_metrics(metrics: &MetricRegistry, name: &'static str, handle: &Handle) {
+    let runtime = handle.metrics();
+    let id = |metric: &'static str| MetricId::new(metric).with_tag("runtime", name);
+
+    metrics.gauge(id("tokio.workers"), {
+        let runtime = runtime.clone();
+        move || runtime.num_workers()
+    });
+    metrics.gauge(id("tokio.alive-tasks"), {
+        let runtime = runtime.clone();
+        move || runtime.num_alive_tasks()
+    });
+    metrics.gauge(id("tokio.global-queue-depth"), {
+        let runtime = runtime.clone();
+        move || runtime.global_queue_depth()
+    });
+
+    // Rates are computed by a single background task so every reader of the gauges sees the same values.
+    #[cfg(target_has_atomic = "64")]
+    let rates = {
+        let rates = Arc::new(Mutex::new(Rates::default()));
+        handle.spawn(update_rates(runtime.clone(), rates.clone()));
+        rates
+    };
+    #[cfg(target_has_atomic = "64")]
+    metrics.gauge(id("tokio.busy-ratio"), {
+        let rates = rates.clone();
+        move || rates.lock().busy_ratio
+    });
+
+    #[cfg(tokio_unstable)]
+    {
+        metrics.gauge(id("tokio.local-queue-depth"), {
+            let runtime = runtime.clone();
+            move || {
+                (0..runtime.num_workers())
+                    .map(|worker| runtime.worker_local_queue_depth(worker))
+                    .sum::<usize>()
+            }
+        });
+        metrics.gauge(id("tokio.blocking-threads"), {
+            let runtime = runtime.clone();
+            move || runtime.num_blocking_threads()
+        });
+        metrics.gauge(id("tokio.idle-blocking-threads"), {
+            let runtime = runtime.clone();
+            move || runtime.num_idle_blocking_threads()
+        });
+        metrics.gauge(id("tokio.blocking-queue-depth"), {
+            let runtime = runtime.clone();
+            move || runtime.blocking_queue_depth()
+        });
+        metrics.gauge(id("tokio.spawned-tasks"), {
+            let runtime = runtime.clone();
+            move || runtime.spawned_tasks_count()
+        });
+        #[cfg(target_has_atomic = "64")]
+        if runtime.poll_time_histogram_enabled() {
+            metrics.gauge(id("tokio.poll-time.p50"), {
+                let rates = rates.clone();
+                move || rates.lock().poll_time_p50
+            });
+            metrics.gauge(id("tokio.poll-time.p99"), {
+                let rates = rates.clone();
+                move || rates.lock().poll_time_p99
+            });
+        }
+    }
+}
+
+/// Periodically recomputes the runtime's rates over the last interval.
+#[cfg(target_has_atomic = "64")]
+async fn update_rates(runtime: RuntimeMetrics, rates: Arc<Mutex<Rates>>) {
+    let mut last = Totals::new(&runtime);
+    loop {
+        time::sleep(RATE_INTERVAL).await;
+
+        let totals = Totals::new(&runtime);
+        *rates.lock() = Rates::new(&runtime, &last, &totals);
+        last = totals;
+    }
+}
+
+/// The runtime's cumulative counters at a point in time.
+#[cfg(target_has_atomic = "64")]
+struct Totals {
+    time: Instant,
+    busy: Duration,
+    #[cfg(tokio_unstable)]
+    poll_counts: Vec<u64>,
+}
+
+#[cfg(target_has_atomic = "64")]
+impl Totals {
+    fn new(runtime: &RuntimeMetrics) -> Self {
+        Totals {
+            time: Instant::now(),
+            busy: (0..runtime.num_workers())
+                .map(|worker| runtime.worker_total_busy_duration(worker))
+                .sum(),
+            #[cfg(tokio_unstable)]
+            poll_counts: if runtime.poll_time_histogram_enabled() {
+                (0..runtime.poll_time_histogram_num_buckets())
+                    .map(|bucket| {
+                        (0..runtime.num_workers())
+                            .map(|worker| runtime.poll_time_histogram_bucket_count(worker, bucket))
+                            .sum()
+                    })
+                    .collect()
+            } else {
+                vec![]
+            },
+        }
+    }
+}
+
+/// The rates of the runtime's counters over an interval.
+#[cfg(target_has_atomic = "64")]
+#[derive(Default)]
+struct Rates {
+    busy_ratio: f64,
+    #[cfg(tokio_unstable)]
+    poll_time_p50: u64,
+    #[cfg(tokio_unstable)]
+    poll_time_p99: u64,
+}
+
+#[cfg(target_has_atomic = "64")]
+impl Rates {
+    fn new(runtime: &RuntimeMetrics, old: &Totals, new: &Totals) -> Self {
+        #[cfg(tokio_unstable)]
+        let poll_time = |quantile| {
+            let deltas = new
+                .poll_counts
+                .iter()
+                .zip(&old.poll_counts)
+                .map(|(new, old)| new.saturating_sub(*old))
+                .collect::<Vec<_>>();
+            quantile_bucket(&deltas, quantile).map_or(0, |bucket| {
+                runtime
+                    .poll_time_histogram_bucket_range(bucket)
+                    .start
+                    .as_micros() as u64
+            })
+        };
+
+        Rates {
+            busy_ratio: busy_ratio(
+                new.time - old.time,
+                new.busy.saturating_sub(old.busy),
+                runtime.num_workers(),
+            ),
+            #[cfg(tokio_unstable)]
+            poll_time_p50: poll_time(0.5),
+            #[cfg(tokio_unstable)]
+            poll_time_p99: poll_time(0.99),
+        }
+    }
+}
+
+/// Returns the fraction of an interval that a runtime's workers were busy.
+#[cfg(target_has_atomic = "64")]
+fn busy_ratio(elapsed: Duration, busy: Duration, workers: usize) -> f64 {
+    if elapsed.is_zero() || workers == 0 {
+        return 0.;
+    }
+
+    f64::min(
+        busy.as_secs_f64() / (elapsed.as_secs_f64() * workers as f64),
+        1.,
+    )
+}
+
+/// Returns the index of the histogram bucket containing a quantile of the counts, if any were recorded.
+///
+/// The metric reports the lower bound of that bucket as an estimate of the quantile.
+#[cfg(any(test, tokio_unstable))]
+fn quantile_bucket(counts: &[u64], quantile: f64) -> Option<usize> {
+    let total = counts.iter().sum::<u64>();
+    let target = (total as f64 * quantile).ceil() as u64;
+    let mut seen = 0;
+    for (bucket, count) in counts.iter().enumerate() {
+        seen += count;
+        if *count > 0 && seen >= target {
+            return Some(bucket);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use witchcraft_metrics::Metric;
+
+    #[test]
+    fn register() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_time()
+            .build()
+            .unwrap();
+        let registry = MetricRegistry::new();
+        register_metrics(&registry, "server", runtime.handle());
+
+        let value = |name: &'static str| {
+            let id = MetricId::new(name).with_tag("runtime", "server");
+            for (metric_id, metric) in &registry.metrics() {
+                if let (true, Metric::Gauge(gauge)) = (*metric_id == id, metric) {
+                    return serde_json::to_value(gauge.value()).unwrap();
+                }
+            }
+            panic!("missing gauge {name}");
+        };
+
+        assert_eq!(value("tokio.workers"), 2);
+        #[cfg(target_has_atomic = "64")]
+        {
+            // the rate update task may not have been picked up by a worker yet
+            assert_eq!(value("tokio.alive-tasks"), 1);
+            assert!(value("tokio.global-queue-depth").as_u64().unwrap() <= 1);
+            assert_eq!(value("tokio.busy-ratio"), 0.);
+        }
+        #[cfg(not(target_has_atomic = "64"))]
+        assert_eq!(value("tokio.global-queue-depth"), 0);
+    }
+
+    #[test]
+    #[cfg(target_has_atomic = "64")]
+    fn busy_ratio() {
+        let ratio = super::busy_ratio(Duration::from_secs(10), Duration::from_secs(5), 2);
+        assert_eq!(ratio, 0.25);
+
+        let ratio = super::busy_ratio(Duration::from_secs(10), Duration::from_secs(30), 2);
+        assert_eq!(ratio, 1.);
+
+        let ratio = super::busy_ratio(Duration::ZERO, Duration::from_secs(5), 2);
+        assert_eq!(ratio, 0.);
+    }
+
+    #[test]
+    fn quantile_bucket() {
+        let counts = [0, 50, 0, 45, 5];
+        assert_eq!(super::quantile_bucket(&counts, 0.5), Some(1));
+        assert_eq!(super::quantile_bucket(&counts, 0.9), Some(3));
+        assert_eq!(super::quantile_bucket(&counts, 0.99), Some(4));
+        assert_eq!(super::quantile_bucket(&[0, 0, 0], 0.5), None);
+    }
+}
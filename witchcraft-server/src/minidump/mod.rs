@@ -27,7 +27,9 @@ use std::{env, fs, io, mem, thread};
 use witchcraft_log::{debug, error};
 
 pub mod log;
-mod symbol_provider;
+#[cfg(target_os = "linux")]
+pub mod modules;
+pub mod symbol_provider;
 
 const SOCKET_ADDR: &str = "var/data/tmp/minidump.sock";
 
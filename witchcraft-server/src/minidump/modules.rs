@@ -0,0 +1,208 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use minidump::Module;
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::os::raw::{c_int, c_void};
+use std::{env, slice};
+use symbolic::common::{CodeId, DebugId};
+
+const NT_GNU_BUILD_ID: u32 = 3;
+
+/// The modules loaded into the current process.
+pub struct LoadedModules {
+    modules: Vec<LoadedModule>,
+}
+
+impl LoadedModules {
+    pub fn new() -> Self {
+        let mut modules = vec![];
+        unsafe {
+            libc::dl_iterate_phdr(
+                Some(add_module),
+                (&mut modules as *mut Vec<LoadedModule>).cast(),
+            );
+        }
+
+        LoadedModules { modules }
+    }
+
+    /// Returns the module containing the specified address, if any.
+    pub fn find(&self, address: u64) -> Option<&LoadedModule> {
+        self.modules
+            .iter()
+            .find(|m| m.start <= address && address < m.end)
+    }
+}
+
+unsafe extern "C" fn add_module(
+    info: *mut libc::dl_phdr_info,
+    _: libc::size_t,
+    data: *mut c_void,
+) -> c_int {
+    let modules = &mut *data.cast::<Vec<LoadedModule>>();
+    let info = &*info;
+
+    // The main executable has an empty name, and pseudo-modules like the vDSO don't have a path.
+    let name = CStr::from_ptr(info.dlpi_name).to_string_lossy();
+    let path = if name.is_empty() {
+        match env::current_exe() {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(_) => return 0,
+        }
+    } else if name.starts_with('/') {
+        name.into_owned()
+    } else {
+        return 0;
+    };
+
+    let base = info.dlpi_addr;
+    let headers = slice::from_raw_parts(info.dlpi_phdr, usize::from(info.dlpi_phnum));
+
+    let mut start = u64::MAX;
+    let mut end = 0;
+    let mut code_id = None;
+    for header in headers {
+        match header.p_type {
+            libc::PT_LOAD => {
+                start = u64::min(start, base + header.p_vaddr);
+                end = u64::max(end, base + header.p_vaddr + header.p_memsz);
+            }
+            libc::PT_NOTE if code_id.is_none() => {
+                let notes = slice::from_raw_parts(
+                    (base + header.p_vaddr) as *const u8,
+                    header.p_memsz as usize,
+                );
+                code_id = build_id(notes).map(CodeId::from_binary);
+            }
+            _ => {}
+        }
+    }
+
+    if start < end {
+        modules.push(LoadedModule {
+            path,
+            base,
+            start,
+            end,
+            code_id,
+        });
+    }
+
+    0
+}
+
+// See the "Note Section" part of the ELF specification for the format.
+fn build_id(mut notes: &[u8]) -> Option<&[u8]> {
+    let word = |b: &[u8], i: usize| -> Option<u32> {
+        Some(u32::from_ne_bytes(
+            b.get(i * 4..i * 4 + 4)?.try_into().unwrap(),
+        ))
+    };
+    let align = |n: u32| (n as usize + 3) & !3;
+
+    loop {
+        let name_size = word(notes, 0)?;
+        let desc_size = word(notes, 1)?;
+        let type_ = word(notes, 2)?;
+
+        let name_start = 12;
+        let desc_start = name_start + align(name_size);
+        let next = desc_start + align(desc_size);
+
+        let name = notes.get(name_start..name_start + name_size as usize)?;
+        let desc = notes.get(desc_start..desc_start + desc_size as usize)?;
+        if type_ == NT_GNU_BUILD_ID && name == b"GNU\0" && !desc.is_empty() {
+            return Some(desc);
+        }
+
+        notes = notes.get(next..)?;
+    }
+}
+
+/// A module loaded into the current process.
+pub struct LoadedModule {
+    path: String,
+    base: u64,
+    start: u64,
+    end: u64,
+    code_id: Option<CodeId>,
+}
+
+impl Module for LoadedModule {
+    // Addresses are relative to the load bias of the module rather than its first mapping so that they line up with
+    // the virtual addresses in the object file.
+    fn base_address(&self) -> u64 {
+        self.base
+    }
+
+    fn size(&self) -> u64 {
+        self.end - self.base
+    }
+
+    fn code_file(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.path)
+    }
+
+    fn code_identifier(&self) -> Option<CodeId> {
+        self.code_id.clone()
+    }
+
+    fn debug_file(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    fn debug_identifier(&self) -> Option<DebugId> {
+        None
+    }
+
+    fn version(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn find_current_executable() {
+        let modules = LoadedModules::new();
+
+        let module = modules
+            .find(find_current_executable as *const () as u64)
+            .unwrap();
+        assert_eq!(
+            module.code_file(),
+            env::current_exe().unwrap().to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn parse_build_id() {
+        let mut notes = vec![];
+        // an unrelated note with padding
+        notes.extend_from_slice(&5u32.to_ne_bytes());
+        notes.extend_from_slice(&1u32.to_ne_bytes());
+        notes.extend_from_slice(&1u32.to_ne_bytes());
+        notes.extend_from_slice(b"fooo\0\0\0\0\x01\0\0\0");
+        notes.extend_from_slice(&4u32.to_ne_bytes());
+        notes.extend_from_slice(&4u32.to_ne_bytes());
+        notes.extend_from_slice(&NT_GNU_BUILD_ID.to_ne_bytes());
+        notes.extend_from_slice(b"GNU\0\xde\xad\xbe\xef");
+
+        assert_eq!(build_id(&notes), Some(&[0xde, 0xad, 0xbe, 0xef][..]));
+        assert_eq!(build_id(&notes[..20]), None);
+    }
+}
@@ -68,6 +68,23 @@ impl<'a> WitchcraftSymbolProvider<'a> {
             })
             .as_ref()
     }
+
+    /// Symbolizes an address within a module.
+    ///
+    /// The returned frames are ordered from outermost to innermost, and there will be more than one if the address is
+    /// within inlined code. The list is empty if the address could not be symbolized.
+    pub fn symbolize(&self, module: &(dyn Module + Sync), address: u64) -> Vec<Symbol> {
+        let mut collector = SymbolCollector {
+            address,
+            symbols: vec![],
+        };
+
+        if let Some(object) = self.load_object(module) {
+            let _ = object.fill_symbol(module, &mut collector);
+        }
+
+        collector.symbols
+    }
 }
 
 #[async_trait]
@@ -208,7 +225,10 @@ impl<'a> ObjectState<'a> {
         module: &(dyn Module + Sync),
         frame: &mut (dyn FrameSymbolizer + Send),
     ) -> Result<(), FillSymbolError> {
-        let addr = frame.get_instruction() - module.base_address();
+        let addr = frame
+            .get_instruction()
+            .checked_sub(module.base_address())
+            .ok_or(FillSymbolError {})?;
 
         if self.fill_symbol_dwarf(addr, frame).is_some() {
             return Ok(());
@@ -291,6 +311,47 @@ impl<'a> ObjectState<'a> {
     }
 }
 
+/// A function containing a symbolized address.
+pub struct Symbol {
+    pub function: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+struct SymbolCollector {
+    address: u64,
+    symbols: Vec<Symbol>,
+}
+
+impl FrameSymbolizer for SymbolCollector {
+    fn get_instruction(&self) -> u64 {
+        self.address
+    }
+
+    fn set_function(&mut self, name: &str, _base: u64, _parameter_size: u32) {
+        self.symbols.push(Symbol {
+            function: name.to_string(),
+            file: None,
+            line: None,
+        });
+    }
+
+    fn set_source_file(&mut self, file: &str, line: u32, _base: u64) {
+        if let Some(symbol) = self.symbols.first_mut() {
+            symbol.file = Some(file.to_string());
+            symbol.line = Some(line);
+        }
+    }
+
+    fn add_inline_frame(&mut self, name: &str, file: Option<&str>, line: Option<u32>) {
+        self.symbols.push(Symbol {
+            function: name.to_string(),
+            file: file.map(str::to_string),
+            line,
+        });
+    }
+}
+
 struct PotentialName<'a> {
     name: SymbolMapName<'a>,
     global: bool,
@@ -0,0 +1,103 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Shared support for communicating with an OpenTelemetry collector using the JSON encoding of OTLP/HTTP.
+use bytes::Bytes;
+use conjure_error::Error;
+use conjure_http::client::{AsyncClient, AsyncRequestBody, Endpoint};
+use conjure_runtime::config::ServiceConfig;
+use conjure_runtime::{Agent, Client, UserAgent};
+use http::header::CONTENT_TYPE;
+use http::{HeaderValue, Method, Request, Uri};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::SystemTime;
+use witchcraft_metrics::MetricRegistry;
+use zipkin::SamplingFlags;
+
+/// Creates a client communicating with the collector.
+pub fn client(
+    config: &ServiceConfig,
+    product_name: &str,
+    product_version: &str,
+    metrics: &Arc<MetricRegistry>,
+) -> Result<Client, Error> {
+    conjure_runtime::Builder::new()
+        .service("otlp-collector")
+        .user_agent(UserAgent::new(Agent::new(product_name, product_version)))
+        .from_config(config)
+        .metrics(metrics.clone())
+        .build()
+}
+
+/// Returns the resource describing this service.
+pub fn resource(product_name: &str, product_version: &str) -> Value {
+    json!({
+        "attributes": [
+            attribute("service.name", product_name),
+            attribute("service.version", product_version),
+        ],
+    })
+}
+
+/// Returns the instrumentation scope of data produced by the server.
+pub fn scope() -> Value {
+    json!({
+        "name": "witchcraft-server",
+        "version": env!("CARGO_PKG_VERSION"),
+    })
+}
+
+pub fn attribute(key: &str, value: &str) -> Value {
+    json!({
+        "key": key,
+        "value": {
+            "stringValue": value,
+        },
+    })
+}
+
+// 64 bit integers are encoded as strings in OTLP's JSON encoding
+pub fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+/// Sends an export request to the collector.
+pub async fn send(
+    client: &Client,
+    service: &'static str,
+    path: &'static str,
+    body: &Value,
+) -> Result<(), Error> {
+    let body = serde_json::to_vec(body).map_err(Error::internal_safe)?;
+
+    let mut request = Request::new(AsyncRequestBody::Fixed(Bytes::from(body)));
+    *request.method_mut() = Method::POST;
+    *request.uri_mut() = Uri::from_static(path);
+    request
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    request
+        .extensions_mut()
+        .insert(Endpoint::new(service, None, "export", path));
+
+    // The client creates spans for its requests, so we make sure they aren't sampled to avoid an infinite feedback loop
+    // of exports.
+    let span = zipkin::new_trace_from(SamplingFlags::builder().sampled(false).build()).detach();
+    span.bind(client.send(request)).await?;
+
+    Ok(())
+}
@@ -13,7 +13,9 @@
 // limitations under the License.
 use crate::logging::Loggers;
 use crate::service::accept::AcceptService;
+use crate::service::active_requests::{ActiveRequestBody, ActiveRequestsLayer};
 use crate::service::audit_log::AuditLogLayer;
+use crate::service::boxed::BoxedLayer;
 use crate::service::cancellation::CancellationLayer;
 use crate::service::catch_unwind::CatchUnwindLayer;
 use crate::service::client_certificate::ClientCertificateLayer;
@@ -54,7 +56,7 @@ use std::sync::Arc;
 use tokio::task;
 use witchcraft_log::debug;
 
-pub type RawBody = RequestLogRequestBody<SpannedBody<Incoming>>;
+pub type RawBody = RequestLogRequestBody<ActiveRequestBody<SpannedBody<Incoming>>>;
 
 #[derive(Copy, Clone)]
 pub enum Listener {
@@ -77,25 +79,31 @@ pub(crate) async fn start(
     listener: Listener,
     port: u16,
 ) -> Result<(), Error> {
+    witchcraft
+        .endpoint_inventory
+        .register(listener, &witchcraft.endpoints);
+
     // This service handles individual HTTP requests, each running concurrently.
     let request_service = ServiceBuilder::new()
         .layer(RoutingLayer::new(mem::take(&mut witchcraft.endpoints)))
         .layer(RequestIdLayer)
-        .layer(TracePropagationLayer)
+        .layer(TracePropagationLayer::new(&witchcraft.install_config))
         .layer(SpansLayer)
         .layer(UnverifiedJwtLayer)
+        .layer(ActiveRequestsLayer::new(&witchcraft.active_requests))
         .layer(MdcLayer)
         .layer(WitchcraftMdcLayer)
         .layer(RequestLogLayer::new(loggers.request_logger.clone()))
         .layer(AuditLogLayer::new(loggers.audit_logger.clone()))
         .layer(CancellationLayer)
+        .layer(BoxedLayer)
         .layer(GzipLayer::new(&witchcraft.install_config))
         .layer(DeprecationHeaderLayer)
         .layer(KeepAliveHeaderLayer::new(&witchcraft.install_config))
         .layer(ServerHeaderLayer::new(&witchcraft.install_config)?)
         .layer(NoCachingLayer)
         .layer(WebSecurityLayer)
-        .layer(TraceIdHeaderLayer)
+        .layer(TraceIdHeaderLayer::new(&witchcraft.install_config))
         .layer(ServerMetricsLayer::new(&witchcraft.metrics, listener))
         .layer(EndpointMetricsLayer)
         .layer(EndpointHealthLayer)
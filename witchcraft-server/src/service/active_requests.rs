@@ -0,0 +1,443 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::blocking::Cancellation;
+use crate::endpoint::WitchcraftEndpoint;
+use crate::extensions::PeerAddr;
+use crate::service::request_id::RequestId;
+use crate::service::routing::Route;
+use crate::service::unverified_jwt::UnverifiedJwt;
+use crate::service::{Layer, Service};
+use bytes::Buf;
+use conjure_object::{DateTime, Utc, Uuid};
+use futures_util::ready;
+use http::{Method, Request, Response};
+use http_body::{Body, Frame, SizeHint};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::task;
+use zipkin::TraceId;
+
+/// A registry of the requests currently being handled by the server.
+///
+/// Requests are registered and removed on every call, so the registry is split into independently locked shards to
+/// avoid contention between worker threads.
+pub struct ActiveRequests {
+    shards: Box<[Mutex<HashMap<RequestId, ActiveRequest>>]>,
+    hasher: RandomState,
+}
+
+impl ActiveRequests {
+    pub fn new() -> Self {
+        let shards = (num_cpus::get() * 4).next_power_of_two();
+
+        ActiveRequests {
+            shards: (0..shards).map(|_| Mutex::new(HashMap::new())).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Returns a snapshot of the active requests, ordered from oldest to newest.
+    pub fn snapshot(&self) -> Vec<ActiveRequest> {
+        let mut requests = vec![];
+        for shard in &*self.shards {
+            requests.extend(shard.lock().values().cloned());
+        }
+        requests.sort_by_key(|r| r.start);
+        requests
+    }
+
+    /// Cancels the active request with the specified ID.
+    ///
+    /// Requests can only be cancelled before their endpoint has produced a response.
+    pub fn cancel(&self, request_id: &RequestId) -> CancelOutcome {
+        let requests = self.shard(request_id).lock();
+        let Some(request) = requests.get(request_id) else {
+            return CancelOutcome::NotFound;
+        };
+
+        if request.responded.load(Ordering::Relaxed) {
+            return CancelOutcome::Responded;
+        }
+
+        request.cancellation.cancel();
+        CancelOutcome::Cancelled
+    }
+
+    fn shard(&self, request_id: &RequestId) -> &Mutex<HashMap<RequestId, ActiveRequest>> {
+        let hash = self.hasher.hash_one(request_id) as usize;
+        &self.shards[hash % self.shards.len()]
+    }
+
+    fn register(self: &Arc<Self>, request: ActiveRequest) -> RequestGuard {
+        let request_id = request.request_id;
+        self.shard(&request_id).lock().insert(request_id, request);
+        RequestGuard {
+            requests: self.clone(),
+            request_id,
+        }
+    }
+}
+
+/// The outcome of an attempt to cancel an active request.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CancelOutcome {
+    /// The request was cancelled.
+    Cancelled,
+    /// No request with the specified ID is active.
+    NotFound,
+    /// The request's endpoint has already produced a response, which is still being written.
+    Responded,
+}
+
+/// Information about an in-flight request.
+#[derive(Clone)]
+pub struct ActiveRequest {
+    pub request_id: RequestId,
+    pub method: Method,
+    /// The endpoint the request was routed to, if any.
+    pub endpoint: Option<Arc<dyn WitchcraftEndpoint + Sync + Send>>,
+    pub trace_id: TraceId,
+    /// The unverified user ID from the request's bearer token, if present.
+    pub user_id: Option<Uuid>,
+    pub peer: Option<SocketAddr>,
+    pub start: Instant,
+    pub start_time: DateTime<Utc>,
+    /// The ID of the Tokio task handling the request.
+    pub task_id: Option<task::Id>,
+    pub(crate) bytes_read: Arc<AtomicU64>,
+    pub(crate) bytes_written: Arc<AtomicU64>,
+    pub(crate) cancellation: Cancellation,
+    pub(crate) responded: Arc<AtomicBool>,
+}
+
+impl ActiveRequest {
+    /// Returns the path template of the request's endpoint, or `Unmatched Path` if it was not routed to one.
+    pub fn path(&self) -> &str {
+        self.endpoint
+            .as_ref()
+            .map_or("Unmatched Path", |endpoint| endpoint.template())
+    }
+
+    /// Returns the `service.endpoint` name of the request's endpoint, if it was routed to one.
+    pub fn endpoint_name(&self) -> Option<String> {
+        self.endpoint
+            .as_ref()
+            .map(|endpoint| format!("{}.{}", endpoint.service_name(), endpoint.name()))
+    }
+
+    /// Returns the number of request body bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of response body bytes written so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written.load(Ordering::Relaxed)
+    }
+}
+
+struct RequestGuard {
+    requests: Arc<ActiveRequests>,
+    request_id: RequestId,
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        self.requests
+            .shard(&self.request_id)
+            .lock()
+            .remove(&self.request_id);
+    }
+}
+
+/// A layer which tracks requests in an [`ActiveRequests`] registry until their response bodies are complete.
+///
+/// It also adds a [`Cancellation`] to the request's extensions which can be used to cancel it via the registry.
+///
+/// It must be installed after routing, request ID assignment, span creation, and unverified JWT parsing.
+pub struct ActiveRequestsLayer {
+    requests: Arc<ActiveRequests>,
+}
+
+impl ActiveRequestsLayer {
+    pub fn new(requests: &Arc<ActiveRequests>) -> Self {
+        ActiveRequestsLayer {
+            requests: requests.clone(),
+        }
+    }
+}
+
+impl<S> Layer<S> for ActiveRequestsLayer {
+    type Service = ActiveRequestsService<S>;
+
+    fn layer(self, inner: S) -> Self::Service {
+        ActiveRequestsService {
+            inner,
+            requests: self.requests,
+        }
+    }
+}
+
+pub struct ActiveRequestsService<S> {
+    inner: S,
+    requests: Arc<ActiveRequests>,
+}
+
+impl<S, B1, B2> Service<Request<B1>> for ActiveRequestsService<S>
+where
+    S: Service<Request<ActiveRequestBody<B1>>, Response = Response<B2>> + Sync,
+    B1: Send,
+{
+    type Response = Response<ActiveResponseBody<B2>>;
+
+    async fn call(&self, mut req: Request<B1>) -> Self::Response {
+        let endpoint = match req.extensions().get::<Route>() {
+            Some(Route::Resolved(endpoint)) => Some(endpoint.clone()),
+            _ => None,
+        };
+
+        let cancellation = Cancellation::new();
+        req.extensions_mut().insert(cancellation.clone());
+
+        let request = ActiveRequest {
+            request_id: *req
+                .extensions()
+                .get::<RequestId>()
+                .expect("RequestId missing from request extensions"),
+            method: req.method().clone(),
+            endpoint,
+            trace_id: zipkin::current()
+                .expect("zipkin trace not initialized")
+                .trace_id(),
+            user_id: req
+                .extensions()
+                .get::<UnverifiedJwt>()
+                .map(|jwt| jwt.unverified_user_id()),
+            peer: req.extensions().get::<PeerAddr>().map(|addr| addr.0),
+            start: Instant::now(),
+            start_time: Utc::now(),
+            task_id: task::try_id(),
+            bytes_read: Arc::new(AtomicU64::new(0)),
+            bytes_written: Arc::new(AtomicU64::new(0)),
+            cancellation,
+            responded: Arc::new(AtomicBool::new(false)),
+        };
+        let bytes_read = request.bytes_read.clone();
+        let bytes_written = request.bytes_written.clone();
+        let responded = request.responded.clone();
+        let guard = self.requests.register(request);
+
+        let response = self
+            .inner
+            .call(req.map(|inner| ActiveRequestBody {
+                inner,
+                bytes: bytes_read,
+            }))
+            .await;
+        responded.store(true, Ordering::Relaxed);
+
+        response.map(|inner| ActiveResponseBody {
+            inner,
+            bytes: bytes_written,
+            _guard: guard,
+        })
+    }
+}
+
+fn record<D>(frame: &Frame<D>, bytes: &AtomicU64)
+where
+    D: Buf,
+{
+    if let Some(chunk) = frame.data_ref() {
+        bytes.fetch_add(chunk.remaining() as u64, Ordering::Relaxed);
+    }
+}
+
+#[pin_project]
+pub struct ActiveRequestBody<B> {
+    #[pin]
+    inner: B,
+    bytes: Arc<AtomicU64>,
+}
+
+impl<B> Body for ActiveRequestBody<B>
+where
+    B: Body,
+{
+    type Data = B::Data;
+
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let value = ready!(this.inner.poll_frame(cx));
+        if let Some(Ok(frame)) = &value {
+            record(frame, this.bytes);
+        }
+
+        Poll::Ready(value)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[pin_project]
+pub struct ActiveResponseBody<B> {
+    #[pin]
+    inner: B,
+    bytes: Arc<AtomicU64>,
+    _guard: RequestGuard,
+}
+
+impl<B> Body for ActiveResponseBody<B>
+where
+    B: Body,
+{
+    type Data = B::Data;
+
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let value = ready!(this.inner.poll_frame(cx));
+        if let Some(Ok(frame)) = &value {
+            record(frame, this.bytes);
+        }
+
+        Poll::Ready(value)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::test_util::{self, service_fn};
+    use bytes::Bytes;
+    use http_body_util::{BodyExt, Full};
+
+    #[tokio::test]
+    async fn tracked_until_complete() {
+        test_util::setup_tracer();
+
+        let requests = Arc::new(ActiveRequests::new());
+        let request_id = RequestId::random();
+
+        let service = ActiveRequestsLayer::new(&requests).layer(service_fn({
+            let requests = requests.clone();
+            move |req: Request<ActiveRequestBody<Full<Bytes>>>| {
+                let snapshot = requests.snapshot();
+                async move {
+                    assert_eq!(snapshot.len(), 1);
+                    assert_eq!(snapshot[0].request_id, request_id);
+                    assert_eq!(snapshot[0].method, Method::POST);
+                    assert_eq!(snapshot[0].path(), "Unmatched Path");
+                    assert_eq!(snapshot[0].endpoint_name(), None);
+                    assert_eq!(snapshot[0].trace_id, zipkin::current().unwrap().trace_id());
+                    assert!(!req
+                        .extensions()
+                        .get::<Cancellation>()
+                        .unwrap()
+                        .is_cancelled());
+
+                    req.into_body().collect().await.unwrap();
+                    Response::new(Full::new(Bytes::from("hello world")))
+                }
+            }
+        }));
+
+        let mut req = Request::post("/")
+            .body(Full::new(Bytes::from("foo")))
+            .unwrap();
+        req.extensions_mut().insert(request_id);
+        req.extensions_mut().insert(Route::Unresolved);
+
+        let response = zipkin::new_trace().detach().bind(service.call(req)).await;
+
+        let snapshot = requests.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].bytes_read(), 3);
+        assert_eq!(snapshot[0].bytes_written(), 0);
+
+        response.into_body().collect().await.unwrap();
+        assert_eq!(snapshot[0].bytes_written(), 11);
+        assert!(requests.snapshot().is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancel() {
+        test_util::setup_tracer();
+
+        let requests = Arc::new(ActiveRequests::new());
+        let request_id = RequestId::random();
+
+        let service = ActiveRequestsLayer::new(&requests).layer(service_fn({
+            let requests = requests.clone();
+            move |req: Request<ActiveRequestBody<()>>| {
+                assert_eq!(
+                    requests.cancel(&RequestId::random()),
+                    CancelOutcome::NotFound
+                );
+                assert_eq!(requests.cancel(&request_id), CancelOutcome::Cancelled);
+                let cancelled = req
+                    .extensions()
+                    .get::<Cancellation>()
+                    .unwrap()
+                    .is_cancelled();
+                async move {
+                    assert!(cancelled);
+                    Response::new(())
+                }
+            }
+        }));
+
+        let mut req = Request::new(());
+        req.extensions_mut().insert(request_id);
+        req.extensions_mut().insert(Route::Unresolved);
+
+        let response = zipkin::new_trace().detach().bind(service.call(req)).await;
+        assert_eq!(requests.cancel(&request_id), CancelOutcome::Responded);
+
+        drop(response);
+        assert_eq!(requests.cancel(&request_id), CancelOutcome::NotFound);
+    }
+}
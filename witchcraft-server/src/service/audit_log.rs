@@ -14,7 +14,7 @@
 
 use crate::extensions::AuditLogEntry;
 use crate::logging::api::AuditLogV3;
-use crate::logging::Payload;
+use crate::logging::{self, Payload};
 use crate::service::{Layer, Service};
 use conjure_error::Error;
 use futures_channel::oneshot;
@@ -80,7 +80,7 @@ where
             let (tx, rx) = oneshot::channel();
 
             let payload = Payload {
-                value: audit_log_entry.0,
+                value: logging::redact_audit_log(audit_log_entry.0),
                 cb: Some(tx),
             };
 
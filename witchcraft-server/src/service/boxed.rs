@@ -0,0 +1,49 @@
+// Copyright 2026 Palantir Technologies, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::service::{Layer, Service};
+use std::future::Future;
+use std::pin::Pin;
+
+/// A layer which boxes the inner service's future.
+///
+/// The request service stack is deep enough that computing the layout of its fully nested future can exceed the
+/// compiler's query depth limit in crates which instantiate the server. Boxing partway down the stack splits that
+/// computation in two.
+pub struct BoxedLayer;
+
+impl<S> Layer<S> for BoxedLayer {
+    type Service = BoxedService<S>;
+
+    fn layer(self, inner: S) -> Self::Service {
+        BoxedService { inner }
+    }
+}
+
+pub struct BoxedService<S> {
+    inner: S,
+}
+
+impl<S, R> Service<R> for BoxedService<S>
+where
+    S: Service<R> + Sync,
+    R: 'static,
+{
+    type Response = S::Response;
+
+    fn call(&self, req: R) -> impl Future<Output = Self::Response> + Send {
+        let future: Pin<Box<dyn Future<Output = Self::Response> + Send + '_>> =
+            Box::pin(self.inner.call(req));
+        future
+    }
+}
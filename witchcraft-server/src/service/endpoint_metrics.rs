@@ -11,6 +11,7 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use crate::service::handler::Cancelled;
 use crate::service::routing::Route;
 use crate::service::{Layer, Service};
 use conjure_http::server::EndpointMetadata;
@@ -82,7 +83,8 @@ where
 
         let start_time = Instant::now();
         let response = self.inner.call(req).await;
-        if response.status().is_server_error() {
+        if response.status().is_server_error() && response.extensions().get::<Cancelled>().is_none()
+        {
             if let Some(metrics) = &endpoint_metrics {
                 metrics.response_error.mark(1);
             }
@@ -11,17 +11,21 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use crate::blocking::Cancellation;
+use crate::endpoint::errors;
 use crate::server::RawBody;
 use crate::service::routing::Route;
 use crate::service::Service;
 use bytes::Bytes;
+use conjure_error::Error;
 use http::header::ALLOW;
 use http::{HeaderValue, Method, Request, Response, StatusCode};
 use http_body::{Body, Frame, SizeHint};
 use http_body_util::combinators::BoxBody;
-use http_body_util::BodyExt;
+use http_body_util::{BodyExt, Full};
 use itertools::Itertools;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::{error, fmt};
 
@@ -40,7 +44,17 @@ impl Service<Request<RawBody>> for HandlerService {
             .expect("Route missing from request extensions");
 
         match route {
-            Route::Resolved(endpoint) => endpoint.handle(req).await,
+            Route::Resolved(endpoint) => {
+                let Some(cancellation) = req.extensions().get::<Cancellation>().cloned() else {
+                    return endpoint.handle(req).await;
+                };
+
+                tokio::select! {
+                    biased;
+                    response = endpoint.handle(req) => response,
+                    _ = cancellation.cancelled() => cancelled_response(),
+                }
+            }
             Route::MethodNotAllowed(methods) => {
                 let mut response = Response::new(EmptyBody.boxed());
                 *response.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
@@ -67,6 +81,24 @@ impl Service<Request<RawBody>> for HandlerService {
     }
 }
 
+/// A response extension marking responses to requests which were cancelled by an administrator.
+///
+/// These are not counted as endpoint errors.
+#[derive(Clone)]
+pub struct Cancelled;
+
+fn cancelled_response() -> Response<BoxBody<Bytes, BodyWriteAborted>> {
+    let error = Error::unavailable_safe("request cancelled by an administrator");
+    let mut response = errors::to_response(error, |o| match o {
+        Some(body) => Full::new(body).map_err(|e| match e {}).boxed(),
+        None => EmptyBody.boxed(),
+    });
+    // The cancellation is logged when it's requested, so we don't want it to be reported as a server error.
+    response.extensions_mut().remove::<Arc<Error>>();
+    response.extensions_mut().insert(Cancelled);
+    response
+}
+
 fn allow_header(methods: &[Method]) -> HeaderValue {
     let header = methods.iter().map(|m| m.to_string()).join(", ");
     HeaderValue::try_from(header).unwrap()
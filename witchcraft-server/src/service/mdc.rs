@@ -11,6 +11,7 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use crate::logging;
 use crate::service::{Layer, Service};
 use http::Response;
 use http_body::{Body, Frame};
@@ -19,8 +20,9 @@ use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use witchcraft_log::mdc::{self, Snapshot};
+use witchcraft_log::LevelFilter;
 
-/// A layer which manages the witchcraft-log MDC around the inner service.
+/// A layer which manages the witchcraft-log MDC and request log level override around the inner service.
 pub struct MdcLayer;
 
 impl<S> Layer<S> for MdcLayer {
@@ -44,13 +46,15 @@ where
 
     async fn call(&self, req: R) -> Self::Response {
         let mut snapshot = Snapshot::new();
-        let guard = with(&mut snapshot);
+        let mut level = None;
+        let guard = with(&mut snapshot, &mut level);
         let inner = self.inner.call(req);
         drop(guard);
 
         MdcFuture {
             inner: Some(inner),
             snapshot,
+            level,
         }
         .await
     }
@@ -61,13 +65,14 @@ pub struct MdcFuture<F> {
     #[pin]
     inner: Option<F>,
     snapshot: Snapshot,
+    level: Option<LevelFilter>,
 }
 
 #[pinned_drop]
 impl<F> PinnedDrop for MdcFuture<F> {
     fn drop(self: Pin<&mut Self>) {
         let mut this = self.project();
-        let _guard = with(this.snapshot);
+        let _guard = with(this.snapshot, this.level);
         this.inner.set(None);
     }
 }
@@ -80,12 +85,13 @@ where
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
-        let _guard = with(this.snapshot);
+        let _guard = with(this.snapshot, this.level);
 
         this.inner.as_pin_mut().unwrap().poll(cx).map(|r| {
             r.map(|inner| MdcBody {
                 inner,
                 snapshot: mdc::snapshot(),
+                level: logging::request_level(),
             })
         })
     }
@@ -96,6 +102,7 @@ pub struct MdcBody<B> {
     #[pin]
     inner: B,
     snapshot: Snapshot,
+    level: Option<LevelFilter>,
 }
 
 impl<B> Body for MdcBody<B>
@@ -111,7 +118,7 @@ where
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
         let this = self.project();
-        let _guard = with(this.snapshot);
+        let _guard = with(this.snapshot, this.level);
         this.inner.poll_frame(cx)
     }
 
@@ -124,18 +131,21 @@ where
     }
 }
 
-fn with(snapshot: &mut Snapshot) -> MdcGuard<'_> {
+fn with<'a>(snapshot: &'a mut Snapshot, level: &'a mut Option<LevelFilter>) -> MdcGuard<'a> {
     mdc::swap(snapshot);
-    MdcGuard { snapshot }
+    *level = logging::set_request_level(*level);
+    MdcGuard { snapshot, level }
 }
 
 struct MdcGuard<'a> {
     snapshot: &'a mut Snapshot,
+    level: &'a mut Option<LevelFilter>,
 }
 
 impl Drop for MdcGuard<'_> {
     fn drop(&mut self) {
         mdc::swap(self.snapshot);
+        *self.level = logging::set_request_level(*self.level);
     }
 }
 
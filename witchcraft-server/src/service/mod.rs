@@ -15,7 +15,9 @@ use std::future::Future;
 use std::sync::Arc;
 
 pub mod accept;
+pub mod active_requests;
 pub mod audit_log;
+pub mod boxed;
 pub mod cancellation;
 pub mod catch_unwind;
 pub mod client_certificate;
@@ -41,7 +43,7 @@ pub mod server_header;
 pub mod server_metrics;
 pub mod spans;
 #[cfg(test)]
-mod test_util;
+pub mod test_util;
 pub mod tls;
 pub mod tls_metrics;
 pub mod trace_id_header;
@@ -13,9 +13,10 @@
 // limitations under the License.
 use crate::service::{Layer, Service};
 use http::Request;
-use std::fmt;
+use std::str::FromStr;
+use std::{error, fmt};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct RequestId {
     id: [u8; 8],
 }
@@ -35,6 +36,35 @@ impl fmt::Display for RequestId {
     }
 }
 
+impl FromStr for RequestId {
+    type Err = ParseRequestIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 16 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ParseRequestIdError(()));
+        }
+
+        let mut id = [0; 8];
+        for (i, b) in id.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).unwrap();
+        }
+
+        Ok(RequestId { id })
+    }
+}
+
+/// The error returned when parsing an invalid [`RequestId`].
+#[derive(Debug)]
+pub struct ParseRequestIdError(());
+
+impl fmt::Display for ParseRequestIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid request ID")
+    }
+}
+
+impl error::Error for ParseRequestIdError {}
+
 /// A layer which adds a unique [`RequestId`] to each request's extensions.
 pub struct RequestIdLayer;
 
@@ -62,3 +92,19 @@ where
         self.inner.call(req).await
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let id = RequestId::random();
+        assert_eq!(id.to_string().parse::<RequestId>().unwrap(), id);
+
+        assert!("".parse::<RequestId>().is_err());
+        assert!("0123456789abcdeg".parse::<RequestId>().is_err());
+        assert!("+123456789abcdef".parse::<RequestId>().is_err());
+        assert!("0123456789abcdef0".parse::<RequestId>().is_err());
+    }
+}
@@ -330,24 +330,26 @@ impl Drop for State {
             .ok()
             .unwrap_or_else(SafeLong::max_value);
 
-        let request_log = RequestLogV2::builder()
-            .type_("request.2")
-            .time(Utc::now())
-            .protocol(mem::take(&mut self.protocol))
-            .path(mem::take(&mut self.path))
-            .status(self.status)
-            .request_size(request_size)
-            .response_size(response_size)
-            .duration(duration)
-            .method(mem::take(&mut self.method))
-            .uid(self.uid.take())
-            .sid(self.sid.take())
-            .token_id(self.token_id.take())
-            .org_id(self.org_id.take())
-            .trace_id(self.trace_id.take())
-            .extend_params(self.params.drain(..))
-            .extend_unsafe_params(self.unsafe_params.drain(..))
-            .build();
+        let request_log = logging::redact_request_log(
+            RequestLogV2::builder()
+                .type_("request.2")
+                .time(Utc::now())
+                .protocol(mem::take(&mut self.protocol))
+                .path(mem::take(&mut self.path))
+                .status(self.status)
+                .request_size(request_size)
+                .response_size(response_size)
+                .duration(duration)
+                .method(mem::take(&mut self.method))
+                .uid(self.uid.take())
+                .sid(self.sid.take())
+                .token_id(self.token_id.take())
+                .org_id(self.org_id.take())
+                .trace_id(self.trace_id.take())
+                .extend_params(self.params.drain(..))
+                .extend_unsafe_params(self.unsafe_params.drain(..))
+                .build(),
+        );
 
         let _ = self.appender.try_send(Payload {
             value: request_log,
@@ -23,6 +23,7 @@ use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::Write;
 use std::sync::Arc;
+use witchcraft_log::warn;
 
 // Our behavior here follows a subset of the JAX-RS spec:
 // https://jakarta.ee/specifications/restful-ws/3.0/jakarta-restful-ws-spec-3.0.html#request_matching
@@ -89,6 +90,46 @@ impl Endpoint {
             .then_with(|| self.custom_path_params.cmp(&other.custom_path_params))
             .reverse()
     }
+
+    /// Returns `true` if every path matched by `other` is also matched by this endpoint.
+    ///
+    /// This is conservative: custom parameter regexes are only compared textually, so it may miss some overlaps.
+    fn covers(&self, other: &Self) -> bool {
+        let path = self.endpoint.path();
+        let other_path = other.endpoint.path();
+
+        path.len() == other_path.len()
+            && path
+                .iter()
+                .zip(other_path)
+                .all(|(segment, other)| covers_segment(segment, other))
+    }
+}
+
+fn covers_segment(segment: &PathSegment, other: &PathSegment) -> bool {
+    match (segment, other) {
+        (PathSegment::Literal(a), PathSegment::Literal(b)) => a == b,
+        (PathSegment::Literal(_), PathSegment::Parameter { .. }) => false,
+        (PathSegment::Parameter { regex, .. }, PathSegment::Literal(b)) => {
+            let regex = regex.as_deref().unwrap_or(DEFAULT_REGEX);
+            Regex::new(&format!("^(?:{regex})$")).is_ok_and(|r| r.is_match(b))
+        }
+        (PathSegment::Parameter { regex: a, .. }, PathSegment::Parameter { regex: b, .. }) => {
+            a.as_deref().unwrap_or(DEFAULT_REGEX) == b.as_deref().unwrap_or(DEFAULT_REGEX)
+        }
+    }
+}
+
+/// Returns pairs of indices `(shadowed, by)` of endpoints which can never be routed to because a higher priority
+/// endpoint matches all of their paths.
+fn shadowed(endpoints: &[Endpoint]) -> Vec<(usize, usize)> {
+    (0..endpoints.len())
+        .filter_map(|i| {
+            (0..i)
+                .find(|&j| endpoints[j].covers(&endpoints[i]))
+                .map(|j| (i, j))
+        })
+        .collect()
 }
 
 #[derive(Clone)]
@@ -145,6 +186,21 @@ impl Routes {
     fn new(mut endpoints: Vec<Endpoint>) -> Self {
         endpoints.sort_by(Endpoint::cmp_priority);
 
+        for (shadowed, by) in shadowed(&endpoints) {
+            let shadowed = &endpoints[shadowed].endpoint;
+            let by = &endpoints[by].endpoint;
+            warn!(
+                "endpoint is shadowed by another endpoint and will never be routed to",
+                safe: {
+                    method: shadowed.method().as_str(),
+                    template: shadowed.template(),
+                    endpoint: format_args!("{}.{}", shadowed.service_name(), shadowed.name()),
+                    shadowedByTemplate: by.template(),
+                    shadowedByEndpoint: format_args!("{}.{}", by.service_name(), by.name()),
+                },
+            );
+        }
+
         Routes {
             set: RegexSet::new(endpoints.iter().map(|e| e.regex.as_str())).unwrap(),
             endpoints,
@@ -233,71 +289,22 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::health::endpoint_500s::EndpointHealth;
-    use crate::server::RawBody;
-    use crate::service::endpoint_metrics::EndpointMetrics;
-    use crate::service::handler::BodyWriteAborted;
-    use crate::service::test_util::service_fn;
-    use async_trait::async_trait;
+    use crate::service::test_util::{service_fn, TestEndpoint};
     use bytes::Bytes;
-    use conjure_http::server::EndpointMetadata;
-    use http::Response;
-    use http_body_util::combinators::BoxBody;
     use http_body_util::Empty;
 
-    struct TestEndpoint {
-        method: Method,
-        path: Vec<PathSegment>,
-        name: &'static str,
-    }
-
-    impl EndpointMetadata for TestEndpoint {
-        fn method(&self) -> Method {
-            self.method.clone()
-        }
-
-        fn path(&self) -> &[PathSegment] {
-            &self.path
-        }
-
-        fn template(&self) -> &str {
-            ""
-        }
-
-        fn service_name(&self) -> &str {
-            ""
-        }
-
-        fn name(&self) -> &str {
-            self.name
-        }
-
-        fn deprecated(&self) -> Option<&str> {
-            None
-        }
-    }
-
-    #[async_trait]
-    impl WitchcraftEndpoint for TestEndpoint {
-        fn metrics(&self) -> Option<&EndpointMetrics> {
-            None
-        }
-
-        fn health(&self) -> Option<&Arc<EndpointHealth>> {
-            None
-        }
-
-        async fn handle(&self, _: Request<RawBody>) -> Response<BoxBody<Bytes, BodyWriteAborted>> {
-            unimplemented!()
-        }
-    }
-
     fn endpoint(
         method: Method,
         path: Vec<PathSegment>,
         name: &'static str,
     ) -> Box<dyn WitchcraftEndpoint + Sync + Send> {
-        Box::new(TestEndpoint { method, path, name })
+        Box::new(TestEndpoint {
+            method,
+            path,
+            template: "",
+            service_name: "",
+            name,
+        })
     }
 
     #[tokio::test]
@@ -543,4 +550,67 @@ mod test {
             _ => panic!("bad route"),
         }
     }
+
+    #[test]
+    fn shadowing() {
+        let param = |name: &'static str, regex: Option<&'static str>| PathSegment::Parameter {
+            name: Cow::Borrowed(name),
+            regex: regex.map(Cow::Borrowed),
+        };
+        let endpoints = vec![
+            endpoint(
+                Method::GET,
+                vec![
+                    PathSegment::Literal(Cow::Borrowed("foo")),
+                    PathSegment::Literal(Cow::Borrowed("bar")),
+                ],
+                "a",
+            ),
+            endpoint(
+                Method::GET,
+                vec![
+                    PathSegment::Literal(Cow::Borrowed("foo")),
+                    param("arg", None),
+                ],
+                "b",
+            ),
+            endpoint(
+                Method::GET,
+                vec![
+                    PathSegment::Literal(Cow::Borrowed("foo")),
+                    param("other", None),
+                ],
+                "c",
+            ),
+            endpoint(
+                Method::GET,
+                vec![
+                    PathSegment::Literal(Cow::Borrowed("foo")),
+                    param("id", Some("[0-9]+")),
+                ],
+                "d",
+            ),
+            endpoint(
+                Method::GET,
+                vec![
+                    PathSegment::Literal(Cow::Borrowed("foo")),
+                    param("arg", None),
+                    PathSegment::Literal(Cow::Borrowed("baz")),
+                ],
+                "e",
+            ),
+        ];
+
+        let routes = Routes::new(endpoints.into_iter().map(Endpoint::new).collect());
+        let shadowed = shadowed(&routes.endpoints)
+            .into_iter()
+            .map(|(shadowed, by)| {
+                (
+                    routes.endpoints[shadowed].endpoint.name(),
+                    routes.endpoints[by].endpoint.name(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(shadowed, vec![("c", "b")]);
+    }
 }
@@ -11,6 +11,7 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use crate::logging::DeferredSpan;
 use crate::service::{Layer, Service};
 use http::{Request, Response};
 use http_body::{Body, Frame};
@@ -48,11 +49,10 @@ where
         let req =
             req.map(|inner| SpannedBody::new(inner, "witchcraft: read-request-body", body_context));
 
-        let response = zipkin::next_span()
-            .with_name("witchcraft: handle")
-            .detach()
-            .bind(self.inner.call(req))
-            .await;
+        let span = zipkin::next_span().with_name("witchcraft: handle").detach();
+        let deferred = DeferredSpan::new(span.context(), "witchcraft: handle");
+        let response = span.bind(self.inner.call(req)).await;
+        drop(deferred);
 
         response
             .map(|inner| SpannedBody::new(inner, "witchcraft: write-response-body", body_context))
@@ -65,7 +65,10 @@ enum LazySpan {
         name: &'static str,
         context: Option<TraceContext>,
     },
-    Live(OpenSpan<Detached>),
+    Live {
+        span: OpenSpan<Detached>,
+        _deferred: Option<DeferredSpan>,
+    },
 }
 
 impl LazySpan {
@@ -78,9 +81,13 @@ impl LazySpan {
                         None => zipkin::new_trace(),
                     };
                     let span = span.with_name(name).detach();
-                    *self = LazySpan::Live(span);
+                    let deferred = DeferredSpan::new(span.context(), name);
+                    *self = LazySpan::Live {
+                        span,
+                        _deferred: deferred,
+                    };
                 }
-                LazySpan::Live(span) => return span,
+                LazySpan::Live { span, .. } => return span,
             }
         }
     }
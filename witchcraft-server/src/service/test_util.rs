@@ -11,10 +11,21 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use crate::endpoint::WitchcraftEndpoint;
+use crate::health::endpoint_500s::EndpointHealth;
+use crate::server::RawBody;
+use crate::service::endpoint_metrics::EndpointMetrics;
+use crate::service::handler::BodyWriteAborted;
 use crate::service::Service;
+use async_trait::async_trait;
+use bytes::Bytes;
+use conjure_http::server::{EndpointMetadata, PathSegment};
+use http::{Method, Request, Response};
+use http_body_util::combinators::BoxBody;
 use std::cell::RefCell;
 use std::future::Future;
 use std::mem;
+use std::sync::Arc;
 use zipkin::{Endpoint, Report, Sample, Span, TraceId};
 
 pub fn service_fn<F>(f: F) -> ServiceFn<F> {
@@ -36,6 +47,63 @@ where
     }
 }
 
+pub struct TestEndpoint {
+    pub method: Method,
+    pub path: Vec<PathSegment>,
+    pub template: &'static str,
+    pub service_name: &'static str,
+    pub name: &'static str,
+}
+
+impl EndpointMetadata for TestEndpoint {
+    fn method(&self) -> Method {
+        self.method.clone()
+    }
+
+    fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    fn template(&self) -> &str {
+        self.template
+    }
+
+    fn service_name(&self) -> &str {
+        self.service_name
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn deprecated(&self) -> Option<&str> {
+        None
+    }
+}
+
+#[async_trait]
+impl WitchcraftEndpoint for TestEndpoint {
+    fn metrics(&self) -> Option<&EndpointMetrics> {
+        None
+    }
+
+    fn health(&self) -> Option<&Arc<EndpointHealth>> {
+        None
+    }
+
+    fn blocking(&self) -> bool {
+        false
+    }
+
+    fn path_prefix(&self) -> &str {
+        ""
+    }
+
+    async fn handle(&self, _: Request<RawBody>) -> Response<BoxBody<Bytes, BodyWriteAborted>> {
+        unimplemented!()
+    }
+}
+
 thread_local! {
     static SPANS: RefCell<Vec<Span>> = const { RefCell::new(vec![]) };
 }
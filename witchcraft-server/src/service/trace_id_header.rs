@@ -11,44 +11,80 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use crate::service::trace_propagation::{self, TraceState, TRACEPARENT, TRACESTATE};
 use crate::service::{Layer, Service};
 use http::header::HeaderName;
-use http::{HeaderValue, Response};
+use http::{HeaderValue, Request, Response};
+use witchcraft_server_config::install::{InstallConfig, TracePropagationFormat};
 
 #[allow(clippy::declare_interior_mutable_const)]
 const TRACE_ID: HeaderName = HeaderName::from_static("x-b3-traceid");
 
 /// A layer which adds an `X-B3-TraceId` header to responses.
 ///
+/// If W3C Trace Context propagation is enabled, `traceparent` and `tracestate` headers are also added, identifying the
+/// server's span and echoing the request's trace state.
+///
 /// It must be installed after trace propagation.
-pub struct TraceIdHeaderLayer;
+pub struct TraceIdHeaderLayer {
+    w3c: bool,
+}
+
+impl TraceIdHeaderLayer {
+    pub fn new(config: &InstallConfig) -> Self {
+        TraceIdHeaderLayer {
+            w3c: config
+                .server()
+                .trace_propagation()
+                .contains(&TracePropagationFormat::W3c),
+        }
+    }
+}
 
 impl<S> Layer<S> for TraceIdHeaderLayer {
     type Service = TraceIdHeaderService<S>;
 
     fn layer(self, inner: S) -> Self::Service {
-        TraceIdHeaderService { inner }
+        TraceIdHeaderService {
+            inner,
+            w3c: self.w3c,
+        }
     }
 }
 
 pub struct TraceIdHeaderService<S> {
     inner: S,
+    w3c: bool,
 }
 
-impl<S, R, B> Service<R> for TraceIdHeaderService<S>
+impl<S, B1, B2> Service<Request<B1>> for TraceIdHeaderService<S>
 where
-    S: Service<R, Response = Response<B>> + Sync,
-    R: Send,
+    S: Service<Request<B1>, Response = Response<B2>> + Sync,
+    B1: Send,
 {
     type Response = S::Response;
 
-    async fn call(&self, req: R) -> Self::Response {
+    async fn call(&self, req: Request<B1>) -> Self::Response {
+        let trace_state = if self.w3c {
+            req.extensions().get::<TraceState>().cloned()
+        } else {
+            None
+        };
+
         let mut response = self.inner.call(req).await;
         let context = zipkin::current().expect("zipkin trace not initialized");
         response.headers_mut().insert(
             TRACE_ID,
             HeaderValue::from_str(&context.trace_id().to_string()).unwrap(),
         );
+        if self.w3c {
+            response
+                .headers_mut()
+                .insert(TRACEPARENT, trace_propagation::traceparent(context));
+            if let Some(TraceState(trace_state)) = trace_state {
+                response.headers_mut().insert(TRACESTATE, trace_state);
+            }
+        }
         response
     }
 }
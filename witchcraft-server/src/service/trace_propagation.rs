@@ -11,35 +11,97 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use crate::logging::{self, DeferredSpan, RequestSampling};
 use crate::service::request_id::RequestId;
 use crate::service::routing::Route;
 use crate::service::{Layer, Service};
 use futures_util::ready;
-use http::header::USER_AGENT;
-use http::{Request, Response};
+use http::header::{HeaderName, USER_AGENT};
+use http::{HeaderMap, HeaderValue, Request, Response};
 use http_body::{Body, Frame};
 use pin_project::pin_project;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use zipkin::{Detached, Kind, OpenSpan};
+use witchcraft_server_config::install::{InstallConfig, TracePropagationFormat};
+use zipkin::trace_context;
+use zipkin::{Detached, Kind, OpenSpan, SamplingFlags, SpanId, TraceContext, TraceId};
 
-/// A layer which extracts Zipkin tracing information from a request and creates a top-level span which wraps the inner
+#[allow(clippy::declare_interior_mutable_const)]
+pub const TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
+#[allow(clippy::declare_interior_mutable_const)]
+pub const TRACESTATE: HeaderName = HeaderName::from_static("tracestate");
+
+const SAMPLED_FLAG: u8 = 0x01;
+
+type SampleFn = fn(SamplingFlags, Option<&str>, Option<&str>) -> RequestSampling;
+
+/// A layer which extracts tracing information from a request and creates a top-level span which wraps the inner
 /// service.
 ///
+/// Both W3C Trace Context and Zipkin B3 headers are supported, with W3C preferred if both are present. The set of
+/// accepted formats is controlled by the `server.trace-propagation` field in the install config.
+///
 /// It must be installed after routing and request ID generation.
-pub struct TracePropagationLayer;
+pub struct TracePropagationLayer {
+    w3c: bool,
+    b3: bool,
+    sample: SampleFn,
+}
+
+impl TracePropagationLayer {
+    pub fn new(config: &InstallConfig) -> Self {
+        let formats = config.server().trace_propagation();
+        TracePropagationLayer {
+            w3c: formats.contains(&TracePropagationFormat::W3c),
+            b3: formats.contains(&TracePropagationFormat::B3),
+            sample: logging::sample_request,
+        }
+    }
+}
 
 impl<S> Layer<S> for TracePropagationLayer {
     type Service = TracePropagationService<S>;
 
     fn layer(self, inner: S) -> Self::Service {
-        TracePropagationService { inner }
+        TracePropagationService {
+            inner,
+            w3c: self.w3c,
+            b3: self.b3,
+            sample: self.sample,
+        }
     }
 }
 
 pub struct TracePropagationService<S> {
     inner: S,
+    w3c: bool,
+    b3: bool,
+    sample: SampleFn,
+}
+
+impl<S> TracePropagationService<S> {
+    fn trace_context(&self, headers: &HeaderMap) -> Option<TraceContext> {
+        if self.w3c {
+            if let Some(context) = get_w3c_trace_context(headers) {
+                return Some(context);
+            }
+        }
+
+        if self.b3 {
+            return http_zipkin::get_trace_context(headers);
+        }
+
+        None
+    }
+
+    fn sampling_flags(&self, headers: &HeaderMap) -> SamplingFlags {
+        if self.b3 {
+            http_zipkin::get_sampling_flags(headers)
+        } else {
+            SamplingFlags::default()
+        }
+    }
 }
 
 impl<S, B1, B2> Service<Request<B1>> for TracePropagationService<S>
@@ -49,30 +111,44 @@ where
 {
     type Response = Response<TracePropagationBody<B2>>;
 
-    async fn call(&self, req: Request<B1>) -> Self::Response {
+    async fn call(&self, mut req: Request<B1>) -> Self::Response {
         let route = req
             .extensions()
             .get::<Route>()
             .expect("Route missing from request extensions");
 
-        let mut span = match http_zipkin::get_trace_context(req.headers()) {
-            Some(context) => zipkin::new_child(context).detach(),
-            None => {
-                let flags = http_zipkin::get_sampling_flags(req.headers());
-                zipkin::new_trace_from(flags).detach()
-            }
+        let endpoint = match route {
+            Route::Resolved(endpoint) => Some(endpoint),
+            _ => None,
         };
 
-        let template = match route {
-            Route::Resolved(endpoint) => Some(endpoint.template()),
-            _ => None,
+        let context = self.trace_context(req.headers());
+        let flags = match &context {
+            Some(context) => context.sampling_flags(),
+            None => self.sampling_flags(req.headers()),
+        };
+        let sampling = (self.sample)(
+            flags,
+            endpoint.map(|e| e.service_name()),
+            endpoint.map(|e| e.name()),
+        );
+        let span = match context {
+            Some(context) => zipkin::new_child(
+                trace_context::Builder::from(context)
+                    .sampling_flags(sampling.flags)
+                    .build(),
+            )
+            .detach(),
+            None => zipkin::new_trace_from(sampling.flags).detach(),
         };
+        let mut span = ServerSpan::new(span, sampling.deferred);
+
+        let template = endpoint.map(|e| e.template());
         span.name(&format!(
             "witchcraft: {} {}",
             req.method(),
             template.unwrap_or("not_found")
         ));
-        span.kind(Kind::Server);
         span.tag("http.method", req.method().as_str());
         span.tag(
             "http.request_id",
@@ -90,6 +166,12 @@ where
         }
         span.tag("http.version", &format!("{:?}", req.version()));
 
+        if self.w3c {
+            if let Some(state) = get_trace_state(req.headers()) {
+                req.extensions_mut().insert(state);
+            }
+        }
+
         TracePropagationFuture {
             inner: self.inner.call(req),
             span: Some(span),
@@ -98,11 +180,151 @@ where
     }
 }
 
+/// The vendor-specific W3C `tracestate` of a request, which is echoed back in the response.
+#[derive(Clone)]
+pub struct TraceState(pub HeaderValue);
+
+/// Parses a trace context from the W3C `traceparent` header.
+///
+/// The sampled flag is only propagated when set. An unset flag just means the caller didn't record its span, so we
+/// make our own sampling decision in that case.
+fn get_w3c_trace_context(headers: &HeaderMap) -> Option<TraceContext> {
+    let mut values = headers.get_all(TRACEPARENT).iter();
+    let value = values.next()?;
+    if values.next().is_some() {
+        return None;
+    }
+
+    let value = value.to_str().ok()?;
+    let mut parts = value.split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let span_id = parts.next()?;
+    let flags = parts.next()?;
+
+    if version.len() != 2 || !is_lower_hex(version) || version == "ff" {
+        return None;
+    }
+    let version = u8::from_str_radix(version, 16).ok()?;
+    // future versions may append fields, but version 00 must contain exactly four
+    if version == 0 && parts.next().is_some() {
+        return None;
+    }
+
+    if trace_id.len() != 32
+        || !is_lower_hex(trace_id)
+        || span_id.len() != 16
+        || !is_lower_hex(span_id)
+    {
+        return None;
+    }
+    let trace_id = trace_id.parse::<TraceId>().ok()?;
+    let span_id = span_id.parse::<SpanId>().ok()?;
+    if trace_id.bytes().iter().all(|b| *b == 0) || span_id.bytes().iter().all(|b| *b == 0) {
+        return None;
+    }
+    // 8 byte zipkin trace IDs are zero-padded in traceparent, so convert them back
+    let trace_id = match trace_id.bytes().split_at(8) {
+        (high, low) if high.iter().all(|b| *b == 0) => {
+            TraceId::from(<[u8; 8]>::try_from(low).unwrap())
+        }
+        _ => trace_id,
+    };
+
+    if flags.len() != 2 || !is_lower_hex(flags) {
+        return None;
+    }
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+
+    let mut context = TraceContext::builder();
+    context.trace_id(trace_id).span_id(span_id);
+    if flags & SAMPLED_FLAG != 0 {
+        context.sampled(true);
+    }
+
+    Some(context.build())
+}
+
+fn is_lower_hex(s: &str) -> bool {
+    s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn get_trace_state(headers: &HeaderMap) -> Option<TraceState> {
+    let mut values = headers.get_all(TRACESTATE).iter();
+    let first = values.next()?;
+
+    let mut combined = first.as_bytes().to_vec();
+    for value in values {
+        combined.push(b',');
+        combined.extend_from_slice(value.as_bytes());
+    }
+
+    HeaderValue::from_bytes(&combined).ok().map(TraceState)
+}
+
+/// Formats a trace context as a W3C `traceparent` header.
+pub fn traceparent(context: TraceContext) -> HeaderValue {
+    // W3C requires 16 byte trace IDs, but zipkin allows 8 byte IDs
+    let flags = if context.sampled() == Some(true) {
+        SAMPLED_FLAG
+    } else {
+        0
+    };
+    let value = format!(
+        "00-{:0>32}-{}-{:02x}",
+        context.trace_id().to_string(),
+        context.span_id(),
+        flags,
+    );
+    HeaderValue::from_str(&value).unwrap()
+}
+
+/// The server span of a request.
+///
+/// If the request's sampling decision was deferred, its zipkin span is unsampled so the deferral isn't propagated to
+/// other services. The server's spans of the request's trace are instead recorded separately, and reported once the
+/// request completes if it qualifies for tail sampling.
+struct ServerSpan {
+    span: OpenSpan<Detached>,
+    deferred: Option<DeferredSpan>,
+}
+
+impl ServerSpan {
+    fn new(mut span: OpenSpan<Detached>, deferred: bool) -> Self {
+        span.kind(Kind::Server);
+        let deferred = if deferred {
+            Some(DeferredSpan::server(span.context()))
+        } else {
+            None
+        };
+
+        ServerSpan { span, deferred }
+    }
+
+    fn context(&self) -> TraceContext {
+        self.span.context()
+    }
+
+    fn name(&mut self, name: &str) {
+        self.span.name(name);
+        if let Some(deferred) = &mut self.deferred {
+            deferred.name(name);
+        }
+    }
+
+    fn tag(&mut self, key: &str, value: &str) {
+        self.span.tag(key, value);
+        if let Some(deferred) = &mut self.deferred {
+            deferred.tag(key, value);
+        }
+    }
+}
+
 #[pin_project]
 pub struct TracePropagationFuture<F> {
     #[pin]
     inner: F,
-    span: Option<OpenSpan<Detached>>,
+    span: Option<ServerSpan>,
 }
 
 impl<F, B> Future for TracePropagationFuture<F>
@@ -128,7 +350,7 @@ where
 pub struct TracePropagationBody<B> {
     #[pin]
     inner: B,
-    span: OpenSpan<Detached>,
+    span: ServerSpan,
 }
 
 impl<B> Body for TracePropagationBody<B>
@@ -161,35 +383,210 @@ where
 mod test {
     use super::*;
     use crate::service::test_util::{self, service_fn};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
 
-    #[tokio::test]
-    async fn propagated() {
-        test_util::setup_tracer();
-
-        let service = TracePropagationLayer.layer(service_fn(|_| async {
+    fn service(
+        w3c: bool,
+        b3: bool,
+    ) -> impl Service<Request<()>, Response = Response<TracePropagationBody<()>>> {
+        TracePropagationLayer {
+            w3c,
+            b3,
+            sample: logging::sample_request,
+        }
+        .layer(service_fn(|_| async {
             Response::builder().status(204).body(()).unwrap()
-        }));
+        }))
+    }
 
-        service
-            .call(
-                Request::builder()
-                    .method("POST")
-                    .header("x-b3-traceid", "0011223344556677")
-                    .header("x-b3-spanid", "7766554433221100")
-                    .header("x-b3-sampled", "1")
-                    .extension(Route::Unresolved)
-                    .extension(RequestId::random())
-                    .body(())
-                    .unwrap(),
-            )
-            .await;
+    async fn call(service: &impl Service<Request<()>>, headers: &[(&str, &str)]) -> zipkin::Span {
+        test_util::setup_tracer();
+
+        let mut request = Request::builder()
+            .method("POST")
+            .extension(Route::Unresolved)
+            .extension(RequestId::random());
+        for (key, value) in headers {
+            request = request.header(*key, *value);
+        }
+        service.call(request.body(()).unwrap()).await;
 
-        let spans = test_util::spans();
+        let mut spans = test_util::spans();
         assert_eq!(spans.len(), 1);
-        let span = &spans[0];
+        spans.pop().unwrap()
+    }
+
+    #[tokio::test]
+    async fn propagated() {
+        let span = call(
+            &service(true, true),
+            &[
+                ("x-b3-traceid", "0011223344556677"),
+                ("x-b3-spanid", "7766554433221100"),
+                ("x-b3-sampled", "1"),
+            ],
+        )
+        .await;
 
         assert_eq!(span.trace_id(), "0011223344556677".parse().unwrap());
         assert_eq!(span.parent_id(), Some("7766554433221100".parse().unwrap()));
         assert_eq!(span.name(), Some("witchcraft: post not_found"));
     }
+
+    #[tokio::test]
+    async fn w3c_propagated() {
+        let span = call(
+            &service(true, true),
+            &[(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            )],
+        )
+        .await;
+
+        assert_eq!(
+            span.trace_id(),
+            "0af7651916cd43dd8448eb211c80319c".parse().unwrap()
+        );
+        assert_eq!(span.parent_id(), Some("b7ad6b7169203331".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn w3c_preferred() {
+        let span = call(
+            &service(true, true),
+            &[
+                (
+                    "traceparent",
+                    "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+                ),
+                ("x-b3-traceid", "0011223344556677"),
+                ("x-b3-spanid", "7766554433221100"),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            span.trace_id(),
+            "0af7651916cd43dd8448eb211c80319c".parse().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn disabled_formats_ignored() {
+        let headers = [
+            (
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            ),
+            ("x-b3-traceid", "0011223344556677"),
+            ("x-b3-spanid", "7766554433221100"),
+        ];
+
+        let span = call(&service(false, true), &headers).await;
+        assert_eq!(span.trace_id(), "0011223344556677".parse().unwrap());
+
+        let span = call(&service(false, false), &headers).await;
+        assert_eq!(span.parent_id(), None);
+    }
+
+    #[tokio::test]
+    async fn deferred_not_propagated() {
+        test_util::setup_tracer();
+
+        let outgoing = Arc::new(Mutex::new(HeaderMap::new()));
+        let service = TracePropagationLayer {
+            w3c: true,
+            b3: true,
+            sample: |_, _, _| RequestSampling {
+                flags: SamplingFlags::builder().sampled(false).build(),
+                deferred: true,
+            },
+        }
+        .layer(service_fn({
+            let outgoing = outgoing.clone();
+            move |_| {
+                let context = zipkin::current().unwrap();
+                let mut headers = outgoing.lock();
+                http_zipkin::set_trace_context(context, &mut headers);
+                headers.insert(TRACEPARENT, traceparent(context));
+                async { Response::builder().status(204).body(()).unwrap() }
+            }
+        }));
+
+        let request = Request::builder()
+            .method("POST")
+            .extension(Route::Unresolved)
+            .extension(RequestId::random())
+            .header("x-b3-traceid", "0011223344556677")
+            .header("x-b3-spanid", "7766554433221100")
+            .body(())
+            .unwrap();
+        service.call(request).await;
+
+        let outgoing = outgoing.lock();
+        assert_eq!(outgoing["x-b3-traceid"], "0011223344556677");
+        assert_eq!(outgoing["x-b3-sampled"], "0");
+        assert!(outgoing[TRACEPARENT].to_str().unwrap().ends_with("-00"));
+        assert!(test_util::spans().is_empty());
+    }
+
+    #[test]
+    fn invalid_traceparent() {
+        for value in [
+            "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-extra",
+            "00-00000000000000000000000000000000-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-0000000000000000-01",
+            "00-0AF7651916CD43DD8448EB211C80319C-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331",
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert(TRACEPARENT, HeaderValue::from_static(value));
+            assert!(get_w3c_trace_context(&headers).is_none(), "{value}");
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            TRACEPARENT,
+            HeaderValue::from_static(
+                "01-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00-extra",
+            ),
+        );
+        let context = get_w3c_trace_context(&headers).unwrap();
+        assert_eq!(context.sampled(), None);
+    }
+
+    #[test]
+    fn traceparent_round_trip() {
+        for (trace_id, expected) in [
+            (
+                "0011223344556677",
+                "00-00000000000000000011223344556677-7766554433221100-01",
+            ),
+            (
+                "0af7651916cd43dd8448eb211c80319c",
+                "00-0af7651916cd43dd8448eb211c80319c-7766554433221100-01",
+            ),
+        ] {
+            let context = TraceContext::builder()
+                .trace_id(trace_id.parse().unwrap())
+                .span_id("7766554433221100".parse().unwrap())
+                .sampled(true)
+                .build();
+
+            let value = traceparent(context);
+            assert_eq!(value, expected);
+
+            let mut headers = HeaderMap::new();
+            headers.insert(TRACEPARENT, value);
+            let parsed = get_w3c_trace_context(&headers).unwrap();
+            assert_eq!(parsed.trace_id(), context.trace_id());
+            assert_eq!(parsed.trace_id().bytes().len(), trace_id.len() / 2);
+            assert_eq!(parsed.span_id(), context.span_id());
+            assert_eq!(parsed.sampled(), Some(true));
+        }
+    }
 }
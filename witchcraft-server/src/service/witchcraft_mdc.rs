@@ -12,8 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::logging;
+use crate::logging::{self, OverrideRequest};
 use crate::service::request_id::RequestId;
+use crate::service::routing::Route;
 use crate::service::unverified_jwt::UnverifiedJwt;
 use crate::service::{Layer, Service};
 use http::Request;
@@ -21,7 +22,7 @@ use witchcraft_log::mdc;
 
 /// A layer which injects Witchcraft-managed request state into the MDC.
 ///
-/// It must be installed after MDC tracking, JWT extraction, request ID generation, and trace propagation.
+/// It must be installed after routing, MDC tracking, JWT extraction, request ID generation, and trace propagation.
 pub struct WitchcraftMdcLayer;
 
 impl<S> Layer<S> for WitchcraftMdcLayer {
@@ -44,7 +45,8 @@ where
     type Response = S::Response;
 
     async fn call(&self, req: Request<B>) -> Self::Response {
-        if let Some(jwt) = req.extensions().get::<UnverifiedJwt>() {
+        let jwt = req.extensions().get::<UnverifiedJwt>();
+        if let Some(jwt) = jwt {
             mdc::insert_safe(logging::mdc::UID_KEY, jwt.unverified_user_id());
             if let Some(session_id) = jwt.unverified_session_id() {
                 mdc::insert_safe(logging::mdc::SID_KEY, session_id);
@@ -58,11 +60,25 @@ where
         }
 
         let context = zipkin::current().expect("zipkin trace not initialized");
-        mdc::insert_safe(logging::mdc::TRACE_ID_KEY, context.trace_id().to_string());
+        let trace_id = context.trace_id().to_string();
+        mdc::insert_safe(logging::mdc::TRACE_ID_KEY, &trace_id);
         if let Some(sampled) = context.sampled() {
             mdc::insert_safe(logging::SAMPLED_KEY, sampled);
         }
 
+        let user_id = jwt.map(|jwt| jwt.unverified_user_id().to_string());
+        let endpoint = match req.extensions().get::<Route>() {
+            Some(Route::Resolved(endpoint)) => Some(endpoint.name()),
+            _ => None,
+        };
+        let level = logging::level_override(&OverrideRequest {
+            trace_id: &trace_id,
+            user_id: user_id.as_deref(),
+            endpoint,
+            headers: req.headers(),
+        });
+        logging::set_request_level(level);
+
         let request_id = req
             .extensions()
             .get::<RequestId>()
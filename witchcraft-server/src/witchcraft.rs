@@ -16,9 +16,11 @@ use crate::blocking::pool::ThreadPool;
 use crate::debug::DiagnosticRegistry;
 use crate::endpoint::conjure::ConjureEndpoint;
 use crate::endpoint::extended_path::ExtendedPathEndpoint;
+use crate::endpoint::inventory::EndpointInventory;
 use crate::endpoint::WitchcraftEndpoint;
 use crate::health::HealthCheckRegistry;
 use crate::readiness::ReadinessCheckRegistry;
+use crate::service::active_requests::ActiveRequests;
 use crate::shutdown_hooks::ShutdownHooks;
 use crate::{blocking, RequestBody, ResponseWriter};
 use conjure_http::server::{AsyncService, BoxAsyncEndpoint, ConjureRuntime, Endpoint, Service};
@@ -35,6 +37,8 @@ pub struct Witchcraft {
     pub(crate) health_checks: Arc<HealthCheckRegistry>,
     pub(crate) readiness_checks: Arc<ReadinessCheckRegistry>,
     pub(crate) diagnostics: Arc<DiagnosticRegistry>,
+    pub(crate) active_requests: Arc<ActiveRequests>,
+    pub(crate) endpoint_inventory: Arc<EndpointInventory>,
     pub(crate) client_factory: ClientFactory,
     pub(crate) handle: Handle,
     pub(crate) install_config: InstallConfig,